@@ -0,0 +1,232 @@
+//! Throughput of primitive reads and writes
+//!
+//! Compares the current Reader and Writer methods against the original implementation,
+//! which allocated a Vec for every value, on an in-memory `Cursor<Vec<u8>>` and on a
+//! `BufReader<File>`. Run with `cargo bench`.
+
+extern crate io_operations;
+
+use io_operations::reader::Reader;
+use io_operations::writer::Writer;
+
+use std::env;
+use std::fs::{self, File};
+use std::hint::black_box;
+use std::io::{BufReader, Cursor, Seek, SeekFrom};
+use std::time::{Duration, Instant};
+
+/// The number of values each benchmark reads or writes per run
+const VALUES: usize = 1 << 20;
+
+/// The number of timed runs; the fastest one is reported
+const RUNS: usize = 5;
+
+/// The original Vec-allocating implementation, kept for comparison
+#[allow(clippy::useless_vec, clippy::io_other_error)]
+mod original {
+
+    use std::io::{Error, ErrorKind, Read, Result, Write};
+
+    pub fn read_be_u32<R: Read>(reader: &mut R) -> Result<u32> {
+
+        let mut raw_buffer = vec![0u8; 4];
+
+        match reader.read(&mut raw_buffer) {
+            Ok(4) => Ok(
+                ((raw_buffer[0] as u32) << 24) |
+                ((raw_buffer[1] as u32) << 16) |
+                ((raw_buffer[2] as u32) << 8) |
+                raw_buffer[3] as u32
+            ),
+            Ok(_) => Err(Error::new(ErrorKind::Other, "Could not read four bytes (end of stream?)")),
+            Err(error) => Err(error)
+        }
+
+    }
+
+    pub fn read_le_u64<R: Read>(reader: &mut R) -> Result<u64> {
+
+        let mut raw_buffer = vec![0u8; 8];
+
+        match reader.read(&mut raw_buffer) {
+            Ok(8) => Ok(
+                raw_buffer[0] as u64 |
+                ((raw_buffer[1] as u64) << 8) |
+                ((raw_buffer[2] as u64) << 16) |
+                ((raw_buffer[3] as u64) << 24) |
+                ((raw_buffer[4] as u64) << 32) |
+                ((raw_buffer[5] as u64) << 40) |
+                ((raw_buffer[6] as u64) << 48) |
+                ((raw_buffer[7] as u64) << 56)
+            ),
+            Ok(_) => Err(Error::new(ErrorKind::Other, "Could not read eight bytes (end of stream?)")),
+            Err(error) => Err(error)
+        }
+
+    }
+
+    pub fn write_be_u32<W: Write>(writer: &mut W, value: u32) -> Result<()> {
+
+        let raw_buffer = vec![
+            (value >> 24) as u8,
+            (value >> 16) as u8,
+            (value >> 8) as u8,
+            value as u8
+        ];
+
+        writer.write_all(&raw_buffer[..])
+
+    }
+
+    pub fn write_le_u64<W: Write>(writer: &mut W, value: u64) -> Result<()> {
+
+        let raw_buffer = vec![
+            value as u8,
+            (value >> 8) as u8,
+            (value >> 16) as u8,
+            (value >> 24) as u8,
+            (value >> 32) as u8,
+            (value >> 40) as u8,
+            (value >> 48) as u8,
+            (value >> 56) as u8
+        ];
+
+        writer.write_all(&raw_buffer[..])
+
+    }
+
+}
+
+/// Runs `run` once to warm up, then reports the fastest of RUNS timed runs
+fn measure<F: FnMut()>(name: &str, bytes: usize, mut run: F) {
+
+    run();
+
+    let mut fastest = Duration::from_secs(u64::MAX);
+
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        run();
+        fastest = fastest.min(start.elapsed());
+    }
+
+    let seconds = fastest.as_secs_f64();
+
+    println!(
+        "{:<40} {:>9.2} ms {:>9.1} MiB/s {:>7.2} ns/value",
+        name,
+        seconds * 1e3,
+        bytes as f64 / seconds / (1024.0 * 1024.0),
+        seconds * 1e9 / VALUES as f64
+    );
+
+}
+
+fn bench_cursor() {
+
+    let mut cursor = Cursor::new(vec![0xA5u8; VALUES * 8]);
+
+    measure("cursor read_be_u32 (original)", VALUES * 4, || {
+        cursor.set_position(0);
+        for _ in 0..VALUES {
+            black_box(original::read_be_u32(&mut cursor).unwrap());
+        }
+    });
+
+    measure("cursor read_be_u32", VALUES * 4, || {
+        cursor.set_position(0);
+        for _ in 0..VALUES {
+            black_box(cursor.read_be_u32().unwrap());
+        }
+    });
+
+    measure("cursor read_le_u64 (original)", VALUES * 8, || {
+        cursor.set_position(0);
+        for _ in 0..VALUES {
+            black_box(original::read_le_u64(&mut cursor).unwrap());
+        }
+    });
+
+    measure("cursor read_le_u64", VALUES * 8, || {
+        cursor.set_position(0);
+        for _ in 0..VALUES {
+            black_box(cursor.read_le_u64().unwrap());
+        }
+    });
+
+    measure("cursor write_be_u32 (original)", VALUES * 4, || {
+        cursor.set_position(0);
+        for value in 0..VALUES as u32 {
+            original::write_be_u32(&mut cursor, black_box(value)).unwrap();
+        }
+    });
+
+    measure("cursor write_be_u32", VALUES * 4, || {
+        cursor.set_position(0);
+        for value in 0..VALUES as u32 {
+            cursor.write_be_u32(black_box(value)).unwrap();
+        }
+    });
+
+    measure("cursor write_le_u64 (original)", VALUES * 8, || {
+        cursor.set_position(0);
+        for value in 0..VALUES as u64 {
+            original::write_le_u64(&mut cursor, black_box(value)).unwrap();
+        }
+    });
+
+    measure("cursor write_le_u64", VALUES * 8, || {
+        cursor.set_position(0);
+        for value in 0..VALUES as u64 {
+            cursor.write_le_u64(black_box(value)).unwrap();
+        }
+    });
+
+}
+
+fn bench_buf_reader() {
+
+    let path = env::temp_dir().join(format!("io_operations_bench_{}.bin", std::process::id()));
+
+    fs::write(&path, vec![0xA5u8; VALUES * 8]).unwrap();
+
+    let mut reader = BufReader::new(File::open(&path).unwrap());
+
+    measure("BufReader<File> read_be_u32 (original)", VALUES * 4, || {
+        reader.seek(SeekFrom::Start(0)).unwrap();
+        for _ in 0..VALUES {
+            black_box(original::read_be_u32(&mut reader).unwrap());
+        }
+    });
+
+    measure("BufReader<File> read_be_u32", VALUES * 4, || {
+        reader.seek(SeekFrom::Start(0)).unwrap();
+        for _ in 0..VALUES {
+            black_box(reader.read_be_u32().unwrap());
+        }
+    });
+
+    measure("BufReader<File> read_le_u64 (original)", VALUES * 8, || {
+        reader.seek(SeekFrom::Start(0)).unwrap();
+        for _ in 0..VALUES {
+            black_box(original::read_le_u64(&mut reader).unwrap());
+        }
+    });
+
+    measure("BufReader<File> read_le_u64", VALUES * 8, || {
+        reader.seek(SeekFrom::Start(0)).unwrap();
+        for _ in 0..VALUES {
+            black_box(reader.read_le_u64().unwrap());
+        }
+    });
+
+    fs::remove_file(&path).unwrap();
+
+}
+
+fn main() {
+
+    bench_cursor();
+    bench_buf_reader();
+
+}
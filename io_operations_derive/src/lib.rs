@@ -0,0 +1,538 @@
+//! Derive macros for io_operations' Readable and Writable traits
+//!
+//! Both derives are built from the same parsed description of a type, and every field
+//! encoding produces its read and its write together, so the two halves cannot drift
+//! apart. Fields are read and written in declaration order; by default each uses its
+//! own Readable and Writable implementations, which `#[io(...)]` attributes override:
+//!
+//! * `#[io(be)]` and `#[io(le)]` store a number from u16 to f64 in the given byte order
+//! * `#[io(varint)]` stores a u32, i32, u64 or i64 as a VarInt
+//! * `#[io(zigzag)]` stores an i32 or i64 as a ZigZag VarInt
+//! * `#[io(prefix = "be_u16", max = 256)]` gives a `String` or `Vec` another length prefix
+//!   (`u8`, `be_u16`, `le_u16`, `be_u32`, `le_u32` or `varint`) and optionally a limit
+//! * `#[io(fixed = 16)]` stores a `String` as exactly that many NUL-padded UTF-8 bytes,
+//!   or a `Vec` as exactly that many elements with no prefix
+//!
+//! Enums start with a tag that picks the variant, stored as a single byte unless the enum
+//! says otherwise with `#[io(tag = "varint")]`, using the same names as `prefix`. A
+//! variant's tag is its `#[io(tag = N)]`, else its explicit discriminant, else one more
+//! than the variant before it, counting from zero. Reading an unknown tag is an error.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Ident, Lit, LitInt, LitStr, Type};
+
+/// Derives `io_operations::Readable`
+#[proc_macro_derive(Readable, attributes(io))]
+pub fn derive_readable(input: TokenStream) -> TokenStream {
+
+    let input = parse_macro_input!(input as DeriveInput);
+
+    Container::parse(&input).map(|container| container.expand_read()).unwrap_or_else(|error| error.to_compile_error()).into()
+
+}
+
+/// Derives `io_operations::Writable`
+#[proc_macro_derive(Writable, attributes(io))]
+pub fn derive_writable(input: TokenStream) -> TokenStream {
+
+    let input = parse_macro_input!(input as DeriveInput);
+
+    Container::parse(&input).map(|container| container.expand_write()).unwrap_or_else(|error| error.to_compile_error()).into()
+
+}
+
+/// The names accepted by `prefix` and `tag`, their LengthPrefix variants and largest values
+const PREFIXES: [(&str, &str, u64); 6] = [
+    ("u8", "U8", u8::MAX as u64),
+    ("be_u16", "BeU16", u16::MAX as u64),
+    ("le_u16", "LeU16", u16::MAX as u64),
+    ("be_u32", "BeU32", u32::MAX as u64),
+    ("le_u32", "LeU32", u32::MAX as u64),
+    ("varint", "VarInt", u32::MAX as u64)
+];
+
+/// A length prefix or enum tag type named in an attribute
+#[derive(Clone, Copy)]
+struct Prefix {
+
+    /// The LengthPrefix variant
+    variant: &'static str,
+
+    /// The largest value the prefix can hold
+    max: u64
+
+}
+
+impl Prefix {
+
+    /// Looks up a prefix by the name used in attributes
+    fn parse(name: &LitStr) -> syn::Result<Prefix> {
+        PREFIXES.iter()
+            .find(|(attribute, _, _)| name.value() == *attribute)
+            .map(|&(_, variant, max)| Prefix { variant, max })
+            .ok_or_else(|| syn::Error::new(name.span(), "expected one of \"u8\", \"be_u16\", \"le_u16\", \"be_u32\", \"le_u32\" or \"varint\""))
+    }
+
+    /// The LengthPrefix value for generated code
+    fn tokens(self) -> TokenStream2 {
+
+        let variant = Ident::new(self.variant, Span::call_site());
+
+        quote!(::io_operations::LengthPrefix::#variant)
+
+    }
+
+}
+
+/// How a single field is stored
+enum Encoding {
+
+    /// Through the field's own Readable and Writable implementations
+    Value,
+
+    /// As a number in the given byte order
+    Endian(&'static str),
+
+    /// As a VarInt
+    VarInt,
+
+    /// As a ZigZag VarInt
+    ZigZag,
+
+    /// As a String or Vec with the given length prefix and limit
+    Prefixed(Prefix, Option<Expr>),
+
+    /// As a String or Vec of a fixed size
+    Fixed(Expr)
+
+}
+
+impl Encoding {
+
+    /// Parses a field's `#[io(...)]` attributes
+    fn parse(attributes: &[Attribute]) -> syn::Result<Encoding> {
+
+        let mut encoding = None;
+        let mut max = None;
+
+        for attribute in attributes.iter().filter(|attribute| attribute.path().is_ident("io")) {
+            attribute.parse_nested_meta(|meta| {
+
+                let parsed = if meta.path.is_ident("be") {
+                    Encoding::Endian("Big")
+                } else if meta.path.is_ident("le") {
+                    Encoding::Endian("Little")
+                } else if meta.path.is_ident("varint") {
+                    Encoding::VarInt
+                } else if meta.path.is_ident("zigzag") {
+                    Encoding::ZigZag
+                } else if meta.path.is_ident("prefix") {
+                    Encoding::Prefixed(Prefix::parse(&meta.value()?.parse()?)?, None)
+                } else if meta.path.is_ident("fixed") {
+                    Encoding::Fixed(meta.value()?.parse()?)
+                } else if meta.path.is_ident("max") {
+                    max = Some(meta.value()?.parse()?);
+                    return Ok(());
+                } else {
+                    return Err(meta.error("unknown io attribute; expected be, le, varint, zigzag, prefix, max or fixed"));
+                };
+
+                if encoding.replace(parsed).is_some() {
+                    return Err(meta.error("a field can only have one encoding"));
+                }
+
+                Ok(())
+
+            })?;
+        }
+
+        match (encoding, max) {
+            (Some(Encoding::Prefixed(prefix, _)), max) => Ok(Encoding::Prefixed(prefix, max)),
+            (_, Some(max)) => Err(syn::Error::new_spanned(max, "max can only be used with prefix")),
+            (encoding, None) => Ok(encoding.unwrap_or(Encoding::Value))
+        }
+
+    }
+
+    /// Returns the expression reading a field of the given type and the statement writing `value`
+    fn codec(&self, ty: &Type, value: &TokenStream2) -> (TokenStream2, TokenStream2) {
+
+        let support = quote!(::io_operations::derive_support);
+
+        match self {
+            Encoding::Value => (
+                quote!(<#ty as ::io_operations::Readable>::read_from(__io_reader)?),
+                quote!(::io_operations::Writable::write_to(#value, __io_writer)?;)
+            ),
+            Encoding::Endian(endian) => {
+                let endian = Ident::new(endian, Span::call_site());
+                (
+                    quote!(<#ty as #support::EndianValue>::read_in(__io_reader, ::io_operations::Endian::#endian)?),
+                    quote!(#support::EndianValue::write_in(#value, __io_writer, ::io_operations::Endian::#endian)?;)
+                )
+            },
+            Encoding::VarInt => (
+                quote!(<#ty as #support::VarIntValue>::read_varint(__io_reader)?),
+                quote!(#support::VarIntValue::write_varint(#value, __io_writer)?;)
+            ),
+            Encoding::ZigZag => (
+                quote!(<#ty as #support::ZigZagValue>::read_zigzag(__io_reader)?),
+                quote!(#support::ZigZagValue::write_zigzag(#value, __io_writer)?;)
+            ),
+            Encoding::Prefixed(prefix, max) => {
+                let prefix = prefix.tokens();
+                let max = max.as_ref().map(|max| quote!(#max)).unwrap_or_else(|| quote!(::io_operations::value::DEFAULT_MAX_LENGTH));
+                (
+                    quote!(<#ty as #support::ReadPrefixed>::read_prefixed(__io_reader, #prefix, #max)?),
+                    quote!(#support::WritePrefixed::write_prefixed(#value, __io_writer, #prefix, #max)?;)
+                )
+            },
+            Encoding::Fixed(size) => (
+                quote!(<#ty as #support::ReadFixed>::read_fixed(__io_reader, #size)?),
+                quote!(#support::WriteFixed::write_fixed(#value, __io_writer, #size)?;)
+            )
+        }
+
+    }
+
+}
+
+/// A field of a struct or variant
+struct Field {
+
+    /// The field's name, or None in a tuple struct or variant
+    name: Option<Ident>,
+
+    /// The field's type
+    ty: Type,
+
+    /// How the field is stored
+    encoding: Encoding
+
+}
+
+/// The fields of a struct or variant, with their kind
+struct Shape {
+
+    /// The fields, in declaration order
+    fields: Vec<Field>,
+
+    /// Whether the fields are named, as opposed to a tuple or unit
+    named: bool,
+
+    /// Whether there are no fields and no brackets at all
+    unit: bool
+
+}
+
+impl Shape {
+
+    /// Parses the fields of a struct or variant
+    fn parse(fields: &Fields) -> syn::Result<Shape> {
+
+        let parsed = fields.iter().map(|field| Ok(Field {
+            name: field.ident.clone(),
+            ty: field.ty.clone(),
+            encoding: Encoding::parse(&field.attrs)?
+        })).collect::<syn::Result<Vec<Field>>>()?;
+
+        Ok(Shape {
+            fields: parsed,
+            named: matches!(fields, Fields::Named(_)),
+            unit: matches!(fields, Fields::Unit)
+        })
+
+    }
+
+    /// The names bound to each field when writing
+    fn bindings(&self) -> Vec<Ident> {
+        self.fields.iter().enumerate().map(|(index, field)| {
+            field.name.clone().unwrap_or_else(|| format_ident!("__io_field{}", index))
+        }).collect()
+    }
+
+    /// Constructs `path` from fields read in order
+    fn expand_read(&self, path: TokenStream2) -> TokenStream2 {
+
+        let reads = self.fields.iter().map(|field| field.encoding.codec(&field.ty, &quote!()).0);
+
+        if self.unit {
+            path
+        } else if self.named {
+            let names = self.fields.iter().map(|field| &field.name);
+            quote!(#path { #(#names: #reads),* })
+        } else {
+            quote!(#path(#(#reads),*))
+        }
+
+    }
+
+    /// A pattern destructuring `path` into the bindings
+    fn pattern(&self, path: TokenStream2) -> TokenStream2 {
+
+        let bindings = self.bindings();
+
+        if self.unit {
+            path
+        } else if self.named {
+            quote!(#path { #(#bindings),* })
+        } else {
+            quote!(#path(#(#bindings),*))
+        }
+
+    }
+
+    /// Writes the bound fields in order
+    fn expand_write(&self) -> TokenStream2 {
+        self.fields.iter().zip(self.bindings()).map(|(field, binding)| field.encoding.codec(&field.ty, &quote!(#binding)).1).collect()
+    }
+
+}
+
+/// A variant of an enum
+struct Variant {
+
+    /// The variant's name
+    name: Ident,
+
+    /// The variant's tag
+    tag: u64,
+
+    /// The variant's fields
+    shape: Shape
+
+}
+
+/// What is being derived for
+enum Body {
+
+    /// A struct
+    Struct(Shape),
+
+    /// An enum, its tag type and its variants
+    Enum(Prefix, Vec<Variant>)
+
+}
+
+/// A type the derives are applied to
+struct Container {
+
+    /// The type's name
+    name: Ident,
+
+    /// The type's generics
+    generics: syn::Generics,
+
+    /// The type's fields or variants
+    body: Body
+
+}
+
+impl Container {
+
+    /// Parses a type and all of its attributes
+    fn parse(input: &DeriveInput) -> syn::Result<Container> {
+
+        let tag_type = parse_tag_type(&input.attrs)?;
+
+        let body = match &input.data {
+            Data::Struct(data) => {
+
+                if let Some((span, _)) = tag_type {
+                    return Err(syn::Error::new(span, "tag can only be used on enums"));
+                }
+
+                Body::Struct(Shape::parse(&data.fields)?)
+
+            },
+            Data::Enum(data) => {
+
+                let prefix = tag_type.map(|(_, prefix)| prefix).unwrap_or(Prefix { variant: "U8", max: u8::MAX as u64 });
+                let mut variants: Vec<Variant> = Vec::new();
+                let mut next = 0;
+
+                for variant in &data.variants {
+
+                    let (span, tag) = match (parse_variant_tag(&variant.attrs)?, &variant.discriminant) {
+                        (Some(tag), _) => (tag.span(), tag.base10_parse::<u64>()?),
+                        (None, Some((_, Expr::Lit(ExprLit { lit: Lit::Int(tag), .. })))) => (tag.span(), tag.base10_parse::<u64>()?),
+                        (None, Some((_, discriminant))) => {
+                            return Err(syn::Error::new_spanned(discriminant, "discriminant must be an integer literal; use #[io(tag = N)] instead"));
+                        },
+                        (None, None) => (variant.ident.span(), next)
+                    };
+
+                    if tag > prefix.max {
+                        return Err(syn::Error::new(span, format!("tag {} does not fit in the enum's tag type", tag)));
+                    }
+
+                    if let Some(previous) = variants.iter().find(|previous| previous.tag == tag) {
+                        return Err(syn::Error::new(span, format!("tag {} is already used by {}", tag, previous.name)));
+                    }
+
+                    next = tag + 1;
+                    variants.push(Variant { name: variant.ident.clone(), tag, shape: Shape::parse(&variant.fields)? });
+
+                }
+
+                Body::Enum(prefix, variants)
+
+            },
+            Data::Union(data) => return Err(syn::Error::new(data.union_token.span, "unions cannot be derived"))
+        };
+
+        Ok(Container { name: input.ident.clone(), generics: input.generics.clone(), body })
+
+    }
+
+    /// Generates the Readable implementation
+    fn expand_read(&self) -> TokenStream2 {
+
+        let name = &self.name;
+        let generics = bounded(&self.generics, quote!(::io_operations::Readable));
+        let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+        let body = match &self.body {
+            Body::Struct(shape) => {
+                let value = shape.expand_read(quote!(#name));
+                quote!(::std::result::Result::Ok(#value))
+            },
+            Body::Enum(prefix, variants) => {
+
+                let prefix = prefix.tokens();
+                let arms = variants.iter().map(|variant| {
+                    let tag = Literal::u64_unsuffixed(variant.tag);
+                    let variant_name = &variant.name;
+                    let value = variant.shape.expand_read(quote!(#name::#variant_name));
+                    quote!(#tag => ::std::result::Result::Ok(#value),)
+                });
+
+                quote! {
+                    match ::io_operations::derive_support::read_tag(__io_reader, #prefix)? {
+                        #(#arms)*
+                        _ => ::std::result::Result::Err(::io_operations::derive_support::unknown_tag())
+                    }
+                }
+
+            }
+        };
+
+        quote! {
+            impl #impl_generics ::io_operations::Readable for #name #type_generics #where_clause {
+                fn read_from<__IoReader: ::io_operations::reader::Reader + ?::std::marker::Sized>(__io_reader: &mut __IoReader) -> ::io_operations::Result<Self> {
+                    #body
+                }
+            }
+        }
+
+    }
+
+    /// Generates the Writable implementation
+    fn expand_write(&self) -> TokenStream2 {
+
+        let name = &self.name;
+        let generics = bounded(&self.generics, quote!(::io_operations::Writable));
+        let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+        let body = match &self.body {
+            Body::Struct(shape) => {
+                let pattern = shape.pattern(quote!(#name));
+                let writes = shape.expand_write();
+                quote! {
+                    let #pattern = self;
+                    #writes
+                    ::std::result::Result::Ok(())
+                }
+            },
+            Body::Enum(_, variants) if variants.is_empty() => quote!(match *self {}),
+            Body::Enum(prefix, variants) => {
+
+                let prefix = prefix.tokens();
+                let arms = variants.iter().map(|variant| {
+                    let tag = Literal::u64_unsuffixed(variant.tag);
+                    let variant_name = &variant.name;
+                    let pattern = variant.shape.pattern(quote!(#name::#variant_name));
+                    let writes = variant.shape.expand_write();
+                    quote! {
+                        #pattern => {
+                            ::io_operations::derive_support::write_tag(__io_writer, #prefix, #tag)?;
+                            #writes
+                        },
+                    }
+                });
+
+                quote! {
+                    match self { #(#arms)* }
+                    ::std::result::Result::Ok(())
+                }
+
+            }
+        };
+
+        quote! {
+            impl #impl_generics ::io_operations::Writable for #name #type_generics #where_clause {
+                fn write_to<__IoWriter: ::io_operations::writer::Writer + ?::std::marker::Sized>(&self, __io_writer: &mut __IoWriter) -> ::io_operations::Result<()> {
+                    #body
+                }
+            }
+        }
+
+    }
+
+}
+
+/// Parses the enum-level `#[io(tag = "...")]`, if any
+fn parse_tag_type(attributes: &[Attribute]) -> syn::Result<Option<(Span, Prefix)>> {
+
+    let mut tag_type = None;
+
+    for attribute in attributes.iter().filter(|attribute| attribute.path().is_ident("io")) {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                let name: LitStr = meta.value()?.parse()?;
+                tag_type = Some((name.span(), Prefix::parse(&name)?));
+                Ok(())
+            } else {
+                Err(meta.error("unknown io attribute; expected tag"))
+            }
+        })?;
+    }
+
+    Ok(tag_type)
+
+}
+
+/// Parses a variant's `#[io(tag = N)]`, if any
+fn parse_variant_tag(attributes: &[Attribute]) -> syn::Result<Option<LitInt>> {
+
+    let mut tag = None;
+
+    for attribute in attributes.iter().filter(|attribute| attribute.path().is_ident("io")) {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                tag = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown io attribute; expected tag"))
+            }
+        })?;
+    }
+
+    Ok(tag)
+
+}
+
+/// Adds the given trait bound to every type parameter
+fn bounded(generics: &syn::Generics, bound: TokenStream2) -> syn::Generics {
+
+    let mut generics = generics.clone();
+
+    for parameter in generics.type_params_mut() {
+        parameter.bounds.push(syn::parse_quote!(#bound));
+    }
+
+    generics
+
+}
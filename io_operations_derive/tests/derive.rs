@@ -0,0 +1,172 @@
+use io_operations::reader::Reader;
+use io_operations::writer::Writer;
+use io_operations::{Readable, Writable};
+
+use std::io::ErrorKind;
+
+#[derive(Readable, Writable, Debug, PartialEq)]
+struct Handshake {
+
+    #[io(varint)]
+    protocol: i32,
+
+    #[io(prefix = "varint", max = 255)]
+    address: String,
+
+    port: u16,
+
+    #[io(le)]
+    timestamp: u64,
+
+    #[io(zigzag)]
+    delta: i64,
+
+    #[io(fixed = 4)]
+    tag: String,
+
+    #[io(prefix = "u8")]
+    flags: Vec<bool>,
+
+    next: State
+
+}
+
+#[derive(Readable, Writable, Debug, PartialEq)]
+enum State {
+
+    Status,
+
+    Login(#[io(le)] u32),
+
+    Play { entity: u32, name: Option<String> }
+
+}
+
+#[derive(Readable, Writable, Debug, PartialEq)]
+#[io(tag = "varint")]
+#[repr(u8)]
+enum Packet {
+
+    #[io(tag = 0x20)]
+    KeepAlive(u64),
+
+    Chat = 5,
+
+    Disconnect { reason: String }
+
+}
+
+#[derive(Readable, Writable, Debug, PartialEq)]
+struct Pair<T>(T, #[io(fixed = 2)] Vec<T>);
+
+#[derive(Readable, Writable, Debug, PartialEq)]
+struct Marker;
+
+#[test]
+fn test_struct_round_trip() {
+
+    let handshake = Handshake {
+        protocol: 763,
+        address: String::from("localhost"),
+        port: 25565,
+        timestamp: 0x0102030405060708,
+        delta: -3,
+        tag: String::from("ab"),
+        flags: vec![true, false, true],
+        next: State::Play { entity: 7, name: Some(String::from("steve")) }
+    };
+
+    let mut bytes = Vec::new();
+    bytes.write_value(&handshake).unwrap();
+
+    assert_eq!(handshake, (&bytes[..]).read_value::<Handshake>().unwrap());
+
+}
+
+#[test]
+fn test_layout() {
+
+    let mut expected = Vec::new();
+
+    expected.write_varint_i32(763).unwrap();
+    expected.write_string("localhost", io_operations::LengthPrefix::VarInt).unwrap();
+    expected.write_be_u16(25565).unwrap();
+    expected.write_le_u64(0x0102030405060708).unwrap();
+    expected.write_zigzag_i64(-3).unwrap();
+    expected.extend_from_slice(b"ab\0\0");
+    expected.extend_from_slice(&[3, 1, 0, 1]);
+    expected.extend_from_slice(&[1, 9, 0, 0, 0]);
+
+    let handshake = Handshake {
+        protocol: 763,
+        address: String::from("localhost"),
+        port: 25565,
+        timestamp: 0x0102030405060708,
+        delta: -3,
+        tag: String::from("ab"),
+        flags: vec![true, false, true],
+        next: State::Login(9)
+    };
+
+    let mut bytes = Vec::new();
+    handshake.write_to(&mut bytes).unwrap();
+
+    assert_eq!(expected, bytes);
+
+}
+
+#[test]
+fn test_enum_tags() {
+
+    let mut bytes = Vec::new();
+
+    bytes.write_value(&Packet::KeepAlive(1)).unwrap();
+    bytes.write_value(&Packet::Chat).unwrap();
+    bytes.write_value(&Packet::Disconnect { reason: String::from("bye") }).unwrap();
+
+    assert_eq!(&[0x20, 0, 0, 0, 0, 0, 0, 0, 1, 5, 6, 3, b'b', b'y', b'e'][..], &bytes[..]);
+
+    let mut reader = &bytes[..];
+
+    assert_eq!(Packet::KeepAlive(1), reader.read_value().unwrap());
+    assert_eq!(Packet::Chat, reader.read_value().unwrap());
+    assert_eq!(Packet::Disconnect { reason: String::from("bye") }, reader.read_value().unwrap());
+
+    let error = (&[7u8][..]).read_value::<Packet>().unwrap_err();
+
+    assert_eq!(ErrorKind::InvalidData, error.kind());
+    assert_eq!("read_tag", error.operation());
+
+}
+
+#[test]
+fn test_generics_and_units() {
+
+    let pair = Pair(1u8, vec![2, 3]);
+
+    let mut bytes = Vec::new();
+    bytes.write_value(&pair).unwrap();
+    bytes.write_value(&Marker).unwrap();
+
+    assert_eq!(vec![1, 2, 3], bytes);
+    assert_eq!(pair, Pair::read_from(&mut &bytes[..]).unwrap());
+    assert_eq!(Marker, (&[][..]).read_value::<Marker>().unwrap());
+
+}
+
+#[test]
+fn test_limits() {
+
+    let mut bytes = Vec::new();
+
+    assert!(bytes.write_value(&Pair(1u8, vec![2])).is_err());
+
+    let mut long = Vec::new();
+    long.write_varint_i32(0).unwrap();
+    long.write_string(&"x".repeat(256), io_operations::LengthPrefix::VarInt).unwrap();
+
+    let error = (&long[..]).read_value::<Handshake>().unwrap_err();
+
+    assert_eq!(ErrorKind::InvalidData, error.kind());
+
+}
@@ -0,0 +1,1393 @@
+//! The async extension traits shared by every async runtime integration
+//!
+//! Each integration expands `async_extensions!` with its own AsyncRead and AsyncWrite
+//! traits, so AsyncReader and AsyncWriter are defined once however many runtimes are
+//! supported. Their Send variants, SendAsyncReader and SendAsyncWriter, come from the
+//! same method list. The expanding module must provide the two functions that move bytes:
+//!
+//! * `async fn read_some(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize>`
+//! * `async fn write_some(writer: &mut W, buffer: &[u8]) -> io::Result<usize>`
+//!
+//! where R and W implement the given traits and Unpin, and may be unsized.
+//!
+//! The methods themselves only move bytes: what to read or write, the limit checks and
+//! the error offsets all come from the codec module, which Reader and Writer use too.
+
+/// Defines AsyncReader and AsyncWriter, and their Send variants, over the given AsyncRead and AsyncWrite traits
+macro_rules! async_extensions {
+    (read: $read:path, write: $write:path, library: $library:literal) => {
+
+        use std::future::Future;
+
+        use crate::bulk::Scalar;
+        use crate::codec::{self, Decode, Encoded, Fixed, Length, Primitives, VarInt};
+        use crate::endian::{BigEndian, ByteOrder, Endian, LittleEndian};
+        use crate::error::Result;
+        use crate::prefix::LengthPrefix;
+        use crate::text::{SurrogatePolicy, TextEncoding};
+        use crate::varint;
+
+        crate::async_io::async_traits! {
+            reader: AsyncReader,
+            writer: AsyncWriter,
+            read: $read,
+            write: $write,
+            reader_doc: concat!("Extends ", $library, " AsyncRead to provide the Reader operations asynchronously"),
+            writer_doc: concat!("Extends ", $library, " AsyncWrite to provide the Writer operations asynchronously"),
+            bound: []
+        }
+
+        crate::async_io::async_traits! {
+            reader: SendAsyncReader,
+            writer: SendAsyncWriter,
+            read: $read,
+            write: $write,
+            reader_doc: "AsyncReader for Send streams, whose futures are Send even in generic code",
+            writer_doc: "AsyncWriter for Send streams, whose futures are Send even in generic code",
+            bound: [+ Send]
+        }
+
+        /// Fills the whole buffer from the given reader
+        ///
+        /// Behaves like the blocking `fill_buffer`, retrying short and interrupted reads
+        async fn fill_buffer<R: $read + Unpin + ?Sized>(reader: &mut R, buffer: &mut [u8], operation: &'static str) -> Result<()> {
+
+            let mut count = 0;
+
+            while count < buffer.len() {
+                let result = read_some(reader, &mut buffer[count..]).await;
+                codec::record_read(result, &mut count, buffer.len(), operation)?;
+            }
+
+            Ok(())
+
+        }
+
+        /// Runs a decoder to completion, filling each buffer it asks for from the given reader
+        async fn decode<R: $read + Unpin + ?Sized, D: Decode>(reader: &mut R, mut decoder: D) -> Result<D::Output> {
+
+            loop {
+
+                let consumed = decoder.consumed() as u64;
+                let operation = decoder.operation();
+
+                fill_buffer(reader, decoder.buffer(), operation).await
+                    .map_err(|error| error.with_base_offset(consumed))?;
+
+                if let Some(value) = decoder.advance()? {
+                    return Ok(value);
+                }
+
+            }
+
+        }
+
+        /// Writes the whole buffer to the given writer
+        ///
+        /// Behaves like the blocking `write_buffer`, retrying short and interrupted writes
+        async fn write_buffer<W: $write + Unpin + ?Sized>(writer: &mut W, buffer: &[u8], operation: &'static str) -> Result<()> {
+
+            let mut count = 0;
+
+            while count < buffer.len() {
+                let result = write_some(writer, &buffer[count..]).await;
+                codec::record_write(result, &mut count, operation)?;
+            }
+
+            Ok(())
+
+        }
+
+        /// Writes an encoded header and body to the given writer, skipping whichever is empty
+        async fn write_encoded<W: $write + Unpin + ?Sized>(writer: &mut W, encoded: &Encoded<'_>) -> Result<()> {
+
+            if !encoded.header().is_empty() {
+                write_buffer(writer, encoded.header(), encoded.operation()).await?;
+            }
+
+            if encoded.body().is_empty() {
+                return Ok(());
+            }
+
+            write_buffer(writer, encoded.body(), encoded.operation()).await
+                .map_err(|error| error.with_base_offset(encoded.header().len() as u64))
+
+        }
+
+    }
+}
+
+/// Defines a reader and writer trait pair, with an optional bound on the streams and futures
+///
+/// The bound applies to the streams the traits are implemented for and to the futures
+/// their methods return, so the pair without one covers every stream
+macro_rules! async_traits {
+    (
+        reader: $reader:ident,
+        writer: $writer:ident,
+        read: $read:path,
+        write: $write:path,
+        reader_doc: $reader_doc:expr,
+        writer_doc: $writer_doc:expr,
+        bound: [$($bound:tt)*]
+    ) => {
+
+        #[doc = $reader_doc]
+        pub trait $reader : $read + Unpin $($bound)* {
+
+            /// Reads an unsigned byte from this AsyncReader
+            fn read_u8(&mut self) -> impl Future<Output = Result<u8>> $($bound)* {
+                async move {
+                    decode(self, Fixed::new(Endian::NATIVE, "read_u8")).await
+                }
+            }
+
+            /// Reads a signed byte from this AsyncReader
+            fn read_i8(&mut self) -> impl Future<Output = Result<i8>> $($bound)* {
+                async move {
+                    decode(self, Fixed::new(Endian::NATIVE, "read_i8")).await
+                }
+            }
+
+            /// Reads an unsigned short in the given byte order from this AsyncReader
+            fn read_u16_in(&mut self, endian: Endian) -> impl Future<Output = Result<u16>> $($bound)* {
+                async move {
+                    decode(self, Fixed::new(endian, endian.select("read_be_u16", "read_le_u16"))).await
+                }
+            }
+
+            /// Reads an unsigned short in the byte order `E` from this AsyncReader
+            fn read_u16<E: ByteOrder>(&mut self) -> impl Future<Output = Result<u16>> $($bound)* {
+                async move {
+                    Self::read_u16_in(self, E::ENDIAN).await
+                }
+            }
+
+            /// Reads an unsigned big-endian short from this AsyncReader
+            fn read_be_u16(&mut self) -> impl Future<Output = Result<u16>> $($bound)* {
+                async move {
+                    Self::read_u16::<BigEndian>(self).await
+                }
+            }
+
+            /// Reads an unsigned little-endian short from this AsyncReader
+            fn read_le_u16(&mut self) -> impl Future<Output = Result<u16>> $($bound)* {
+                async move {
+                    Self::read_u16::<LittleEndian>(self).await
+                }
+            }
+
+            /// Reads a signed short in the given byte order from this AsyncReader
+            fn read_i16_in(&mut self, endian: Endian) -> impl Future<Output = Result<i16>> $($bound)* {
+                async move {
+                    decode(self, Fixed::new(endian, endian.select("read_be_i16", "read_le_i16"))).await
+                }
+            }
+
+            /// Reads a signed short in the byte order `E` from this AsyncReader
+            fn read_i16<E: ByteOrder>(&mut self) -> impl Future<Output = Result<i16>> $($bound)* {
+                async move {
+                    Self::read_i16_in(self, E::ENDIAN).await
+                }
+            }
+
+            /// Reads a signed big-endian short from this AsyncReader
+            fn read_be_i16(&mut self) -> impl Future<Output = Result<i16>> $($bound)* {
+                async move {
+                    Self::read_i16::<BigEndian>(self).await
+                }
+            }
+
+            /// Reads a signed little-endian short from this AsyncReader
+            fn read_le_i16(&mut self) -> impl Future<Output = Result<i16>> $($bound)* {
+                async move {
+                    Self::read_i16::<LittleEndian>(self).await
+                }
+            }
+
+            /// Reads an unsigned integer in the given byte order from this AsyncReader
+            fn read_u32_in(&mut self, endian: Endian) -> impl Future<Output = Result<u32>> $($bound)* {
+                async move {
+                    decode(self, Fixed::new(endian, endian.select("read_be_u32", "read_le_u32"))).await
+                }
+            }
+
+            /// Reads an unsigned integer in the byte order `E` from this AsyncReader
+            fn read_u32<E: ByteOrder>(&mut self) -> impl Future<Output = Result<u32>> $($bound)* {
+                async move {
+                    Self::read_u32_in(self, E::ENDIAN).await
+                }
+            }
+
+            /// Reads an unsigned big-endian integer from this AsyncReader
+            fn read_be_u32(&mut self) -> impl Future<Output = Result<u32>> $($bound)* {
+                async move {
+                    Self::read_u32::<BigEndian>(self).await
+                }
+            }
+
+            /// Reads an unsigned little-endian integer from this AsyncReader
+            fn read_le_u32(&mut self) -> impl Future<Output = Result<u32>> $($bound)* {
+                async move {
+                    Self::read_u32::<LittleEndian>(self).await
+                }
+            }
+
+            /// Reads a signed integer in the given byte order from this AsyncReader
+            fn read_i32_in(&mut self, endian: Endian) -> impl Future<Output = Result<i32>> $($bound)* {
+                async move {
+                    decode(self, Fixed::new(endian, endian.select("read_be_i32", "read_le_i32"))).await
+                }
+            }
+
+            /// Reads a signed integer in the byte order `E` from this AsyncReader
+            fn read_i32<E: ByteOrder>(&mut self) -> impl Future<Output = Result<i32>> $($bound)* {
+                async move {
+                    Self::read_i32_in(self, E::ENDIAN).await
+                }
+            }
+
+            /// Reads a signed big-endian integer from this AsyncReader
+            fn read_be_i32(&mut self) -> impl Future<Output = Result<i32>> $($bound)* {
+                async move {
+                    Self::read_i32::<BigEndian>(self).await
+                }
+            }
+
+            /// Reads a signed little-endian integer from this AsyncReader
+            fn read_le_i32(&mut self) -> impl Future<Output = Result<i32>> $($bound)* {
+                async move {
+                    Self::read_i32::<LittleEndian>(self).await
+                }
+            }
+
+            /// Reads an unsigned long in the given byte order from this AsyncReader
+            fn read_u64_in(&mut self, endian: Endian) -> impl Future<Output = Result<u64>> $($bound)* {
+                async move {
+                    decode(self, Fixed::new(endian, endian.select("read_be_u64", "read_le_u64"))).await
+                }
+            }
+
+            /// Reads an unsigned long in the byte order `E` from this AsyncReader
+            fn read_u64<E: ByteOrder>(&mut self) -> impl Future<Output = Result<u64>> $($bound)* {
+                async move {
+                    Self::read_u64_in(self, E::ENDIAN).await
+                }
+            }
+
+            /// Reads an unsigned big-endian long from this AsyncReader
+            fn read_be_u64(&mut self) -> impl Future<Output = Result<u64>> $($bound)* {
+                async move {
+                    Self::read_u64::<BigEndian>(self).await
+                }
+            }
+
+            /// Reads an unsigned little-endian long from this AsyncReader
+            fn read_le_u64(&mut self) -> impl Future<Output = Result<u64>> $($bound)* {
+                async move {
+                    Self::read_u64::<LittleEndian>(self).await
+                }
+            }
+
+            /// Reads a signed long in the given byte order from this AsyncReader
+            fn read_i64_in(&mut self, endian: Endian) -> impl Future<Output = Result<i64>> $($bound)* {
+                async move {
+                    decode(self, Fixed::new(endian, endian.select("read_be_i64", "read_le_i64"))).await
+                }
+            }
+
+            /// Reads a signed long in the byte order `E` from this AsyncReader
+            fn read_i64<E: ByteOrder>(&mut self) -> impl Future<Output = Result<i64>> $($bound)* {
+                async move {
+                    Self::read_i64_in(self, E::ENDIAN).await
+                }
+            }
+
+            /// Reads a signed big-endian long from this AsyncReader
+            fn read_be_i64(&mut self) -> impl Future<Output = Result<i64>> $($bound)* {
+                async move {
+                    Self::read_i64::<BigEndian>(self).await
+                }
+            }
+
+            /// Reads a signed little-endian long from this AsyncReader
+            fn read_le_i64(&mut self) -> impl Future<Output = Result<i64>> $($bound)* {
+                async move {
+                    Self::read_i64::<LittleEndian>(self).await
+                }
+            }
+
+            /// Reads an unsigned 128-bit integer in the given byte order from this AsyncReader
+            fn read_u128_in(&mut self, endian: Endian) -> impl Future<Output = Result<u128>> $($bound)* {
+                async move {
+                    decode(self, Fixed::new(endian, endian.select("read_be_u128", "read_le_u128"))).await
+                }
+            }
+
+            /// Reads an unsigned 128-bit integer in the byte order `E` from this AsyncReader
+            fn read_u128<E: ByteOrder>(&mut self) -> impl Future<Output = Result<u128>> $($bound)* {
+                async move {
+                    Self::read_u128_in(self, E::ENDIAN).await
+                }
+            }
+
+            /// Reads an unsigned big-endian 128-bit integer from this AsyncReader
+            fn read_be_u128(&mut self) -> impl Future<Output = Result<u128>> $($bound)* {
+                async move {
+                    Self::read_u128::<BigEndian>(self).await
+                }
+            }
+
+            /// Reads an unsigned little-endian 128-bit integer from this AsyncReader
+            fn read_le_u128(&mut self) -> impl Future<Output = Result<u128>> $($bound)* {
+                async move {
+                    Self::read_u128::<LittleEndian>(self).await
+                }
+            }
+
+            /// Reads a signed 128-bit integer in the given byte order from this AsyncReader
+            fn read_i128_in(&mut self, endian: Endian) -> impl Future<Output = Result<i128>> $($bound)* {
+                async move {
+                    decode(self, Fixed::new(endian, endian.select("read_be_i128", "read_le_i128"))).await
+                }
+            }
+
+            /// Reads a signed 128-bit integer in the byte order `E` from this AsyncReader
+            fn read_i128<E: ByteOrder>(&mut self) -> impl Future<Output = Result<i128>> $($bound)* {
+                async move {
+                    Self::read_i128_in(self, E::ENDIAN).await
+                }
+            }
+
+            /// Reads a signed big-endian 128-bit integer from this AsyncReader
+            fn read_be_i128(&mut self) -> impl Future<Output = Result<i128>> $($bound)* {
+                async move {
+                    Self::read_i128::<BigEndian>(self).await
+                }
+            }
+
+            /// Reads a signed little-endian 128-bit integer from this AsyncReader
+            fn read_le_i128(&mut self) -> impl Future<Output = Result<i128>> $($bound)* {
+                async move {
+                    Self::read_i128::<LittleEndian>(self).await
+                }
+            }
+
+            /// Reads a single-precision float in the given byte order from this AsyncReader
+            fn read_f32_in(&mut self, endian: Endian) -> impl Future<Output = Result<f32>> $($bound)* {
+                async move {
+                    decode(self, Fixed::new(endian, endian.select("read_be_f32", "read_le_f32"))).await
+                }
+            }
+
+            /// Reads a single-precision float in the byte order `E` from this AsyncReader
+            fn read_f32<E: ByteOrder>(&mut self) -> impl Future<Output = Result<f32>> $($bound)* {
+                async move {
+                    Self::read_f32_in(self, E::ENDIAN).await
+                }
+            }
+
+            /// Reads a big-endian single-precision float from this AsyncReader
+            fn read_be_f32(&mut self) -> impl Future<Output = Result<f32>> $($bound)* {
+                async move {
+                    Self::read_f32::<BigEndian>(self).await
+                }
+            }
+
+            /// Reads a little-endian single-precision float from this AsyncReader
+            fn read_le_f32(&mut self) -> impl Future<Output = Result<f32>> $($bound)* {
+                async move {
+                    Self::read_f32::<LittleEndian>(self).await
+                }
+            }
+
+            /// Reads a double-precision float in the given byte order from this AsyncReader
+            fn read_f64_in(&mut self, endian: Endian) -> impl Future<Output = Result<f64>> $($bound)* {
+                async move {
+                    decode(self, Fixed::new(endian, endian.select("read_be_f64", "read_le_f64"))).await
+                }
+            }
+
+            /// Reads a double-precision float in the byte order `E` from this AsyncReader
+            fn read_f64<E: ByteOrder>(&mut self) -> impl Future<Output = Result<f64>> $($bound)* {
+                async move {
+                    Self::read_f64_in(self, E::ENDIAN).await
+                }
+            }
+
+            /// Reads a big-endian double-precision float from this AsyncReader
+            fn read_be_f64(&mut self) -> impl Future<Output = Result<f64>> $($bound)* {
+                async move {
+                    Self::read_f64::<BigEndian>(self).await
+                }
+            }
+
+            /// Reads a little-endian double-precision float from this AsyncReader
+            fn read_le_f64(&mut self) -> impl Future<Output = Result<f64>> $($bound)* {
+                async move {
+                    Self::read_f64::<LittleEndian>(self).await
+                }
+            }
+
+            /// Fills `values` with unsigned shorts in the byte order `E` from this AsyncReader
+            fn read_u16_into<E: ByteOrder>(&mut self, values: &mut [u16]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_u16_into", "read_le_u16_into"))).await
+                }
+            }
+
+            /// Fills `values` with big-endian unsigned shorts from this AsyncReader
+            fn read_be_u16_into(&mut self, values: &mut [u16]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_u16_into::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with little-endian unsigned shorts from this AsyncReader
+            fn read_le_u16_into(&mut self, values: &mut [u16]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_u16_into::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with signed shorts in the byte order `E` from this AsyncReader
+            fn read_i16_into<E: ByteOrder>(&mut self, values: &mut [i16]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_i16_into", "read_le_i16_into"))).await
+                }
+            }
+
+            /// Fills `values` with big-endian signed shorts from this AsyncReader
+            fn read_be_i16_into(&mut self, values: &mut [i16]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_i16_into::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with little-endian signed shorts from this AsyncReader
+            fn read_le_i16_into(&mut self, values: &mut [i16]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_i16_into::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with unsigned integers in the byte order `E` from this AsyncReader
+            fn read_u32_into<E: ByteOrder>(&mut self, values: &mut [u32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_u32_into", "read_le_u32_into"))).await
+                }
+            }
+
+            /// Fills `values` with big-endian unsigned integers from this AsyncReader
+            fn read_be_u32_into(&mut self, values: &mut [u32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_u32_into::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with little-endian unsigned integers from this AsyncReader
+            fn read_le_u32_into(&mut self, values: &mut [u32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_u32_into::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with signed integers in the byte order `E` from this AsyncReader
+            fn read_i32_into<E: ByteOrder>(&mut self, values: &mut [i32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_i32_into", "read_le_i32_into"))).await
+                }
+            }
+
+            /// Fills `values` with big-endian signed integers from this AsyncReader
+            fn read_be_i32_into(&mut self, values: &mut [i32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_i32_into::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with little-endian signed integers from this AsyncReader
+            fn read_le_i32_into(&mut self, values: &mut [i32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_i32_into::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with unsigned longs in the byte order `E` from this AsyncReader
+            fn read_u64_into<E: ByteOrder>(&mut self, values: &mut [u64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_u64_into", "read_le_u64_into"))).await
+                }
+            }
+
+            /// Fills `values` with big-endian unsigned longs from this AsyncReader
+            fn read_be_u64_into(&mut self, values: &mut [u64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_u64_into::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with little-endian unsigned longs from this AsyncReader
+            fn read_le_u64_into(&mut self, values: &mut [u64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_u64_into::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with signed longs in the byte order `E` from this AsyncReader
+            fn read_i64_into<E: ByteOrder>(&mut self, values: &mut [i64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_i64_into", "read_le_i64_into"))).await
+                }
+            }
+
+            /// Fills `values` with big-endian signed longs from this AsyncReader
+            fn read_be_i64_into(&mut self, values: &mut [i64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_i64_into::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with little-endian signed longs from this AsyncReader
+            fn read_le_i64_into(&mut self, values: &mut [i64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_i64_into::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with unsigned 128-bit integers in the byte order `E` from this AsyncReader
+            fn read_u128_into<E: ByteOrder>(&mut self, values: &mut [u128]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_u128_into", "read_le_u128_into"))).await
+                }
+            }
+
+            /// Fills `values` with big-endian unsigned 128-bit integers from this AsyncReader
+            fn read_be_u128_into(&mut self, values: &mut [u128]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_u128_into::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with little-endian unsigned 128-bit integers from this AsyncReader
+            fn read_le_u128_into(&mut self, values: &mut [u128]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_u128_into::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with signed 128-bit integers in the byte order `E` from this AsyncReader
+            fn read_i128_into<E: ByteOrder>(&mut self, values: &mut [i128]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_i128_into", "read_le_i128_into"))).await
+                }
+            }
+
+            /// Fills `values` with big-endian signed 128-bit integers from this AsyncReader
+            fn read_be_i128_into(&mut self, values: &mut [i128]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_i128_into::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with little-endian signed 128-bit integers from this AsyncReader
+            fn read_le_i128_into(&mut self, values: &mut [i128]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_i128_into::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with single-precision floats in the byte order `E` from this AsyncReader
+            fn read_f32_into<E: ByteOrder>(&mut self, values: &mut [f32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_f32_into", "read_le_f32_into"))).await
+                }
+            }
+
+            /// Fills `values` with big-endian single-precision floats from this AsyncReader
+            fn read_be_f32_into(&mut self, values: &mut [f32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_f32_into::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with little-endian single-precision floats from this AsyncReader
+            fn read_le_f32_into(&mut self, values: &mut [f32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_f32_into::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with double-precision floats in the byte order `E` from this AsyncReader
+            fn read_f64_into<E: ByteOrder>(&mut self, values: &mut [f64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_f64_into", "read_le_f64_into"))).await
+                }
+            }
+
+            /// Fills `values` with big-endian double-precision floats from this AsyncReader
+            fn read_be_f64_into(&mut self, values: &mut [f64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_f64_into::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Fills `values` with little-endian double-precision floats from this AsyncReader
+            fn read_le_f64_into(&mut self, values: &mut [f64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::read_f64_into::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Reads an unsigned 32-bit VarInt from this AsyncReader
+            fn read_varint_u32(&mut self) -> impl Future<Output = Result<u32>> $($bound)* {
+                async move {
+                    decode(self, VarInt::new(32, "read_varint_u32")).await.map(|value| value as u32)
+                }
+            }
+
+            /// Reads a signed 32-bit VarInt, stored in two's complement, from this AsyncReader
+            fn read_varint_i32(&mut self) -> impl Future<Output = Result<i32>> $($bound)* {
+                async move {
+                    decode(self, VarInt::new(32, "read_varint_i32")).await.map(|value| value as u32 as i32)
+                }
+            }
+
+            /// Reads an unsigned 64-bit VarLong from this AsyncReader
+            fn read_varint_u64(&mut self) -> impl Future<Output = Result<u64>> $($bound)* {
+                async move {
+                    decode(self, VarInt::new(64, "read_varint_u64")).await
+                }
+            }
+
+            /// Reads a signed 64-bit VarLong, stored in two's complement, from this AsyncReader
+            fn read_varint_i64(&mut self) -> impl Future<Output = Result<i64>> $($bound)* {
+                async move {
+                    decode(self, VarInt::new(64, "read_varint_i64")).await.map(|value| value as i64)
+                }
+            }
+
+            /// Reads a signed 32-bit ZigZag-encoded VarInt from this AsyncReader
+            fn read_zigzag_i32(&mut self) -> impl Future<Output = Result<i32>> $($bound)* {
+                async move {
+                    decode(self, VarInt::new(32, "read_zigzag_i32")).await.map(|value| varint::decode_zigzag_i32(value as u32))
+                }
+            }
+
+            /// Reads a signed 64-bit ZigZag-encoded VarLong from this AsyncReader
+            fn read_zigzag_i64(&mut self) -> impl Future<Output = Result<i64>> $($bound)* {
+                async move {
+                    decode(self, VarInt::new(64, "read_zigzag_i64")).await.map(varint::decode_zigzag_i64)
+                }
+            }
+
+            /// Reads a length prefix of the given type from this AsyncReader
+            fn read_length_prefix(&mut self, prefix: LengthPrefix) -> impl Future<Output = Result<usize>> $($bound)* {
+                async move {
+                    decode(self, Length::new(prefix, "read_length_prefix")).await.map(|(length, _)| length)
+                }
+            }
+
+            /// Reads a length-prefixed UTF-8 string from this AsyncReader
+            ///
+            /// Lengths above `max_length` bytes are rejected before anything is allocated, so a
+            /// hostile peer cannot make us reserve more memory than the caller allows
+            fn read_string(&mut self, prefix: LengthPrefix, max_length: usize) -> impl Future<Output = Result<String>> $($bound)* {
+                async move {
+                    decode(self, codec::read_string(prefix, max_length)).await
+                }
+            }
+
+            /// Reads a string written by Java's `DataOutput.writeUTF` from this AsyncReader
+            ///
+            /// That is a big-endian u16 byte length followed by modified UTF-8
+            fn read_java_utf(&mut self) -> impl Future<Output = Result<String>> $($bound)* {
+                async move {
+                    decode(self, codec::read_java_utf()).await
+                }
+            }
+
+            /// Reads a UTF-16 string prefixed with its length in code units from this AsyncReader
+            ///
+            /// When `detect_bom` is set, a leading byte order mark overrides `endian`; it still
+            /// counts towards the length. Lengths above `max_units` are rejected before anything
+            /// is allocated
+            fn read_utf16(
+                &mut self,
+                prefix: LengthPrefix,
+                max_units: usize,
+                endian: Endian,
+                detect_bom: bool,
+                policy: SurrogatePolicy
+            ) -> impl Future<Output = Result<String>> $($bound)* {
+                async move {
+                    decode(self, codec::read_utf16(prefix, max_units, endian, detect_bom, policy)).await
+                }
+            }
+
+            /// Reads a UTF-16 string terminated by a NUL code unit from this AsyncReader
+            ///
+            /// When `detect_bom` is set, a leading byte order mark overrides `endian`. Strings
+            /// with more than `max_units` code units before the terminator are rejected
+            fn read_utf16_nul(
+                &mut self,
+                max_units: usize,
+                endian: Endian,
+                detect_bom: bool,
+                policy: SurrogatePolicy
+            ) -> impl Future<Output = Result<String>> $($bound)* {
+                async move {
+                    decode(self, codec::read_utf16_nul(max_units, endian, detect_bom, policy)).await
+                }
+            }
+
+            /// Reads a NUL-terminated string from this AsyncReader
+            ///
+            /// The string is read one byte at a time so that nothing past the terminator is
+            /// consumed; wrap unbuffered streams in a `BufReader`. Strings with more than
+            /// `max_length` bytes before the terminator are rejected
+            fn read_cstring(&mut self, max_length: usize, encoding: TextEncoding) -> impl Future<Output = Result<String>> $($bound)* {
+                async move {
+                    decode(self, codec::read_cstring(max_length, encoding)).await
+                }
+            }
+
+            /// Reads a string stored in a fixed-width field from this AsyncReader
+            ///
+            /// Exactly `width` bytes are consumed and any trailing `pad_byte`s are removed
+            fn read_fixed_str(&mut self, width: usize, pad_byte: u8, encoding: TextEncoding) -> impl Future<Output = Result<String>> $($bound)* {
+                async move {
+                    decode(self, codec::read_fixed_str(width, pad_byte, encoding)).await
+                }
+            }
+
+        }
+
+        impl<T> $reader for T where T: $read + Unpin $($bound)* { }
+
+        #[doc = $writer_doc]
+        ///
+        /// Nothing is flushed; flush the stream once a message is complete
+        pub trait $writer : $write + Unpin $($bound)* {
+
+            /// Writes an unsigned byte to this AsyncWriter
+            fn write_u8(&mut self, value: u8) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_buffer(self, &[value], "write_u8").await
+                }
+            }
+
+            /// Writes a signed byte to this AsyncWriter
+            fn write_i8(&mut self, value: i8) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_buffer(self, &[value as u8], "write_i8").await
+                }
+            }
+
+            /// Writes an unsigned short in the given byte order to this AsyncWriter
+            fn write_u16_in(&mut self, value: u16, endian: Endian) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_u16", "write_le_u16")).await
+                }
+            }
+
+            /// Writes an unsigned short in the byte order `E` to this AsyncWriter
+            fn write_u16<E: ByteOrder>(&mut self, value: u16) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u16_in(self, value, E::ENDIAN).await
+                }
+            }
+
+            /// Writes an unsigned little-endian short to this AsyncWriter
+            fn write_le_u16(&mut self, value: u16) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u16::<LittleEndian>(self, value).await
+                }
+            }
+
+            /// Writes an unsigned big-endian short to this AsyncWriter
+            fn write_be_u16(&mut self, value: u16) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u16::<BigEndian>(self, value).await
+                }
+            }
+
+            /// Writes a signed short in the given byte order to this AsyncWriter
+            fn write_i16_in(&mut self, value: i16, endian: Endian) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_i16", "write_le_i16")).await
+                }
+            }
+
+            /// Writes a signed short in the byte order `E` to this AsyncWriter
+            fn write_i16<E: ByteOrder>(&mut self, value: i16) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i16_in(self, value, E::ENDIAN).await
+                }
+            }
+
+            /// Writes a signed little-endian short to this AsyncWriter
+            fn write_le_i16(&mut self, value: i16) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i16::<LittleEndian>(self, value).await
+                }
+            }
+
+            /// Writes a signed big-endian short to this AsyncWriter
+            fn write_be_i16(&mut self, value: i16) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i16::<BigEndian>(self, value).await
+                }
+            }
+
+            /// Writes an unsigned integer in the given byte order to this AsyncWriter
+            fn write_u32_in(&mut self, value: u32, endian: Endian) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_u32", "write_le_u32")).await
+                }
+            }
+
+            /// Writes an unsigned integer in the byte order `E` to this AsyncWriter
+            fn write_u32<E: ByteOrder>(&mut self, value: u32) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u32_in(self, value, E::ENDIAN).await
+                }
+            }
+
+            /// Writes an unsigned little-endian integer to this AsyncWriter
+            fn write_le_u32(&mut self, value: u32) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u32::<LittleEndian>(self, value).await
+                }
+            }
+
+            /// Writes an unsigned big-endian integer to this AsyncWriter
+            fn write_be_u32(&mut self, value: u32) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u32::<BigEndian>(self, value).await
+                }
+            }
+
+            /// Writes a signed integer in the given byte order to this AsyncWriter
+            fn write_i32_in(&mut self, value: i32, endian: Endian) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_i32", "write_le_i32")).await
+                }
+            }
+
+            /// Writes a signed integer in the byte order `E` to this AsyncWriter
+            fn write_i32<E: ByteOrder>(&mut self, value: i32) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i32_in(self, value, E::ENDIAN).await
+                }
+            }
+
+            /// Writes a signed little-endian integer to this AsyncWriter
+            fn write_le_i32(&mut self, value: i32) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i32::<LittleEndian>(self, value).await
+                }
+            }
+
+            /// Writes a signed big-endian integer to this AsyncWriter
+            fn write_be_i32(&mut self, value: i32) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i32::<BigEndian>(self, value).await
+                }
+            }
+
+            /// Writes an unsigned long in the given byte order to this AsyncWriter
+            fn write_u64_in(&mut self, value: u64, endian: Endian) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_u64", "write_le_u64")).await
+                }
+            }
+
+            /// Writes an unsigned long in the byte order `E` to this AsyncWriter
+            fn write_u64<E: ByteOrder>(&mut self, value: u64) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u64_in(self, value, E::ENDIAN).await
+                }
+            }
+
+            /// Writes an unsigned little-endian long to this AsyncWriter
+            fn write_le_u64(&mut self, value: u64) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u64::<LittleEndian>(self, value).await
+                }
+            }
+
+            /// Writes an unsigned big-endian long to this AsyncWriter
+            fn write_be_u64(&mut self, value: u64) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u64::<BigEndian>(self, value).await
+                }
+            }
+
+            /// Writes a signed long in the given byte order to this AsyncWriter
+            fn write_i64_in(&mut self, value: i64, endian: Endian) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_i64", "write_le_i64")).await
+                }
+            }
+
+            /// Writes a signed long in the byte order `E` to this AsyncWriter
+            fn write_i64<E: ByteOrder>(&mut self, value: i64) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i64_in(self, value, E::ENDIAN).await
+                }
+            }
+
+            /// Writes a signed little-endian long to this AsyncWriter
+            fn write_le_i64(&mut self, value: i64) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i64::<LittleEndian>(self, value).await
+                }
+            }
+
+            /// Writes a signed big-endian long to this AsyncWriter
+            fn write_be_i64(&mut self, value: i64) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i64::<BigEndian>(self, value).await
+                }
+            }
+
+            /// Writes an unsigned 128-bit integer in the given byte order to this AsyncWriter
+            fn write_u128_in(&mut self, value: u128, endian: Endian) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_u128", "write_le_u128")).await
+                }
+            }
+
+            /// Writes an unsigned 128-bit integer in the byte order `E` to this AsyncWriter
+            fn write_u128<E: ByteOrder>(&mut self, value: u128) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u128_in(self, value, E::ENDIAN).await
+                }
+            }
+
+            /// Writes an unsigned little-endian 128-bit integer to this AsyncWriter
+            fn write_le_u128(&mut self, value: u128) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u128::<LittleEndian>(self, value).await
+                }
+            }
+
+            /// Writes an unsigned big-endian 128-bit integer to this AsyncWriter
+            fn write_be_u128(&mut self, value: u128) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u128::<BigEndian>(self, value).await
+                }
+            }
+
+            /// Writes a signed 128-bit integer in the given byte order to this AsyncWriter
+            fn write_i128_in(&mut self, value: i128, endian: Endian) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_i128", "write_le_i128")).await
+                }
+            }
+
+            /// Writes a signed 128-bit integer in the byte order `E` to this AsyncWriter
+            fn write_i128<E: ByteOrder>(&mut self, value: i128) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i128_in(self, value, E::ENDIAN).await
+                }
+            }
+
+            /// Writes a signed little-endian 128-bit integer to this AsyncWriter
+            fn write_le_i128(&mut self, value: i128) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i128::<LittleEndian>(self, value).await
+                }
+            }
+
+            /// Writes a signed big-endian 128-bit integer to this AsyncWriter
+            fn write_be_i128(&mut self, value: i128) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i128::<BigEndian>(self, value).await
+                }
+            }
+
+            /// Writes a single-precision float in the given byte order to this AsyncWriter
+            fn write_f32_in(&mut self, value: f32, endian: Endian) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_f32", "write_le_f32")).await
+                }
+            }
+
+            /// Writes a single-precision float in the byte order `E` to this AsyncWriter
+            fn write_f32<E: ByteOrder>(&mut self, value: f32) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_f32_in(self, value, E::ENDIAN).await
+                }
+            }
+
+            /// Writes a little-endian single-precision float to this AsyncWriter
+            fn write_le_f32(&mut self, value: f32) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_f32::<LittleEndian>(self, value).await
+                }
+            }
+
+            /// Writes a big-endian single-precision float to this AsyncWriter
+            fn write_be_f32(&mut self, value: f32) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_f32::<BigEndian>(self, value).await
+                }
+            }
+
+            /// Writes a double-precision float in the given byte order to this AsyncWriter
+            fn write_f64_in(&mut self, value: f64, endian: Endian) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_f64", "write_le_f64")).await
+                }
+            }
+
+            /// Writes a double-precision float in the byte order `E` to this AsyncWriter
+            fn write_f64<E: ByteOrder>(&mut self, value: f64) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_f64_in(self, value, E::ENDIAN).await
+                }
+            }
+
+            /// Writes a little-endian double-precision float to this AsyncWriter
+            fn write_le_f64(&mut self, value: f64) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_f64::<LittleEndian>(self, value).await
+                }
+            }
+
+            /// Writes a big-endian double-precision float to this AsyncWriter
+            fn write_be_f64(&mut self, value: f64) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_f64::<BigEndian>(self, value).await
+                }
+            }
+
+            /// Writes a slice of unsigned shorts in the byte order `E` to this AsyncWriter
+            fn write_u16_slice<E: ByteOrder>(&mut self, values: &[u16]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_u16_slice", "write_le_u16_slice"))).await
+                }
+            }
+
+            /// Writes a slice of little-endian unsigned shorts to this AsyncWriter
+            fn write_le_u16_slice(&mut self, values: &[u16]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u16_slice::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of big-endian unsigned shorts to this AsyncWriter
+            fn write_be_u16_slice(&mut self, values: &[u16]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u16_slice::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of signed shorts in the byte order `E` to this AsyncWriter
+            fn write_i16_slice<E: ByteOrder>(&mut self, values: &[i16]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_i16_slice", "write_le_i16_slice"))).await
+                }
+            }
+
+            /// Writes a slice of little-endian signed shorts to this AsyncWriter
+            fn write_le_i16_slice(&mut self, values: &[i16]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i16_slice::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of big-endian signed shorts to this AsyncWriter
+            fn write_be_i16_slice(&mut self, values: &[i16]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i16_slice::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of unsigned integers in the byte order `E` to this AsyncWriter
+            fn write_u32_slice<E: ByteOrder>(&mut self, values: &[u32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_u32_slice", "write_le_u32_slice"))).await
+                }
+            }
+
+            /// Writes a slice of little-endian unsigned integers to this AsyncWriter
+            fn write_le_u32_slice(&mut self, values: &[u32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u32_slice::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of big-endian unsigned integers to this AsyncWriter
+            fn write_be_u32_slice(&mut self, values: &[u32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u32_slice::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of signed integers in the byte order `E` to this AsyncWriter
+            fn write_i32_slice<E: ByteOrder>(&mut self, values: &[i32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_i32_slice", "write_le_i32_slice"))).await
+                }
+            }
+
+            /// Writes a slice of little-endian signed integers to this AsyncWriter
+            fn write_le_i32_slice(&mut self, values: &[i32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i32_slice::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of big-endian signed integers to this AsyncWriter
+            fn write_be_i32_slice(&mut self, values: &[i32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i32_slice::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of unsigned longs in the byte order `E` to this AsyncWriter
+            fn write_u64_slice<E: ByteOrder>(&mut self, values: &[u64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_u64_slice", "write_le_u64_slice"))).await
+                }
+            }
+
+            /// Writes a slice of little-endian unsigned longs to this AsyncWriter
+            fn write_le_u64_slice(&mut self, values: &[u64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u64_slice::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of big-endian unsigned longs to this AsyncWriter
+            fn write_be_u64_slice(&mut self, values: &[u64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u64_slice::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of signed longs in the byte order `E` to this AsyncWriter
+            fn write_i64_slice<E: ByteOrder>(&mut self, values: &[i64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_i64_slice", "write_le_i64_slice"))).await
+                }
+            }
+
+            /// Writes a slice of little-endian signed longs to this AsyncWriter
+            fn write_le_i64_slice(&mut self, values: &[i64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i64_slice::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of big-endian signed longs to this AsyncWriter
+            fn write_be_i64_slice(&mut self, values: &[i64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i64_slice::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of unsigned 128-bit integers in the byte order `E` to this AsyncWriter
+            fn write_u128_slice<E: ByteOrder>(&mut self, values: &[u128]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_u128_slice", "write_le_u128_slice"))).await
+                }
+            }
+
+            /// Writes a slice of little-endian unsigned 128-bit integers to this AsyncWriter
+            fn write_le_u128_slice(&mut self, values: &[u128]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u128_slice::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of big-endian unsigned 128-bit integers to this AsyncWriter
+            fn write_be_u128_slice(&mut self, values: &[u128]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_u128_slice::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of signed 128-bit integers in the byte order `E` to this AsyncWriter
+            fn write_i128_slice<E: ByteOrder>(&mut self, values: &[i128]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_i128_slice", "write_le_i128_slice"))).await
+                }
+            }
+
+            /// Writes a slice of little-endian signed 128-bit integers to this AsyncWriter
+            fn write_le_i128_slice(&mut self, values: &[i128]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i128_slice::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of big-endian signed 128-bit integers to this AsyncWriter
+            fn write_be_i128_slice(&mut self, values: &[i128]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_i128_slice::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of single-precision floats in the byte order `E` to this AsyncWriter
+            fn write_f32_slice<E: ByteOrder>(&mut self, values: &[f32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_f32_slice", "write_le_f32_slice"))).await
+                }
+            }
+
+            /// Writes a slice of little-endian single-precision floats to this AsyncWriter
+            fn write_le_f32_slice(&mut self, values: &[f32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_f32_slice::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of big-endian single-precision floats to this AsyncWriter
+            fn write_be_f32_slice(&mut self, values: &[f32]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_f32_slice::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of double-precision floats in the byte order `E` to this AsyncWriter
+            fn write_f64_slice<E: ByteOrder>(&mut self, values: &[f64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_f64_slice", "write_le_f64_slice"))).await
+                }
+            }
+
+            /// Writes a slice of little-endian double-precision floats to this AsyncWriter
+            fn write_le_f64_slice(&mut self, values: &[f64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_f64_slice::<LittleEndian>(self, values).await
+                }
+            }
+
+            /// Writes a slice of big-endian double-precision floats to this AsyncWriter
+            fn write_be_f64_slice(&mut self, values: &[f64]) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    Self::write_f64_slice::<BigEndian>(self, values).await
+                }
+            }
+
+            /// Writes an unsigned 32-bit VarInt to this AsyncWriter
+            fn write_varint_u32(&mut self, value: u32) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::varint(value as u64, "write_varint_u32")).await
+                }
+            }
+
+            /// Writes a signed 32-bit VarInt, stored in two's complement, to this AsyncWriter
+            fn write_varint_i32(&mut self, value: i32) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::varint(value as u32 as u64, "write_varint_i32")).await
+                }
+            }
+
+            /// Writes an unsigned 64-bit VarLong to this AsyncWriter
+            fn write_varint_u64(&mut self, value: u64) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::varint(value, "write_varint_u64")).await
+                }
+            }
+
+            /// Writes a signed 64-bit VarLong, stored in two's complement, to this AsyncWriter
+            fn write_varint_i64(&mut self, value: i64) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::varint(value as u64, "write_varint_i64")).await
+                }
+            }
+
+            /// Writes a signed 32-bit ZigZag-encoded VarInt to this AsyncWriter
+            fn write_zigzag_i32(&mut self, value: i32) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::varint(varint::encode_zigzag_i32(value) as u64, "write_zigzag_i32")).await
+                }
+            }
+
+            /// Writes a signed 64-bit ZigZag-encoded VarLong to this AsyncWriter
+            fn write_zigzag_i64(&mut self, value: i64) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::varint(varint::encode_zigzag_i64(value), "write_zigzag_i64")).await
+                }
+            }
+
+            /// Writes a length prefix of the given type to this AsyncWriter
+            fn write_length_prefix(&mut self, prefix: LengthPrefix, length: usize) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &Encoded::length(prefix, length, "write_length_prefix")?).await
+                }
+            }
+
+            /// Writes a length-prefixed UTF-8 string to this AsyncWriter
+            ///
+            /// Strings longer than the prefix can describe are rejected before anything is written
+            fn write_string(&mut self, value: &str, prefix: LengthPrefix) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &codec::write_string(value, prefix)?).await
+                }
+            }
+
+            /// Writes a string the way Java's `DataOutput.writeUTF` does to this AsyncWriter
+            ///
+            /// That is a big-endian u16 byte length followed by modified UTF-8. Strings whose
+            /// encoding is longer than 65535 bytes are rejected before anything is written
+            fn write_java_utf(&mut self, value: &str) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &codec::write_java_utf(value)?).await
+                }
+            }
+
+            /// Writes a UTF-16 string prefixed with its length in code units to this AsyncWriter
+            ///
+            /// When `bom` is set, a byte order mark is written first and counted in the length
+            fn write_utf16(&mut self, value: &str, prefix: LengthPrefix, endian: Endian, bom: bool) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &codec::write_utf16(value, prefix, endian, bom)?).await
+                }
+            }
+
+            /// Writes a UTF-16 string terminated by a NUL code unit to this AsyncWriter
+            ///
+            /// When `bom` is set, a byte order mark is written first. Strings that contain NUL
+            /// are rejected, since they could not be read back
+            fn write_utf16_nul(&mut self, value: &str, endian: Endian, bom: bool) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &codec::write_utf16_nul(value, endian, bom)?).await
+                }
+            }
+
+            /// Writes a NUL-terminated string to this AsyncWriter
+            ///
+            /// Strings that contain NUL, or characters the encoding cannot represent, are
+            /// rejected before anything is written
+            fn write_cstring(&mut self, value: &str, encoding: TextEncoding) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &codec::write_cstring(value, encoding)?).await
+                }
+            }
+
+            /// Writes a string into a fixed-width field of this AsyncWriter, padded with `pad_byte`
+            ///
+            /// Values whose encoding is longer than `width` are rejected rather than truncated.
+            /// Trailing `pad_byte`s in the value itself cannot be told apart from padding when
+            /// the field is read back
+            fn write_fixed_str(&mut self, value: &str, width: usize, pad_byte: u8, encoding: TextEncoding) -> impl Future<Output = Result<()>> $($bound)* {
+                async move {
+                    write_encoded(self, &codec::write_fixed_str(value, width, pad_byte, encoding)?).await
+                }
+            }
+
+        }
+
+        impl<T> $writer for T where T: $write + Unpin $($bound)* { }
+
+    }
+}
+
+pub(crate) use async_extensions;
+pub(crate) use async_traits;
@@ -0,0 +1,685 @@
+//! Bit-level reads and writes
+//!
+//! BitReader and BitWriter wrap a Reader or Writer and move values of any width from 0
+//! to 64 bits. The order bits are packed into each byte is chosen when they are
+//! created: `MsbFirst` suits most media formats, such as H.264 headers, while
+//! `LsbFirst` suits DEFLATE and similar codecs.
+
+use crate::endian::Endian;
+use crate::error::{Error, Result};
+use crate::reader::{self, Reader};
+use crate::writer::{self, Writer};
+
+/// The order bits are packed into each byte
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BitOrder {
+
+    /// The first bit is the most significant bit of each byte
+    MsbFirst,
+
+    /// The first bit is the least significant bit of each byte
+    LsbFirst
+
+}
+
+impl BitOrder {
+
+    /// Picks the bit order matching the given byte order
+    pub fn from_endian(endian: Endian) -> BitOrder {
+        match endian {
+            Endian::Big => BitOrder::MsbFirst,
+            Endian::Little => BitOrder::LsbFirst
+        }
+    }
+
+}
+
+/// Reads values bit by bit from a Reader
+///
+/// Bytes are pulled from the underlying Reader one at a time as they are needed, so
+/// wrapping an unbuffered source such as a File in a BufReader is recommended.
+#[derive(Debug)]
+pub struct BitReader<R: Reader> {
+    reader: R,
+    order: BitOrder,
+    byte: u8,
+    available: u32
+}
+
+impl<R: Reader> BitReader<R> {
+
+    /// Creates a BitReader that unpacks bits in the given order
+    pub fn new(reader: R, order: BitOrder) -> BitReader<R> {
+        BitReader { reader, order, byte: 0, available: 0 }
+    }
+
+    /// Returns the order bits are unpacked in
+    pub fn bit_order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// Returns true if the next bit is the first bit of a byte
+    pub fn is_aligned(&self) -> bool {
+        self.available == 0
+    }
+
+    /// Returns a reference to the underlying Reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the underlying Reader, discarding any unread bits of the current byte
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads a single bit
+    pub fn read_bit(&mut self) -> Result<bool> {
+        self.take(1, "read_bit", &mut 0).map(|bit| bit == 1)
+    }
+
+    /// Reads an unsigned value of the given number of bits, up to 64
+    pub fn read_bits(&mut self, count: u32) -> Result<u64> {
+
+        check_count(count, "read_bits")?;
+
+        self.take(count, "read_bits", &mut 0)
+
+    }
+
+    /// Reads a two's complement value of the given number of bits, up to 64
+    pub fn read_signed_bits(&mut self, count: u32) -> Result<i64> {
+
+        check_count(count, "read_signed_bits")?;
+
+        let value = self.take(count, "read_signed_bits", &mut 0)?;
+
+        Ok(match count {
+            0 => 0,
+            _ => ((value << (64 - count)) as i64) >> (64 - count)
+        })
+
+    }
+
+    /// Discards the rest of the current byte, if any
+    pub fn align_to_byte(&mut self) {
+        self.available = 0;
+    }
+
+    /// Reads an unsigned Exp-Golomb code, as used by H.264 and HEVC
+    ///
+    /// Codes are taken in stream order whatever the bit order, and must decode to a
+    /// value that fits in 64 bits
+    pub fn read_exp_golomb(&mut self) -> Result<u64> {
+        self.take_exp_golomb("read_exp_golomb")
+    }
+
+    /// Reads a signed Exp-Golomb code, as used by H.264 and HEVC
+    pub fn read_signed_exp_golomb(&mut self) -> Result<i64> {
+
+        let code = self.take_exp_golomb("read_signed_exp_golomb")?;
+
+        Ok(match code & 1 {
+            1 => ((code >> 1) + 1) as i64,
+            _ => -((code >> 1) as i64)
+        })
+
+    }
+
+    /// Reads an Exp-Golomb code, counting the bytes it pulls in
+    fn take_exp_golomb(&mut self, operation: &'static str) -> Result<u64> {
+
+        let mut fetched = 0;
+        let mut zeros = 0;
+
+        while self.take(1, operation, &mut fetched)? == 0 {
+
+            zeros += 1;
+
+            if zeros > 63 {
+                return Err(Error::OutOfRange {
+                    operation,
+                    offset: fetched.saturating_sub(1),
+                    reason: "Exp-Golomb code exceeds 64 bits"
+                });
+            }
+
+        }
+
+        let mut value = 1u64;
+
+        for _ in 0..zeros {
+            value = (value << 1) | self.take(1, operation, &mut fetched)?;
+        }
+
+        Ok(value - 1)
+
+    }
+
+    /// Unpacks the given number of bits, pulling in bytes as needed
+    fn take(&mut self, count: u32, operation: &'static str, fetched: &mut u64) -> Result<u64> {
+
+        let mut value = 0u64;
+        let mut remaining = count;
+
+        while remaining > 0 {
+
+            if self.available == 0 {
+
+                let mut raw_buffer = [0u8; 1];
+
+                reader::fill_buffer(&mut self.reader, &mut raw_buffer, operation)
+                    .map_err(|error| error.with_base_offset(*fetched))?;
+
+                self.byte = raw_buffer[0];
+                self.available = 8;
+                *fetched += 1;
+
+            }
+
+            let take = remaining.min(self.available);
+            let mask = (1u32 << take) - 1;
+
+            match self.order {
+                BitOrder::MsbFirst => {
+                    let bits = (self.byte as u32 >> (self.available - take)) & mask;
+                    value = (value << take) | bits as u64;
+                },
+                BitOrder::LsbFirst => {
+                    let bits = (self.byte as u32 >> (8 - self.available)) & mask;
+                    value |= (bits as u64) << (count - remaining);
+                }
+            }
+
+            self.available -= take;
+            remaining -= take;
+
+        }
+
+        Ok(value)
+
+    }
+
+}
+
+/// Writes values bit by bit to a Writer
+///
+/// A partial final byte is only written by `align_to_byte`, `flush` or `finish`, which
+/// pad it with zero bits. Dropping a BitWriter discards any partial byte, so call
+/// `flush` or `finish` once the last value is written.
+#[derive(Debug)]
+pub struct BitWriter<W: Writer> {
+    writer: W,
+    order: BitOrder,
+    byte: u8,
+    used: u32
+}
+
+impl<W: Writer> BitWriter<W> {
+
+    /// Creates a BitWriter that packs bits in the given order
+    pub fn new(writer: W, order: BitOrder) -> BitWriter<W> {
+        BitWriter { writer, order, byte: 0, used: 0 }
+    }
+
+    /// Returns the order bits are packed in
+    pub fn bit_order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// Returns true if the next bit will be the first bit of a byte
+    pub fn is_aligned(&self) -> bool {
+        self.used == 0
+    }
+
+    /// Returns a reference to the underlying Writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the underlying Writer
+    ///
+    /// Bytes written through it land before any partial byte still held here
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Writes a single bit
+    pub fn write_bit(&mut self, value: bool) -> Result<()> {
+        self.put(value as u64, 1, "write_bit", &mut 0)
+    }
+
+    /// Writes an unsigned value in the given number of bits, up to 64
+    ///
+    /// Values with bits set above the given width are rejected
+    pub fn write_bits(&mut self, value: u64, count: u32) -> Result<()> {
+
+        check_count(count, "write_bits")?;
+
+        if count < 64 && value >> count != 0 {
+            return Err(Error::OutOfRange {
+                operation: "write_bits",
+                offset: 0,
+                reason: "value does not fit in the given number of bits"
+            });
+        }
+
+        self.put(value, count, "write_bits", &mut 0)
+
+    }
+
+    /// Writes a two's complement value in the given number of bits, up to 64
+    ///
+    /// Values outside the range the given width can represent are rejected
+    pub fn write_signed_bits(&mut self, value: i64, count: u32) -> Result<()> {
+
+        check_count(count, "write_signed_bits")?;
+
+        let fits = match count {
+            0 => value == 0,
+            64 => true,
+            _ => value >= -(1i64 << (count - 1)) && value < (1i64 << (count - 1))
+        };
+
+        if !fits {
+            return Err(Error::OutOfRange {
+                operation: "write_signed_bits",
+                offset: 0,
+                reason: "value does not fit in the given number of bits"
+            });
+        }
+
+        let bits = match count {
+            64 => value as u64,
+            _ => value as u64 & ((1u64 << count) - 1)
+        };
+
+        self.put(bits, count, "write_signed_bits", &mut 0)
+
+    }
+
+    /// Pads the current byte with zero bits and writes it, if any bits are pending
+    pub fn align_to_byte(&mut self) -> Result<()> {
+
+        match self.used {
+            0 => Ok(()),
+            _ => self.emit("align_to_byte", &mut 0)
+        }
+
+    }
+
+    /// Writes an unsigned Exp-Golomb code, as used by H.264 and HEVC
+    ///
+    /// Codes are written in stream order whatever the bit order. `u64::MAX` has no
+    /// 64-bit code and is rejected
+    pub fn write_exp_golomb(&mut self, value: u64) -> Result<()> {
+
+        if value == u64::MAX {
+            return Err(Error::OutOfRange {
+                operation: "write_exp_golomb",
+                offset: 0,
+                reason: "Exp-Golomb code exceeds 64 bits"
+            });
+        }
+
+        self.put_exp_golomb(value, "write_exp_golomb")
+
+    }
+
+    /// Writes a signed Exp-Golomb code, as used by H.264 and HEVC
+    ///
+    /// `i64::MIN` has no 64-bit code and is rejected
+    pub fn write_signed_exp_golomb(&mut self, value: i64) -> Result<()> {
+
+        let code = match value {
+            value if value > 0 => Some(value as u64 * 2 - 1),
+            value => value.unsigned_abs().checked_mul(2)
+        };
+
+        match code {
+            Some(code) => self.put_exp_golomb(code, "write_signed_exp_golomb"),
+            None => Err(Error::OutOfRange {
+                operation: "write_signed_exp_golomb",
+                offset: 0,
+                reason: "Exp-Golomb code exceeds 64 bits"
+            })
+        }
+
+    }
+
+    /// Pads and writes any partial byte, then flushes the underlying Writer
+    ///
+    /// The next bit starts a new byte, as after `align_to_byte`
+    pub fn flush(&mut self) -> Result<()> {
+
+        let mut emitted = 0;
+
+        if self.used > 0 {
+            self.emit("flush", &mut emitted)?;
+        }
+
+        self.writer.flush().map_err(|error| Error::Io { operation: "flush", offset: emitted, error })
+
+    }
+
+    /// Pads and writes any partial byte, then returns the underlying Writer
+    pub fn finish(mut self) -> Result<W> {
+
+        if self.used > 0 {
+            self.emit("finish", &mut 0)?;
+        }
+
+        Ok(self.writer)
+
+    }
+
+    /// Writes an Exp-Golomb code for a value below `u64::MAX`
+    fn put_exp_golomb(&mut self, value: u64, operation: &'static str) -> Result<()> {
+
+        let mut emitted = 0;
+        let code = value + 1;
+        let length = 64 - code.leading_zeros();
+
+        for _ in 1..length {
+            self.put(0, 1, operation, &mut emitted)?;
+        }
+
+        for bit in (0..length).rev() {
+            self.put((code >> bit) & 1, 1, operation, &mut emitted)?;
+        }
+
+        Ok(())
+
+    }
+
+    /// Packs the low `count` bits of the value, writing out each byte as it fills
+    fn put(&mut self, value: u64, count: u32, operation: &'static str, emitted: &mut u64) -> Result<()> {
+
+        let mut remaining = count;
+
+        while remaining > 0 {
+
+            let take = remaining.min(8 - self.used);
+            let mask = (1u64 << take) - 1;
+
+            match self.order {
+                BitOrder::MsbFirst => {
+                    let bits = (value >> (remaining - take)) & mask;
+                    self.byte |= (bits as u8) << (8 - self.used - take);
+                },
+                BitOrder::LsbFirst => {
+                    let bits = (value >> (count - remaining)) & mask;
+                    self.byte |= (bits as u8) << self.used;
+                }
+            }
+
+            self.used += take;
+            remaining -= take;
+
+            if self.used == 8 {
+                self.emit(operation, emitted)?;
+            }
+
+        }
+
+        Ok(())
+
+    }
+
+    /// Writes out the current byte
+    fn emit(&mut self, operation: &'static str, emitted: &mut u64) -> Result<()> {
+
+        writer::write_buffer(&mut self.writer, &[self.byte], operation)
+            .map_err(|error| error.with_base_offset(*emitted))?;
+
+        self.byte = 0;
+        self.used = 0;
+        *emitted += 1;
+
+        Ok(())
+
+    }
+
+}
+
+/// Rejects bit counts wider than a u64
+fn check_count(count: u32, operation: &'static str) -> Result<()> {
+
+    if count > 64 {
+        return Err(Error::OutOfRange {
+            operation,
+            offset: 0,
+            reason: "bit count exceeds 64"
+        });
+    }
+
+    Ok(())
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::io::{BufWriter, Cursor, ErrorKind};
+
+    #[test]
+    fn test_msb_first() {
+
+        let mut writer = BitWriter::new(Vec::new(), BitOrder::MsbFirst);
+
+        assert!(writer.write_bits(0b101, 3).is_ok());
+        assert!(writer.write_bits(0b11111, 5).is_ok());
+        assert!(writer.write_bits(0x123, 12).is_ok());
+
+        let bytes = writer.finish().unwrap();
+
+        assert_eq!(vec![0xBF, 0x12, 0x30], bytes);
+
+        let mut reader = BitReader::new(Cursor::new(bytes), BitOrder::MsbFirst);
+
+        assert_eq!(0b101, reader.read_bits(3).unwrap());
+        assert_eq!(0b11111, reader.read_bits(5).unwrap());
+        assert_eq!(0x123, reader.read_bits(12).unwrap());
+        assert_eq!(0, reader.read_bits(4).unwrap());
+        assert!(reader.read_bit().is_err());
+
+    }
+
+    #[test]
+    fn test_lsb_first() {
+
+        let mut writer = BitWriter::new(Vec::new(), BitOrder::LsbFirst);
+
+        assert!(writer.write_bits(0b101, 3).is_ok());
+        assert!(writer.write_bits(0b11111, 5).is_ok());
+        assert!(writer.write_bits(0x123, 12).is_ok());
+
+        let bytes = writer.finish().unwrap();
+
+        assert_eq!(vec![0xFD, 0x23, 0x01], bytes);
+
+        let mut reader = BitReader::new(Cursor::new(bytes), BitOrder::LsbFirst);
+
+        assert_eq!(0b101, reader.read_bits(3).unwrap());
+        assert_eq!(0b11111, reader.read_bits(5).unwrap());
+        assert_eq!(0x123, reader.read_bits(12).unwrap());
+
+    }
+
+    #[test]
+    fn test_full_width() {
+
+        for order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+
+            let mut writer = BitWriter::new(Vec::new(), order);
+
+            writer.write_bit(true).unwrap();
+            writer.write_bits(0x0123_4567_89AB_CDEF, 64).unwrap();
+            writer.write_signed_bits(i64::MIN, 64).unwrap();
+
+            let mut reader = BitReader::new(Cursor::new(writer.finish().unwrap()), order);
+
+            assert!(reader.read_bit().unwrap());
+            assert_eq!(0x0123_4567_89AB_CDEF, reader.read_bits(64).unwrap());
+            assert_eq!(i64::MIN, reader.read_signed_bits(64).unwrap());
+
+        }
+
+    }
+
+    #[test]
+    fn test_signed_bits() {
+
+        let mut writer = BitWriter::new(Vec::new(), BitOrder::MsbFirst);
+
+        assert!(writer.write_signed_bits(-1, 3).is_ok());
+        assert!(writer.write_signed_bits(3, 3).is_ok());
+        assert!(writer.write_signed_bits(-4, 3).is_ok());
+        assert!(writer.write_signed_bits(4, 3).is_err());
+        assert!(writer.write_signed_bits(-5, 3).is_err());
+
+        let mut reader = BitReader::new(Cursor::new(writer.finish().unwrap()), BitOrder::MsbFirst);
+
+        assert_eq!(-1, reader.read_signed_bits(3).unwrap());
+        assert_eq!(3, reader.read_signed_bits(3).unwrap());
+        assert_eq!(-4, reader.read_signed_bits(3).unwrap());
+
+    }
+
+    #[test]
+    fn test_out_of_range() {
+
+        let mut writer = BitWriter::new(Vec::new(), BitOrder::MsbFirst);
+
+        assert_eq!(ErrorKind::InvalidData, writer.write_bits(8, 3).unwrap_err().kind());
+        assert_eq!(ErrorKind::InvalidData, writer.write_bits(0, 65).unwrap_err().kind());
+        assert!(writer.is_aligned());
+
+        let mut reader = BitReader::new(Cursor::new(vec![0u8; 16]), BitOrder::MsbFirst);
+
+        assert_eq!(ErrorKind::InvalidData, reader.read_bits(65).unwrap_err().kind());
+
+    }
+
+    #[test]
+    fn test_alignment() {
+
+        let mut writer = BitWriter::new(Vec::new(), BitOrder::MsbFirst);
+
+        writer.write_bit(true).unwrap();
+        writer.align_to_byte().unwrap();
+        writer.align_to_byte().unwrap();
+        writer.write_bits(0xAA, 8).unwrap();
+
+        assert!(writer.is_aligned());
+        assert_eq!(&vec![0x80, 0xAA], writer.get_ref());
+
+        let mut reader = BitReader::new(Cursor::new(vec![0xFF, 0x01]), BitOrder::LsbFirst);
+
+        assert!(reader.read_bit().unwrap());
+        reader.align_to_byte();
+        assert!(reader.is_aligned());
+        assert_eq!(1, reader.read_bits(8).unwrap());
+
+    }
+
+    #[test]
+    fn test_flush() {
+
+        let mut writer = BitWriter::new(BufWriter::new(Vec::new()), BitOrder::LsbFirst);
+
+        writer.write_bits(0b101, 3).unwrap();
+        writer.flush().unwrap();
+
+        assert!(writer.is_aligned());
+        assert_eq!(&vec![0b101], writer.get_ref().get_ref());
+
+        writer.get_mut().write_u8(0xFF).unwrap();
+        writer.write_bit(true).unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(&vec![0b101, 0xFF, 0x01], writer.get_ref().get_ref());
+
+    }
+
+    #[test]
+    fn test_exp_golomb() {
+
+        let mut writer = BitWriter::new(Vec::new(), BitOrder::MsbFirst);
+
+        for value in 0..4 {
+            writer.write_exp_golomb(value).unwrap();
+        }
+
+        // 1 010 011 00100, padded
+        assert_eq!(vec![0xA6, 0x40], writer.finish().unwrap());
+
+        for order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+
+            let mut writer = BitWriter::new(Vec::new(), order);
+            let values = [0, 1, 2, 255, 1 << 40, u64::MAX - 1];
+
+            for value in values.iter() {
+                writer.write_exp_golomb(*value).unwrap();
+            }
+
+            let mut reader = BitReader::new(Cursor::new(writer.finish().unwrap()), order);
+
+            for value in values.iter() {
+                assert_eq!(*value, reader.read_exp_golomb().unwrap());
+            }
+
+        }
+
+        let mut writer = BitWriter::new(Vec::new(), BitOrder::MsbFirst);
+
+        assert!(writer.write_exp_golomb(u64::MAX).is_err());
+
+    }
+
+    #[test]
+    fn test_signed_exp_golomb() {
+
+        let mut writer = BitWriter::new(Vec::new(), BitOrder::MsbFirst);
+        let values = [0, 1, -1, 2, -2, i64::MAX, i64::MIN + 1];
+
+        for value in values.iter() {
+            writer.write_signed_exp_golomb(*value).unwrap();
+        }
+
+        assert!(writer.write_signed_exp_golomb(i64::MIN).is_err());
+
+        let bytes = writer.finish().unwrap();
+
+        // 1 010 011 00100 00101
+        assert_eq!(&[0xA6, 0x42, 0x80], &bytes[..3]);
+
+        let mut reader = BitReader::new(Cursor::new(bytes), BitOrder::MsbFirst);
+
+        for value in values.iter() {
+            assert_eq!(*value, reader.read_signed_exp_golomb().unwrap());
+        }
+
+    }
+
+    #[test]
+    fn test_exp_golomb_errors() {
+
+        let mut reader = BitReader::new(Cursor::new(vec![0u8; 9]), BitOrder::MsbFirst);
+        let error = reader.read_exp_golomb().unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!(7, error.offset());
+
+        let mut reader = BitReader::new(Cursor::new(vec![0x00, 0x01]), BitOrder::MsbFirst);
+        let error = reader.read_exp_golomb().unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        assert_eq!(2, error.offset());
+        assert_eq!("read_exp_golomb", error.operation());
+
+    }
+
+}
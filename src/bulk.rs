@@ -0,0 +1,142 @@
+//! Byte conversions for fixed-width numbers
+//!
+//! Single values go through the standard `to_be_bytes` and `from_le_bytes` family.
+//! Slices are viewed as their underlying bytes, so that a whole slice can be filled or
+//! written with one call and converted in place
+
+use std::mem;
+use std::slice;
+
+use crate::endian::Endian;
+
+/// A fixed-width number whose every bit pattern is a valid value
+///
+/// # Safety
+///
+/// Implementors must be plain integers or floats with no padding, so that a slice of
+/// them can be viewed as bytes and filled from arbitrary bytes
+pub(crate) unsafe trait Primitive : Copy {
+
+    /// Converts between the native byte order and the given one
+    fn convert(self, endian: Endian) -> Self;
+
+}
+
+macro_rules! integer_primitive {
+    ($($type:ty),*) => {
+        $(
+            unsafe impl Primitive for $type {
+                fn convert(self, endian: Endian) -> $type {
+                    if endian == Endian::NATIVE { self } else { self.swap_bytes() }
+                }
+            }
+        )*
+    }
+}
+
+integer_primitive!(u16, i16, u32, i32, u64, i64, u128, i128);
+
+unsafe impl Primitive for f32 {
+    fn convert(self, endian: Endian) -> f32 {
+        f32::from_bits(self.to_bits().convert(endian))
+    }
+}
+
+unsafe impl Primitive for f64 {
+    fn convert(self, endian: Endian) -> f64 {
+        f64::from_bits(self.to_bits().convert(endian))
+    }
+}
+
+/// A fixed-width number that converts to and from its bytes in either byte order
+pub(crate) trait Scalar : Sized {
+
+    /// The bytes of one value
+    type Bytes: AsRef<[u8]> + AsMut<[u8]> + Default;
+
+    /// Returns the bytes of this value in the given byte order
+    fn to_bytes(self, endian: Endian) -> Self::Bytes;
+
+    /// Builds a value from its bytes in the given byte order
+    fn from_bytes(bytes: Self::Bytes, endian: Endian) -> Self;
+
+}
+
+macro_rules! scalar {
+    ($($type:ty),*) => {
+        $(
+            impl Scalar for $type {
+
+                type Bytes = [u8; mem::size_of::<$type>()];
+
+                fn to_bytes(self, endian: Endian) -> Self::Bytes {
+                    match endian {
+                        Endian::Big => self.to_be_bytes(),
+                        Endian::Little => self.to_le_bytes()
+                    }
+                }
+
+                fn from_bytes(bytes: Self::Bytes, endian: Endian) -> $type {
+                    match endian {
+                        Endian::Big => <$type>::from_be_bytes(bytes),
+                        Endian::Little => <$type>::from_le_bytes(bytes)
+                    }
+                }
+
+            }
+        )*
+    }
+}
+
+scalar!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, f32, f64);
+
+/// Views a slice of primitives as its underlying bytes
+pub(crate) fn as_bytes<T: Primitive>(values: &[T]) -> &[u8] {
+    // Primitive guarantees there is no padding, and u8 has no alignment requirement
+    unsafe { slice::from_raw_parts(values.as_ptr() as *const u8, mem::size_of_val(values)) }
+}
+
+/// Views a mutable slice of primitives as its underlying bytes
+pub(crate) fn as_bytes_mut<T: Primitive>(values: &mut [T]) -> &mut [u8] {
+    // Primitive guarantees any bytes written through the view form valid values
+    unsafe { slice::from_raw_parts_mut(values.as_mut_ptr() as *mut u8, mem::size_of_val(values)) }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+
+        assert_eq!(0x0102u16.to_be(), 0x0102u16.convert(Endian::Big));
+        assert_eq!(0x0102u16.to_le(), 0x0102u16.convert(Endian::Little));
+        assert_eq!(1.5f64, 1.5f64.convert(Endian::NATIVE));
+
+    }
+
+    #[test]
+    fn test_scalar_bytes() {
+
+        assert_eq!([0x01, 0x02], 0x0102u16.to_bytes(Endian::Big));
+        assert_eq!([0x02, 0x01], 0x0102u16.to_bytes(Endian::Little));
+        assert_eq!(-2i8, i8::from_bytes([0xFE], Endian::Big));
+        assert_eq!(1.5f32, f32::from_bytes(1.5f32.to_bytes(Endian::Little), Endian::Little));
+
+    }
+
+    #[test]
+    fn test_as_bytes() {
+
+        let mut values = [0x0102u16.to_be(), 0x0304u16.to_be()];
+
+        assert_eq!(&[0x01, 0x02, 0x03, 0x04], as_bytes(&values));
+
+        as_bytes_mut(&mut values)[3] = 0x05;
+
+        assert_eq!(0x0305, u16::from_be(values[1]));
+
+    }
+
+}
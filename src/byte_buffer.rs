@@ -0,0 +1,455 @@
+//! A growable in-memory writer with backpatching
+
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::mem;
+
+use crate::endian::{BigEndian, ByteOrder, Endian, LittleEndian};
+use crate::error::{Error, Result};
+
+/// A placeholder written into a ByteBuffer, to be filled in later
+///
+/// The type parameter is the width of the field, so a `Reservation<u32>` can only be
+/// filled with a u32. Filling consumes the reservation.
+#[derive(Debug, PartialEq, Eq)]
+#[must_use = "a reservation should be filled once its value is known"]
+pub struct Reservation<T> {
+    position: usize,
+    endian: Endian,
+    generation: u64,
+    width: PhantomData<T>
+}
+
+impl<T> Reservation<T> {
+
+    /// Returns the offset of the first byte of the placeholder
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the offset just past the placeholder, where the data it describes begins
+    pub fn end(&self) -> usize {
+        self.position + mem::size_of::<T>()
+    }
+
+}
+
+/// A Vec-backed Writer that can go back and fill in fields written earlier
+///
+/// Every Writer method is available through its `Write` implementation. Length and
+/// offset fields that are only known once the data after them has been written are
+/// reserved with `reserve_u32` and friends, then filled with `fill_u32` or
+/// `fill_u32_length`.
+#[derive(Clone, Debug, Default)]
+pub struct ByteBuffer {
+    bytes: Vec<u8>,
+    generation: u64,
+    truncations: Vec<(u64, usize)>
+}
+
+impl ByteBuffer {
+
+    /// Creates an empty ByteBuffer
+    pub fn new() -> ByteBuffer {
+        ByteBuffer::from(Vec::new())
+    }
+
+    /// Creates an empty ByteBuffer with room for the given number of bytes
+    pub fn with_capacity(capacity: usize) -> ByteBuffer {
+        ByteBuffer::from(Vec::with_capacity(capacity))
+    }
+
+    /// Returns the offset the next byte will be written at
+    pub fn position(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns the number of bytes written
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns true if nothing has been written
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns the bytes written so far
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the bytes written, consuming this ByteBuffer
+    pub fn into_vec(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Discards everything after the given number of bytes
+    ///
+    /// Reservations that pointed past the new end can no longer be filled, even once
+    /// the buffer has grown past them again
+    pub fn truncate(&mut self, length: usize) {
+
+        if length >= self.bytes.len() {
+            return;
+        }
+
+        self.bytes.truncate(length);
+        self.generation += 1;
+
+        // Only the shortest length since each generation matters, so the lengths kept
+        // here always increase along with their generations
+        while self.truncations.last().is_some_and(|&(_, shortest)| shortest >= length) {
+            self.truncations.pop();
+        }
+
+        self.truncations.push((self.generation, length));
+
+    }
+
+    /// Returns the number of bytes written after the given reservation
+    pub fn length_since<T>(&self, reservation: &Reservation<T>) -> usize {
+        self.bytes.len().saturating_sub(reservation.end())
+    }
+
+    /// Reserves an unsigned short in the given byte order
+    pub fn reserve_u16_in(&mut self, endian: Endian) -> Reservation<u16> {
+        self.reserve(endian)
+    }
+
+    /// Reserves an unsigned short in the byte order `E`
+    pub fn reserve_u16<E: ByteOrder>(&mut self) -> Reservation<u16> {
+        self.reserve_u16_in(E::ENDIAN)
+    }
+
+    /// Reserves an unsigned big-endian short
+    pub fn reserve_be_u16(&mut self) -> Reservation<u16> {
+        self.reserve_u16::<BigEndian>()
+    }
+
+    /// Reserves an unsigned little-endian short
+    pub fn reserve_le_u16(&mut self) -> Reservation<u16> {
+        self.reserve_u16::<LittleEndian>()
+    }
+
+    /// Reserves an unsigned integer in the given byte order
+    pub fn reserve_u32_in(&mut self, endian: Endian) -> Reservation<u32> {
+        self.reserve(endian)
+    }
+
+    /// Reserves an unsigned integer in the byte order `E`
+    pub fn reserve_u32<E: ByteOrder>(&mut self) -> Reservation<u32> {
+        self.reserve_u32_in(E::ENDIAN)
+    }
+
+    /// Reserves an unsigned big-endian integer
+    pub fn reserve_be_u32(&mut self) -> Reservation<u32> {
+        self.reserve_u32::<BigEndian>()
+    }
+
+    /// Reserves an unsigned little-endian integer
+    pub fn reserve_le_u32(&mut self) -> Reservation<u32> {
+        self.reserve_u32::<LittleEndian>()
+    }
+
+    /// Reserves an unsigned long in the given byte order
+    pub fn reserve_u64_in(&mut self, endian: Endian) -> Reservation<u64> {
+        self.reserve(endian)
+    }
+
+    /// Reserves an unsigned long in the byte order `E`
+    pub fn reserve_u64<E: ByteOrder>(&mut self) -> Reservation<u64> {
+        self.reserve_u64_in(E::ENDIAN)
+    }
+
+    /// Reserves an unsigned big-endian long
+    pub fn reserve_be_u64(&mut self) -> Reservation<u64> {
+        self.reserve_u64::<BigEndian>()
+    }
+
+    /// Reserves an unsigned little-endian long
+    pub fn reserve_le_u64(&mut self) -> Reservation<u64> {
+        self.reserve_u64::<LittleEndian>()
+    }
+
+    /// Fills a reserved unsigned short
+    pub fn fill_u16(&mut self, reservation: Reservation<u16>, value: u16) -> Result<()> {
+
+        let raw_buffer = match reservation.endian {
+            Endian::Big => value.to_be_bytes(),
+            Endian::Little => value.to_le_bytes()
+        };
+
+        self.patch(&reservation, &raw_buffer, "fill_u16")
+
+    }
+
+    /// Fills a reserved unsigned integer
+    pub fn fill_u32(&mut self, reservation: Reservation<u32>, value: u32) -> Result<()> {
+
+        let raw_buffer = match reservation.endian {
+            Endian::Big => value.to_be_bytes(),
+            Endian::Little => value.to_le_bytes()
+        };
+
+        self.patch(&reservation, &raw_buffer, "fill_u32")
+
+    }
+
+    /// Fills a reserved unsigned long
+    pub fn fill_u64(&mut self, reservation: Reservation<u64>, value: u64) -> Result<()> {
+
+        let raw_buffer = match reservation.endian {
+            Endian::Big => value.to_be_bytes(),
+            Endian::Little => value.to_le_bytes()
+        };
+
+        self.patch(&reservation, &raw_buffer, "fill_u64")
+
+    }
+
+    /// Fills a reserved unsigned short with the number of bytes written after it
+    pub fn fill_u16_length(&mut self, reservation: Reservation<u16>) -> Result<()> {
+
+        let length = self.length_since(&reservation);
+
+        if length > u16::MAX as usize {
+            return Err(Error::LengthLimitExceeded {
+                operation: "fill_u16_length",
+                offset: reservation.position as u64,
+                length: length as u64,
+                limit: u16::MAX as u64
+            });
+        }
+
+        self.fill_u16(reservation, length as u16)
+
+    }
+
+    /// Fills a reserved unsigned integer with the number of bytes written after it
+    pub fn fill_u32_length(&mut self, reservation: Reservation<u32>) -> Result<()> {
+
+        let length = self.length_since(&reservation);
+
+        if length as u64 > u32::MAX as u64 {
+            return Err(Error::LengthLimitExceeded {
+                operation: "fill_u32_length",
+                offset: reservation.position as u64,
+                length: length as u64,
+                limit: u32::MAX as u64
+            });
+        }
+
+        self.fill_u32(reservation, length as u32)
+
+    }
+
+    /// Fills a reserved unsigned long with the number of bytes written after it
+    pub fn fill_u64_length(&mut self, reservation: Reservation<u64>) -> Result<()> {
+        let length = self.length_since(&reservation) as u64;
+        self.fill_u64(reservation, length)
+    }
+
+    /// Writes a zeroed placeholder the width of `T`
+    fn reserve<T>(&mut self, endian: Endian) -> Reservation<T> {
+
+        let position = self.bytes.len();
+
+        self.bytes.resize(position + mem::size_of::<T>(), 0);
+
+        Reservation { position, endian, generation: self.generation, width: PhantomData }
+
+    }
+
+    /// Overwrites the placeholder of a reservation that has not been truncated away
+    fn patch<T>(&mut self, reservation: &Reservation<T>, raw_buffer: &[u8], operation: &'static str) -> Result<()> {
+
+        let later = self.truncations.partition_point(|&(generation, _)| generation <= reservation.generation);
+        let truncated = self.truncations.get(later).is_some_and(|&(_, shortest)| shortest < reservation.end());
+
+        match self.bytes.get_mut(reservation.position..reservation.end()) {
+            Some(target) if !truncated => {
+                target.copy_from_slice(raw_buffer);
+                Ok(())
+            },
+            _ => Err(Error::OutOfRange {
+                operation,
+                offset: reservation.position as u64,
+                reason: "reservation was truncated away"
+            })
+        }
+
+    }
+
+}
+
+impl Write for ByteBuffer {
+
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.bytes.extend_from_slice(buffer);
+        Ok(buffer.len())
+    }
+
+    fn write_all(&mut self, buffer: &[u8]) -> io::Result<()> {
+        self.bytes.extend_from_slice(buffer);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+}
+
+impl PartialEq for ByteBuffer {
+
+    /// ByteBuffers are equal when they hold the same bytes
+    fn eq(&self, other: &ByteBuffer) -> bool {
+        self.bytes == other.bytes
+    }
+
+}
+
+impl Eq for ByteBuffer { }
+
+impl From<Vec<u8>> for ByteBuffer {
+
+    /// Creates a ByteBuffer that appends to the given bytes
+    fn from(bytes: Vec<u8>) -> ByteBuffer {
+        ByteBuffer { bytes, generation: 0, truncations: Vec::new() }
+    }
+
+}
+
+impl From<ByteBuffer> for Vec<u8> {
+
+    fn from(buffer: ByteBuffer) -> Vec<u8> {
+        buffer.bytes
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::prefix::LengthPrefix;
+    use crate::writer::Writer;
+
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_writer_methods() {
+
+        let mut buffer = ByteBuffer::new();
+
+        assert!(buffer.write_u8(1).is_ok());
+        assert!(buffer.write_le_u16(0x0203).is_ok());
+        assert!(buffer.write_string("hi", LengthPrefix::U8).is_ok());
+
+        assert_eq!(6, buffer.position());
+        assert_eq!(&[1, 3, 2, 2, b'h', b'i'], buffer.as_slice());
+
+    }
+
+    #[test]
+    fn test_fill() {
+
+        let mut buffer = ByteBuffer::new();
+
+        let big = buffer.reserve_be_u32();
+        let little = buffer.reserve_le_u16();
+
+        assert_eq!(4, little.position());
+        assert_eq!(6, little.end());
+        assert_eq!(vec![0; 6], buffer.as_slice());
+
+        assert!(buffer.fill_u16(little, 0x0102).is_ok());
+        assert!(buffer.fill_u32(big, 0x03040506).is_ok());
+
+        assert_eq!(vec![3, 4, 5, 6, 2, 1], buffer.into_vec());
+
+    }
+
+    #[test]
+    fn test_fill_length() {
+
+        let mut buffer = ByteBuffer::new();
+
+        buffer.write_u8(0xFF).unwrap();
+
+        let length = buffer.reserve_be_u32();
+
+        buffer.write_be_u64(0).unwrap();
+        buffer.write_u8(0).unwrap();
+
+        assert_eq!(9, buffer.length_since(&length));
+        assert!(buffer.fill_u32_length(length).is_ok());
+        assert_eq!(&[0xFF, 0, 0, 0, 9], &buffer.as_slice()[..5]);
+
+    }
+
+    #[test]
+    fn test_fill_length_too_long() {
+
+        let mut buffer = ByteBuffer::new();
+
+        let length = buffer.reserve_le_u16();
+
+        buffer.write_all(&[0u8; 65536]).unwrap();
+
+        let error = buffer.fill_u16_length(length).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!(0, error.offset());
+
+    }
+
+    #[test]
+    fn test_fill_after_truncate() {
+
+        let mut buffer = ByteBuffer::with_capacity(16);
+
+        buffer.write_u8(1).unwrap();
+
+        let reservation = buffer.reserve_u64::<BigEndian>();
+
+        buffer.truncate(3);
+
+        assert_eq!(3, buffer.len());
+        assert!(buffer.fill_u64(reservation, 0).is_err());
+        assert_eq!(&[1, 0, 0], buffer.as_slice());
+
+    }
+
+    #[test]
+    fn test_fill_after_truncate_and_regrow() {
+
+        let mut buffer = ByteBuffer::new();
+
+        let outer = buffer.reserve_be_u16();
+        let inner = buffer.reserve_be_u32();
+
+        buffer.write_u8(1).unwrap();
+        buffer.truncate(inner.position());
+        buffer.write_all(&[2; 8]).unwrap();
+
+        let error = buffer.fill_u32(inner, 0xFFFFFFFF).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!(2, error.offset());
+        assert_eq!(&[0, 0, 2, 2, 2, 2, 2, 2, 2, 2], buffer.as_slice());
+
+        let later = buffer.reserve_be_u16();
+
+        buffer.truncate(9);
+        buffer.write_all(&[3; 4]).unwrap();
+
+        assert!(buffer.fill_u16_length(outer).is_ok());
+        assert!(buffer.fill_u16(later, 0).is_err());
+        assert_eq!(&[0, 11, 2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3], buffer.as_slice());
+
+    }
+
+}
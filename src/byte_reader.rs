@@ -0,0 +1,781 @@
+//! Zero-copy decoding of byte slices
+
+use std::io::{self, Read};
+use std::mem;
+use std::str;
+
+use crate::endian::{BigEndian, ByteOrder, Endian, LittleEndian};
+use crate::error::{Error, Result};
+use crate::prefix::LengthPrefix;
+use crate::reader::{self, Reader};
+use crate::text::{SurrogatePolicy, TextEncoding};
+use crate::value::Readable;
+
+/// Decodes values directly from a borrowed byte slice
+///
+/// ByteReader implements `Read`, so every Reader method is available on it. On top of
+/// those it can borrow bytes and strings straight out of the slice, skip ahead and
+/// peek at values without consuming them. Every Reader method is shadowed by one that
+/// reports errors at absolute offsets into the slice; fixed-width primitives are also
+/// decoded straight from the slice. Reader methods reached through the trait, as
+/// generic code does, still count offsets from where they started.
+#[derive(Clone, Copy, Debug)]
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl<'a> ByteReader<'a> {
+
+    /// Creates a ByteReader positioned at the start of the given bytes
+    pub fn new(bytes: &'a [u8]) -> ByteReader<'a> {
+        ByteReader { bytes, position: 0 }
+    }
+
+    /// Returns the offset of the next byte to be read
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the number of bytes left to read
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    /// Returns the bytes left to read without consuming them
+    pub fn remaining_bytes(&self) -> &'a [u8] {
+        &self.bytes[self.position..]
+    }
+
+    /// Advances past the given number of bytes
+    pub fn skip(&mut self, count: usize) -> Result<()> {
+        self.take(count, "skip").map(|_| ())
+    }
+
+    /// Reads the given number of bytes, borrowing them from the underlying slice
+    pub fn read_bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+        self.take(count, "read_bytes")
+    }
+
+    /// Reads a length-prefixed UTF-8 string, borrowing it from the underlying slice
+    ///
+    /// Lengths above `max_length` bytes are rejected. Nothing is consumed if the string
+    /// cannot be read
+    pub fn read_str(&mut self, prefix: LengthPrefix, max_length: usize) -> Result<&'a str> {
+
+        let start = self.position;
+
+        let result = self.read_str_from(start, prefix, max_length);
+
+        if result.is_err() {
+            self.position = start;
+        }
+
+        result
+
+    }
+
+    /// Reads a length-prefixed UTF-8 string starting at `start`, which must be the current position
+    fn read_str_from(&mut self, start: usize, prefix: LengthPrefix, max_length: usize) -> Result<&'a str> {
+
+        let length = reader::read_length(self, prefix, "read_str")
+            .map_err(|error| error.with_base_offset(start as u64))?;
+
+        if length > max_length {
+            return Err(Error::LengthLimitExceeded {
+                operation: "read_str",
+                offset: start as u64,
+                length: length as u64,
+                limit: max_length as u64
+            });
+        }
+
+        let body = self.position;
+        let bytes = self.take(length, "read_str")?;
+
+        str::from_utf8(bytes).map_err(|error| Error::InvalidEncoding {
+            operation: "read_str",
+            offset: (body + error.valid_up_to()) as u64,
+            reason: "invalid UTF-8"
+        })
+
+    }
+
+    /// Reads a NUL-terminated UTF-8 string, borrowing it from the underlying slice
+    ///
+    /// The terminator is consumed but not included. Strings with more than
+    /// `max_length` bytes before the terminator are rejected
+    pub fn read_cstr(&mut self, max_length: usize) -> Result<&'a str> {
+
+        let start = self.position;
+        let search = &self.remaining_bytes()[..self.remaining().min(max_length.saturating_add(1))];
+
+        let length = match search.iter().position(|byte| *byte == 0) {
+            Some(length) => length,
+            None if search.len() > max_length => {
+                return Err(Error::LengthLimitExceeded {
+                    operation: "read_cstr",
+                    offset: (start + max_length) as u64,
+                    length: max_length as u64 + 1,
+                    limit: max_length as u64
+                });
+            },
+            None => {
+                return Err(Error::Truncated {
+                    operation: "read_cstr",
+                    offset: self.bytes.len() as u64,
+                    needed: 1
+                });
+            }
+        };
+
+        let bytes = &self.bytes[start..start + length];
+
+        let value = str::from_utf8(bytes).map_err(|error| Error::InvalidEncoding {
+            operation: "read_cstr",
+            offset: (start + error.valid_up_to()) as u64,
+            reason: "invalid UTF-8"
+        })?;
+
+        self.position += length + 1;
+
+        Ok(value)
+
+    }
+
+    /// Runs a read against a copy of this ByteReader, leaving this one where it is
+    ///
+    /// Errors are reported at absolute offsets into the slice
+    pub fn peek<T, F>(&self, read: F) -> Result<T>
+        where F: FnOnce(&mut ByteReader<'a>) -> Result<T> {
+
+        // The copy starts at the current position, so that both the absolute offsets of
+        // its own methods and the relative ones of Reader methods count from here
+        let mut copy = ByteReader::new(self.remaining_bytes());
+
+        read(&mut copy).map_err(|error| error.with_base_offset(self.position as u64))
+
+    }
+
+    /// Returns the next unsigned byte without consuming it
+    pub fn peek_u8(&self) -> Result<u8> {
+        self.peek(|reader| reader.read_u8())
+    }
+
+    /// Returns the next signed byte without consuming it
+    pub fn peek_i8(&self) -> Result<i8> {
+        self.peek(|reader| reader.read_i8())
+    }
+
+    /// Returns the next unsigned short in the byte order `E` without consuming it
+    pub fn peek_u16<E: ByteOrder>(&self) -> Result<u16> {
+        self.peek(|reader| reader.read_u16::<E>())
+    }
+
+    /// Returns the next signed short in the byte order `E` without consuming it
+    pub fn peek_i16<E: ByteOrder>(&self) -> Result<i16> {
+        self.peek(|reader| reader.read_i16::<E>())
+    }
+
+    /// Returns the next unsigned integer in the byte order `E` without consuming it
+    pub fn peek_u32<E: ByteOrder>(&self) -> Result<u32> {
+        self.peek(|reader| reader.read_u32::<E>())
+    }
+
+    /// Returns the next signed integer in the byte order `E` without consuming it
+    pub fn peek_i32<E: ByteOrder>(&self) -> Result<i32> {
+        self.peek(|reader| reader.read_i32::<E>())
+    }
+
+    /// Returns the next unsigned long in the byte order `E` without consuming it
+    pub fn peek_u64<E: ByteOrder>(&self) -> Result<u64> {
+        self.peek(|reader| reader.read_u64::<E>())
+    }
+
+    /// Returns the next signed long in the byte order `E` without consuming it
+    pub fn peek_i64<E: ByteOrder>(&self) -> Result<i64> {
+        self.peek(|reader| reader.read_i64::<E>())
+    }
+
+    /// Returns the next unsigned 128-bit integer in the byte order `E` without consuming it
+    pub fn peek_u128<E: ByteOrder>(&self) -> Result<u128> {
+        self.peek(|reader| reader.read_u128::<E>())
+    }
+
+    /// Returns the next signed 128-bit integer in the byte order `E` without consuming it
+    pub fn peek_i128<E: ByteOrder>(&self) -> Result<i128> {
+        self.peek(|reader| reader.read_i128::<E>())
+    }
+
+    /// Returns the next single-precision float in the byte order `E` without consuming it
+    pub fn peek_f32<E: ByteOrder>(&self) -> Result<f32> {
+        self.peek(|reader| reader.read_f32::<E>())
+    }
+
+    /// Returns the next double-precision float in the byte order `E` without consuming it
+    pub fn peek_f64<E: ByteOrder>(&self) -> Result<f64> {
+        self.peek(|reader| reader.read_f64::<E>())
+    }
+
+    /// Returns the next VarInt without consuming it
+    pub fn peek_varint_u32(&self) -> Result<u32> {
+        self.peek(|reader| reader.read_varint_u32())
+    }
+
+    /// Consumes and borrows the given number of bytes
+    fn take(&mut self, count: usize, operation: &'static str) -> Result<&'a [u8]> {
+
+        if count > self.remaining() {
+            return Err(Error::Truncated {
+                operation,
+                offset: self.bytes.len() as u64,
+                needed: (count - self.remaining()) as u64
+            });
+        }
+
+        let bytes = &self.bytes[self.position..self.position + count];
+
+        self.position += count;
+
+        Ok(bytes)
+
+    }
+
+}
+
+macro_rules! slice_primitive {
+    ($type:ident, $read_in:ident, $read:ident, $read_be:ident, $read_le:ident) => {
+        impl<'a> ByteReader<'a> {
+
+            #[doc = concat!("Reads a `", stringify!($type), "` in the given byte order from the slice")]
+            pub fn $read_in(&mut self, endian: Endian) -> Result<$type> {
+
+                let mut raw_buffer = [0u8; mem::size_of::<$type>()];
+                let operation = endian.select(stringify!($read_be), stringify!($read_le));
+
+                raw_buffer.copy_from_slice(self.take(mem::size_of::<$type>(), operation)?);
+
+                Ok(match endian {
+                    Endian::Big => $type::from_be_bytes(raw_buffer),
+                    Endian::Little => $type::from_le_bytes(raw_buffer)
+                })
+
+            }
+
+            #[doc = concat!("Reads a `", stringify!($type), "` in the byte order `E` from the slice")]
+            pub fn $read<E: ByteOrder>(&mut self) -> Result<$type> {
+                self.$read_in(E::ENDIAN)
+            }
+
+            #[doc = concat!("Reads a big-endian `", stringify!($type), "` from the slice")]
+            pub fn $read_be(&mut self) -> Result<$type> {
+                self.$read::<BigEndian>()
+            }
+
+            #[doc = concat!("Reads a little-endian `", stringify!($type), "` from the slice")]
+            pub fn $read_le(&mut self) -> Result<$type> {
+                self.$read::<LittleEndian>()
+            }
+
+        }
+    }
+}
+
+impl<'a> ByteReader<'a> {
+
+    /// Reads an unsigned byte from the slice
+    pub fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.take(1, "read_u8")?[0])
+    }
+
+    /// Reads a signed byte from the slice
+    pub fn read_i8(&mut self) -> Result<i8> {
+        Ok(self.take(1, "read_i8")?[0] as i8)
+    }
+
+}
+
+slice_primitive!(u16, read_u16_in, read_u16, read_be_u16, read_le_u16);
+slice_primitive!(i16, read_i16_in, read_i16, read_be_i16, read_le_i16);
+slice_primitive!(u32, read_u32_in, read_u32, read_be_u32, read_le_u32);
+slice_primitive!(i32, read_i32_in, read_i32, read_be_i32, read_le_i32);
+slice_primitive!(u64, read_u64_in, read_u64, read_be_u64, read_le_u64);
+slice_primitive!(i64, read_i64_in, read_i64, read_be_i64, read_le_i64);
+slice_primitive!(u128, read_u128_in, read_u128, read_be_u128, read_le_u128);
+slice_primitive!(i128, read_i128_in, read_i128, read_be_i128, read_le_i128);
+slice_primitive!(f32, read_f32_in, read_f32, read_be_f32, read_le_f32);
+slice_primitive!(f64, read_f64_in, read_f64, read_be_f64, read_le_f64);
+
+/// The Reader methods that are not decoded straight from the slice, shadowed so that
+/// their errors report absolute offsets too
+impl<'a> ByteReader<'a> {
+
+    /// Fills `values` with unsigned shorts in the byte order `E` from the slice
+    pub fn read_u16_into<E: ByteOrder>(&mut self, values: &mut [u16]) -> Result<()> {
+        self.absolute(|reader| Reader::read_u16_into::<E>(reader, values))
+    }
+
+    /// Fills `values` with big-endian unsigned shorts from the slice
+    pub fn read_be_u16_into(&mut self, values: &mut [u16]) -> Result<()> {
+        self.absolute(|reader| Reader::read_be_u16_into(reader, values))
+    }
+
+    /// Fills `values` with little-endian unsigned shorts from the slice
+    pub fn read_le_u16_into(&mut self, values: &mut [u16]) -> Result<()> {
+        self.absolute(|reader| Reader::read_le_u16_into(reader, values))
+    }
+
+    /// Fills `values` with signed shorts in the byte order `E` from the slice
+    pub fn read_i16_into<E: ByteOrder>(&mut self, values: &mut [i16]) -> Result<()> {
+        self.absolute(|reader| Reader::read_i16_into::<E>(reader, values))
+    }
+
+    /// Fills `values` with big-endian signed shorts from the slice
+    pub fn read_be_i16_into(&mut self, values: &mut [i16]) -> Result<()> {
+        self.absolute(|reader| Reader::read_be_i16_into(reader, values))
+    }
+
+    /// Fills `values` with little-endian signed shorts from the slice
+    pub fn read_le_i16_into(&mut self, values: &mut [i16]) -> Result<()> {
+        self.absolute(|reader| Reader::read_le_i16_into(reader, values))
+    }
+
+    /// Fills `values` with unsigned integers in the byte order `E` from the slice
+    pub fn read_u32_into<E: ByteOrder>(&mut self, values: &mut [u32]) -> Result<()> {
+        self.absolute(|reader| Reader::read_u32_into::<E>(reader, values))
+    }
+
+    /// Fills `values` with big-endian unsigned integers from the slice
+    pub fn read_be_u32_into(&mut self, values: &mut [u32]) -> Result<()> {
+        self.absolute(|reader| Reader::read_be_u32_into(reader, values))
+    }
+
+    /// Fills `values` with little-endian unsigned integers from the slice
+    pub fn read_le_u32_into(&mut self, values: &mut [u32]) -> Result<()> {
+        self.absolute(|reader| Reader::read_le_u32_into(reader, values))
+    }
+
+    /// Fills `values` with signed integers in the byte order `E` from the slice
+    pub fn read_i32_into<E: ByteOrder>(&mut self, values: &mut [i32]) -> Result<()> {
+        self.absolute(|reader| Reader::read_i32_into::<E>(reader, values))
+    }
+
+    /// Fills `values` with big-endian signed integers from the slice
+    pub fn read_be_i32_into(&mut self, values: &mut [i32]) -> Result<()> {
+        self.absolute(|reader| Reader::read_be_i32_into(reader, values))
+    }
+
+    /// Fills `values` with little-endian signed integers from the slice
+    pub fn read_le_i32_into(&mut self, values: &mut [i32]) -> Result<()> {
+        self.absolute(|reader| Reader::read_le_i32_into(reader, values))
+    }
+
+    /// Fills `values` with unsigned longs in the byte order `E` from the slice
+    pub fn read_u64_into<E: ByteOrder>(&mut self, values: &mut [u64]) -> Result<()> {
+        self.absolute(|reader| Reader::read_u64_into::<E>(reader, values))
+    }
+
+    /// Fills `values` with big-endian unsigned longs from the slice
+    pub fn read_be_u64_into(&mut self, values: &mut [u64]) -> Result<()> {
+        self.absolute(|reader| Reader::read_be_u64_into(reader, values))
+    }
+
+    /// Fills `values` with little-endian unsigned longs from the slice
+    pub fn read_le_u64_into(&mut self, values: &mut [u64]) -> Result<()> {
+        self.absolute(|reader| Reader::read_le_u64_into(reader, values))
+    }
+
+    /// Fills `values` with signed longs in the byte order `E` from the slice
+    pub fn read_i64_into<E: ByteOrder>(&mut self, values: &mut [i64]) -> Result<()> {
+        self.absolute(|reader| Reader::read_i64_into::<E>(reader, values))
+    }
+
+    /// Fills `values` with big-endian signed longs from the slice
+    pub fn read_be_i64_into(&mut self, values: &mut [i64]) -> Result<()> {
+        self.absolute(|reader| Reader::read_be_i64_into(reader, values))
+    }
+
+    /// Fills `values` with little-endian signed longs from the slice
+    pub fn read_le_i64_into(&mut self, values: &mut [i64]) -> Result<()> {
+        self.absolute(|reader| Reader::read_le_i64_into(reader, values))
+    }
+
+    /// Fills `values` with unsigned 128-bit integers in the byte order `E` from the slice
+    pub fn read_u128_into<E: ByteOrder>(&mut self, values: &mut [u128]) -> Result<()> {
+        self.absolute(|reader| Reader::read_u128_into::<E>(reader, values))
+    }
+
+    /// Fills `values` with big-endian unsigned 128-bit integers from the slice
+    pub fn read_be_u128_into(&mut self, values: &mut [u128]) -> Result<()> {
+        self.absolute(|reader| Reader::read_be_u128_into(reader, values))
+    }
+
+    /// Fills `values` with little-endian unsigned 128-bit integers from the slice
+    pub fn read_le_u128_into(&mut self, values: &mut [u128]) -> Result<()> {
+        self.absolute(|reader| Reader::read_le_u128_into(reader, values))
+    }
+
+    /// Fills `values` with signed 128-bit integers in the byte order `E` from the slice
+    pub fn read_i128_into<E: ByteOrder>(&mut self, values: &mut [i128]) -> Result<()> {
+        self.absolute(|reader| Reader::read_i128_into::<E>(reader, values))
+    }
+
+    /// Fills `values` with big-endian signed 128-bit integers from the slice
+    pub fn read_be_i128_into(&mut self, values: &mut [i128]) -> Result<()> {
+        self.absolute(|reader| Reader::read_be_i128_into(reader, values))
+    }
+
+    /// Fills `values` with little-endian signed 128-bit integers from the slice
+    pub fn read_le_i128_into(&mut self, values: &mut [i128]) -> Result<()> {
+        self.absolute(|reader| Reader::read_le_i128_into(reader, values))
+    }
+
+    /// Fills `values` with single-precision floats in the byte order `E` from the slice
+    pub fn read_f32_into<E: ByteOrder>(&mut self, values: &mut [f32]) -> Result<()> {
+        self.absolute(|reader| Reader::read_f32_into::<E>(reader, values))
+    }
+
+    /// Fills `values` with big-endian single-precision floats from the slice
+    pub fn read_be_f32_into(&mut self, values: &mut [f32]) -> Result<()> {
+        self.absolute(|reader| Reader::read_be_f32_into(reader, values))
+    }
+
+    /// Fills `values` with little-endian single-precision floats from the slice
+    pub fn read_le_f32_into(&mut self, values: &mut [f32]) -> Result<()> {
+        self.absolute(|reader| Reader::read_le_f32_into(reader, values))
+    }
+
+    /// Fills `values` with double-precision floats in the byte order `E` from the slice
+    pub fn read_f64_into<E: ByteOrder>(&mut self, values: &mut [f64]) -> Result<()> {
+        self.absolute(|reader| Reader::read_f64_into::<E>(reader, values))
+    }
+
+    /// Fills `values` with big-endian double-precision floats from the slice
+    pub fn read_be_f64_into(&mut self, values: &mut [f64]) -> Result<()> {
+        self.absolute(|reader| Reader::read_be_f64_into(reader, values))
+    }
+
+    /// Fills `values` with little-endian double-precision floats from the slice
+    pub fn read_le_f64_into(&mut self, values: &mut [f64]) -> Result<()> {
+        self.absolute(|reader| Reader::read_le_f64_into(reader, values))
+    }
+
+    /// Reads an unsigned 32-bit VarInt from the slice
+    pub fn read_varint_u32(&mut self) -> Result<u32> {
+        self.absolute(Reader::read_varint_u32)
+    }
+
+    /// Reads a signed 32-bit VarInt, stored in two's complement, from the slice
+    pub fn read_varint_i32(&mut self) -> Result<i32> {
+        self.absolute(Reader::read_varint_i32)
+    }
+
+    /// Reads an unsigned 64-bit VarLong from the slice
+    pub fn read_varint_u64(&mut self) -> Result<u64> {
+        self.absolute(Reader::read_varint_u64)
+    }
+
+    /// Reads a signed 64-bit VarLong, stored in two's complement, from the slice
+    pub fn read_varint_i64(&mut self) -> Result<i64> {
+        self.absolute(Reader::read_varint_i64)
+    }
+
+    /// Reads a signed 32-bit ZigZag-encoded VarInt from the slice
+    pub fn read_zigzag_i32(&mut self) -> Result<i32> {
+        self.absolute(Reader::read_zigzag_i32)
+    }
+
+    /// Reads a signed 64-bit ZigZag-encoded VarLong from the slice
+    pub fn read_zigzag_i64(&mut self) -> Result<i64> {
+        self.absolute(Reader::read_zigzag_i64)
+    }
+
+    /// Reads a length prefix of the given type from the slice
+    pub fn read_length_prefix(&mut self, prefix: LengthPrefix) -> Result<usize> {
+        self.absolute(|reader| Reader::read_length_prefix(reader, prefix))
+    }
+
+    /// Reads a length-prefixed UTF-8 string from the slice
+    pub fn read_string(&mut self, prefix: LengthPrefix, max_length: usize) -> Result<String> {
+        self.absolute(|reader| Reader::read_string(reader, prefix, max_length))
+    }
+
+    /// Reads a string written by Java's `DataOutput.writeUTF` from the slice
+    pub fn read_java_utf(&mut self) -> Result<String> {
+        self.absolute(Reader::read_java_utf)
+    }
+
+    /// Reads a UTF-16 string prefixed with its length in code units from the slice
+    pub fn read_utf16(
+        &mut self,
+        prefix: LengthPrefix,
+        max_units: usize,
+        endian: Endian,
+        detect_bom: bool,
+        policy: SurrogatePolicy
+    ) -> Result<String> {
+        self.absolute(|reader| Reader::read_utf16(reader, prefix, max_units, endian, detect_bom, policy))
+    }
+
+    /// Reads a UTF-16 string terminated by a NUL code unit from the slice
+    pub fn read_utf16_nul(
+        &mut self,
+        max_units: usize,
+        endian: Endian,
+        detect_bom: bool,
+        policy: SurrogatePolicy
+    ) -> Result<String> {
+        self.absolute(|reader| Reader::read_utf16_nul(reader, max_units, endian, detect_bom, policy))
+    }
+
+    /// Reads a NUL-terminated string from the slice
+    pub fn read_cstring(&mut self, max_length: usize, encoding: TextEncoding) -> Result<String> {
+        self.absolute(|reader| Reader::read_cstring(reader, max_length, encoding))
+    }
+
+    /// Reads a string stored in a fixed-width field from the slice
+    pub fn read_fixed_str(&mut self, width: usize, pad_byte: u8, encoding: TextEncoding) -> Result<String> {
+        self.absolute(|reader| Reader::read_fixed_str(reader, width, pad_byte, encoding))
+    }
+
+    /// Reads a value of any Readable type from the slice
+    pub fn read_value<T: Readable>(&mut self) -> Result<T> {
+        self.absolute(Reader::read_value::<T>)
+    }
+
+    /// Runs a Reader method, rebasing its errors from where it started to the start of the slice
+    fn absolute<T>(&mut self, read: impl FnOnce(&mut ByteReader<'a>) -> Result<T>) -> Result<T> {
+
+        let start = self.position as u64;
+
+        read(self).map_err(|error| error.with_base_offset(start))
+
+    }
+
+}
+
+impl<'a> Read for ByteReader<'a> {
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+
+        let count = buffer.len().min(self.remaining());
+
+        buffer[..count].copy_from_slice(&self.bytes[self.position..self.position + count]);
+
+        self.position += count;
+
+        Ok(count)
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::ByteReader;
+
+    use crate::endian::{BigEndian, Endian, LittleEndian};
+    use crate::prefix::LengthPrefix;
+    use crate::reader::Reader;
+
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_primitives() {
+
+        let bytes = [0x01, 0xFE, 0xFF, 0x00, 0x00, 0x00, 0x2A, 0xDD, 0xC7, 0x01];
+        let mut reader = ByteReader::new(&bytes);
+
+        assert_eq!(1, reader.read_u8().unwrap());
+        assert_eq!(-2, reader.read_le_i16().unwrap());
+        assert_eq!(42, reader.read_be_u32().unwrap());
+        assert_eq!(25565, reader.read_varint_u32().unwrap());
+        assert_eq!(0, reader.remaining());
+        assert!(reader.read_u8().is_err());
+
+    }
+
+    #[test]
+    fn test_primitives_report_absolute_offsets() {
+
+        let bytes = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05];
+        let mut reader = ByteReader::new(&bytes);
+
+        assert_eq!(0x0001, reader.read_be_u16().unwrap());
+        assert_eq!(0x0302, reader.read_u16_in(Endian::Little).unwrap());
+
+        let error = reader.read_be_u32().unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        assert_eq!("read_be_u32", error.operation());
+        assert_eq!(6, error.offset());
+        assert_eq!(4, reader.position());
+
+    }
+
+    #[test]
+    fn test_all_methods_report_absolute_offsets() {
+
+        let bytes = [0x00, 0x01, 0x05, b'a', 0xFF];
+        let mut reader = ByteReader::new(&bytes);
+
+        reader.skip(2).unwrap();
+
+        let error = reader.read_string(LengthPrefix::U8, 16).unwrap_err();
+
+        assert_eq!("read_string", error.operation());
+        assert_eq!(5, error.offset());
+
+        let mut reader = ByteReader::new(&bytes);
+
+        reader.skip(4).unwrap();
+
+        assert_eq!(5, reader.read_varint_u32().unwrap_err().offset());
+
+        let mut reader = ByteReader::new(&bytes);
+
+        reader.skip(4).unwrap();
+
+        assert_eq!(1, Reader::read_varint_u32(&mut reader).unwrap_err().offset());
+
+        let mut values = [0u16; 2];
+        let mut reader = ByteReader::new(&bytes);
+
+        reader.skip(2).unwrap();
+
+        assert_eq!(5, reader.read_be_u16_into(&mut values).unwrap_err().offset());
+
+    }
+
+    #[test]
+    fn test_position_and_skip() {
+
+        let bytes = [0u8; 8];
+        let mut reader = ByteReader::new(&bytes);
+
+        assert!(reader.skip(3).is_ok());
+        assert_eq!(3, reader.position());
+        assert_eq!(5, reader.remaining());
+
+        let error = reader.skip(6).unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        assert_eq!(8, error.offset());
+        assert_eq!(3, reader.position());
+
+    }
+
+    #[test]
+    fn test_read_bytes_borrows() {
+
+        let bytes = [1, 2, 3, 4, 5];
+        let mut reader = ByteReader::new(&bytes);
+
+        let first = reader.read_bytes(2).unwrap();
+        let second = reader.read_bytes(3).unwrap();
+
+        assert_eq!(&[1, 2], first);
+        assert_eq!(&[3, 4, 5], second);
+        assert_eq!(bytes[2..].as_ptr(), second.as_ptr());
+
+    }
+
+    #[test]
+    fn test_peek() {
+
+        let bytes = [0x12, 0x34, 0x56, 0x78];
+        let reader = ByteReader::new(&bytes);
+
+        assert_eq!(0x12, reader.peek_u8().unwrap());
+        assert_eq!(0x3412, reader.peek_u16::<LittleEndian>().unwrap());
+        assert_eq!(0x12345678, reader.peek_u32::<BigEndian>().unwrap());
+        assert_eq!(0, reader.position());
+
+        let error = reader.peek_u64::<BigEndian>().unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+
+    }
+
+    #[test]
+    fn test_peek_reports_absolute_offsets() {
+
+        let bytes = [0x00, 0x00, 0x80, 0x80];
+        let mut reader = ByteReader::new(&bytes);
+
+        reader.skip(2).unwrap();
+
+        assert_eq!(4, reader.peek_varint_u32().unwrap_err().offset());
+
+    }
+
+    #[test]
+    fn test_read_str() {
+
+        let bytes = [0x00, 0x05, b'h', b'e', b'l', b'l', b'o', 0x02, 0xC3, 0x28];
+        let mut reader = ByteReader::new(&bytes);
+
+        let value = reader.read_str(LengthPrefix::BeU16, 16).unwrap();
+
+        assert_eq!("hello", value);
+        assert_eq!(bytes[2..].as_ptr(), value.as_ptr());
+
+        let error = reader.read_str(LengthPrefix::U8, 16).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!(8, error.offset());
+
+    }
+
+    #[test]
+    fn test_read_str_limit() {
+
+        let bytes = [0x03, b'a', b'b', b'c'];
+        let mut reader = ByteReader::new(&bytes);
+
+        assert_eq!(ErrorKind::InvalidData, reader.read_str(LengthPrefix::U8, 2).unwrap_err().kind());
+        assert_eq!(0, reader.position());
+
+    }
+
+    #[test]
+    fn test_read_str_failure_consumes_nothing() {
+
+        let bytes = [0x01, 0x00, 0x06, b'a', b'b', 0x02, 0xC3, 0x28];
+        let mut reader = ByteReader::new(&bytes);
+
+        reader.skip(1).unwrap();
+
+        assert_eq!(ErrorKind::UnexpectedEof, reader.read_str(LengthPrefix::BeU16, 16).unwrap_err().kind());
+        assert_eq!(1, reader.position());
+
+        reader.skip(4).unwrap();
+
+        assert_eq!(ErrorKind::InvalidData, reader.read_str(LengthPrefix::U8, 16).unwrap_err().kind());
+        assert_eq!(5, reader.position());
+
+        let mut reader = ByteReader::new(&[0x80]);
+
+        assert_eq!(ErrorKind::UnexpectedEof, reader.read_str(LengthPrefix::VarInt, 16).unwrap_err().kind());
+        assert_eq!(0, reader.position());
+
+    }
+
+    #[test]
+    fn test_read_cstr() {
+
+        let bytes = [b'a', b'b', 0x00, b'c', 0x00, b'd', b'e', b'f'];
+        let mut reader = ByteReader::new(&bytes);
+
+        assert_eq!("ab", reader.read_cstr(8).unwrap());
+        assert_eq!(3, reader.position());
+        assert_eq!("c", reader.read_cstr(1).unwrap());
+
+        assert_eq!(ErrorKind::InvalidData, reader.read_cstr(2).unwrap_err().kind());
+        assert_eq!(ErrorKind::UnexpectedEof, reader.read_cstr(8).unwrap_err().kind());
+        assert_eq!(5, reader.position());
+
+    }
+
+}
@@ -0,0 +1,356 @@
+//! Checksums for detecting corrupted data
+//!
+//! Three algorithms are provided: CRC-32 as used by Ethernet, zip and PNG, CRC-32C
+//! (Castagnoli) as used by iSCSI and many storage formats, and Adler-32 as used by zlib.
+//! A Checksum is computed incrementally, and ChecksumReader and ChecksumWriter compute
+//! one over everything passing through a Reader or Writer. FrameReader and FrameWriter
+//! can also carry a checksum in a trailer after each payload.
+
+use std::io::{self, Read, Write};
+
+use crate::reader::Reader;
+use crate::writer::Writer;
+
+/// A checksum algorithm
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChecksumAlgorithm {
+
+    /// CRC-32 with the IEEE 802.3 polynomial
+    Crc32,
+
+    /// CRC-32C with the Castagnoli polynomial
+    Crc32c,
+
+    /// Adler-32
+    Adler32
+
+}
+
+impl ChecksumAlgorithm {
+
+    /// Computes the checksum of the given bytes
+    pub fn checksum(self, bytes: &[u8]) -> u32 {
+        let mut checksum = Checksum::new(self);
+        checksum.update(bytes);
+        checksum.value()
+    }
+
+}
+
+/// The largest prime below 2^16, which Adler-32 sums are taken modulo
+const ADLER_MODULUS: u32 = 65521;
+
+/// The most bytes Adler-32 can sum before its 32-bit sums could overflow
+const ADLER_BLOCK: usize = 5552;
+
+static CRC32_TABLE: [u32; 256] = crc_table(0xEDB88320);
+
+static CRC32C_TABLE: [u32; 256] = crc_table(0x82F63B78);
+
+/// Builds the lookup table for a reflected CRC-32 polynomial
+const fn crc_table(polynomial: u32) -> [u32; 256] {
+
+    let mut table = [0u32; 256];
+    let mut index = 0;
+
+    while index < 256 {
+
+        let mut value = index as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            value = if value & 1 == 1 { (value >> 1) ^ polynomial } else { value >> 1 };
+            bit += 1;
+        }
+
+        table[index] = value;
+        index += 1;
+
+    }
+
+    table
+
+}
+
+/// A checksum computed incrementally
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Checksum {
+    algorithm: ChecksumAlgorithm,
+    state: u32
+}
+
+impl Checksum {
+
+    /// Creates the checksum of no bytes
+    pub fn new(algorithm: ChecksumAlgorithm) -> Checksum {
+        Checksum { algorithm, state: Checksum::initial(algorithm) }
+    }
+
+    /// Returns the algorithm in use
+    pub fn algorithm(&self) -> ChecksumAlgorithm {
+        self.algorithm
+    }
+
+    /// Adds the given bytes to the checksum
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self.algorithm {
+            ChecksumAlgorithm::Crc32 => self.update_crc(&CRC32_TABLE, bytes),
+            ChecksumAlgorithm::Crc32c => self.update_crc(&CRC32C_TABLE, bytes),
+            ChecksumAlgorithm::Adler32 => self.update_adler(bytes)
+        }
+    }
+
+    /// Returns the checksum of the bytes added so far
+    pub fn value(&self) -> u32 {
+        match self.algorithm {
+            ChecksumAlgorithm::Crc32 | ChecksumAlgorithm::Crc32c => !self.state,
+            ChecksumAlgorithm::Adler32 => self.state
+        }
+    }
+
+    /// Forgets the bytes added so far
+    pub fn reset(&mut self) {
+        self.state = Checksum::initial(self.algorithm);
+    }
+
+    /// The state before any bytes are added
+    fn initial(algorithm: ChecksumAlgorithm) -> u32 {
+        match algorithm {
+            ChecksumAlgorithm::Crc32 | ChecksumAlgorithm::Crc32c => !0,
+            ChecksumAlgorithm::Adler32 => 1
+        }
+    }
+
+    /// Adds bytes to a CRC one byte at a time
+    fn update_crc(&mut self, table: &[u32; 256], bytes: &[u8]) {
+        for &byte in bytes {
+            self.state = table[((self.state ^ byte as u32) & 0xFF) as usize] ^ (self.state >> 8);
+        }
+    }
+
+    /// Adds bytes to an Adler-32 sum, reducing only once per block
+    fn update_adler(&mut self, bytes: &[u8]) {
+
+        let mut a = self.state & 0xFFFF;
+        let mut b = self.state >> 16;
+
+        for block in bytes.chunks(ADLER_BLOCK) {
+
+            for &byte in block {
+                a += byte as u32;
+                b += a;
+            }
+
+            a %= ADLER_MODULUS;
+            b %= ADLER_MODULUS;
+
+        }
+
+        self.state = (b << 16) | a;
+
+    }
+
+}
+
+/// Computes a checksum of everything read through a Reader
+#[derive(Debug)]
+pub struct ChecksumReader<R: Reader> {
+    reader: R,
+    checksum: Checksum
+}
+
+impl<R: Reader> ChecksumReader<R> {
+
+    /// Creates a ChecksumReader using the given algorithm
+    pub fn new(reader: R, algorithm: ChecksumAlgorithm) -> ChecksumReader<R> {
+        ChecksumReader { reader, checksum: Checksum::new(algorithm) }
+    }
+
+    /// Returns the checksum of the bytes read so far
+    pub fn checksum(&self) -> u32 {
+        self.checksum.value()
+    }
+
+    /// Forgets the bytes read so far, starting a new checksum
+    pub fn reset(&mut self) {
+        self.checksum.reset();
+    }
+
+    /// Returns a reference to the underlying Reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the underlying Reader
+    ///
+    /// Bytes read directly from it are not included in the checksum
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the underlying Reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+}
+
+impl<R: Reader> Read for ChecksumReader<R> {
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buffer)?;
+        self.checksum.update(&buffer[..read]);
+        Ok(read)
+    }
+
+}
+
+/// Computes a checksum of everything written through a Writer
+#[derive(Debug)]
+pub struct ChecksumWriter<W: Writer> {
+    writer: W,
+    checksum: Checksum
+}
+
+impl<W: Writer> ChecksumWriter<W> {
+
+    /// Creates a ChecksumWriter using the given algorithm
+    pub fn new(writer: W, algorithm: ChecksumAlgorithm) -> ChecksumWriter<W> {
+        ChecksumWriter { writer, checksum: Checksum::new(algorithm) }
+    }
+
+    /// Returns the checksum of the bytes written so far
+    pub fn checksum(&self) -> u32 {
+        self.checksum.value()
+    }
+
+    /// Forgets the bytes written so far, starting a new checksum
+    pub fn reset(&mut self) {
+        self.checksum.reset();
+    }
+
+    /// Returns a reference to the underlying Writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the underlying Writer
+    ///
+    /// Bytes written directly to it are not included in the checksum
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns the underlying Writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+}
+
+impl<W: Writer> Write for ChecksumWriter<W> {
+
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buffer)?;
+        self.checksum.update(&buffer[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::endian::Endian;
+    use crate::prefix::LengthPrefix;
+
+    const ALGORITHMS: [ChecksumAlgorithm; 3] = [
+        ChecksumAlgorithm::Crc32,
+        ChecksumAlgorithm::Crc32c,
+        ChecksumAlgorithm::Adler32
+    ];
+
+    #[test]
+    fn test_known_values() {
+
+        assert_eq!(0xCBF43926, ChecksumAlgorithm::Crc32.checksum(b"123456789"));
+        assert_eq!(0xE3069283, ChecksumAlgorithm::Crc32c.checksum(b"123456789"));
+        assert_eq!(0x091E01DE, ChecksumAlgorithm::Adler32.checksum(b"123456789"));
+        assert_eq!(0x11E60398, ChecksumAlgorithm::Adler32.checksum(b"Wikipedia"));
+
+        assert_eq!(0, ChecksumAlgorithm::Crc32.checksum(b""));
+        assert_eq!(0, ChecksumAlgorithm::Crc32c.checksum(b""));
+        assert_eq!(1, ChecksumAlgorithm::Adler32.checksum(b""));
+
+    }
+
+    #[test]
+    fn test_adler_blocks() {
+
+        // Enough 0xFF bytes to overflow the sums if they were not reduced per block
+        let bytes = vec![0xFFu8; 100_000];
+
+        let (mut a, mut b) = (1u32, 0u32);
+        for &byte in &bytes {
+            a = (a + byte as u32) % ADLER_MODULUS;
+            b = (b + a) % ADLER_MODULUS;
+        }
+
+        assert_eq!((b << 16) | a, ChecksumAlgorithm::Adler32.checksum(&bytes));
+
+    }
+
+    quickcheck! {
+        fn test_incremental(bytes: Vec<u8>, split: usize) -> bool {
+
+            ALGORITHMS.iter().all(|&algorithm| {
+
+                let split = if bytes.is_empty() { 0 } else { split % bytes.len() };
+
+                let mut checksum = Checksum::new(algorithm);
+                checksum.update(&bytes[..split]);
+                checksum.update(&bytes[split..]);
+
+                checksum.value() == algorithm.checksum(&bytes)
+
+            })
+
+        }
+    }
+
+    #[test]
+    fn test_reader_and_writer() {
+
+        for algorithm in ALGORITHMS {
+
+            let mut writer = ChecksumWriter::new(Vec::new(), algorithm);
+
+            writer.write_be_u32(0xDEADBEEF).unwrap();
+            writer.write_string("checksum", LengthPrefix::VarInt).unwrap();
+
+            let expected = algorithm.checksum(writer.get_ref());
+
+            assert_eq!(expected, writer.checksum());
+
+            let bytes = writer.into_inner();
+            let mut reader = ChecksumReader::new(&bytes[..], algorithm);
+
+            assert_eq!(0xDEADBEEF, reader.read_u32_in(Endian::Big).unwrap());
+            assert_eq!(algorithm.checksum(&bytes[..4]), reader.checksum());
+
+            reader.reset();
+            reader.read_to_end(&mut Vec::new()).unwrap();
+
+            assert_eq!(algorithm.checksum(&bytes[4..]), reader.checksum());
+
+        }
+
+    }
+
+}
@@ -0,0 +1,640 @@
+//! Encoding and decoding steps shared by the blocking and async implementations
+//!
+//! Reads are described by a Decode, which hands out one buffer after another to be
+//! filled and turns the filled buffers into a value. Writes are prepared as an Encoded
+//! header and body. Reader, Writer and the async traits only move the bytes, so every
+//! limit check, conversion and error offset is worked out here once.
+
+use std::borrow::Cow;
+use std::io::{self, ErrorKind};
+use std::mem;
+
+use crate::bulk::{self, Primitive, Scalar};
+use crate::endian::Endian;
+use crate::error::{Error, Result};
+use crate::prefix::{self, LengthPrefix};
+use crate::text::{self, SurrogatePolicy, TextEncoding};
+use crate::varint;
+
+/// A read that fills one buffer after another until its value is complete
+pub(crate) trait Decode {
+
+    /// The value read
+    type Output;
+
+    /// Returns the name of the operation, for errors
+    fn operation(&self) -> &'static str;
+
+    /// Returns the number of bytes consumed by the buffers already filled
+    fn consumed(&self) -> usize;
+
+    /// Returns the buffer to fill next, which must be filled completely
+    fn buffer(&mut self) -> &mut [u8];
+
+    /// Takes the filled buffer, returning the value once no more bytes are needed
+    ///
+    /// Error offsets count from the start of the operation
+    fn advance(&mut self) -> Result<Option<Self::Output>>;
+
+}
+
+/// Accounts for one read towards filling a buffer of `length` bytes
+///
+/// `count` is the number of bytes filled so far. Interrupted reads are ignored so
+/// that they are retried, and a read of zero bytes means the stream ended too early
+pub(crate) fn record_read(result: io::Result<usize>, count: &mut usize, length: usize, operation: &'static str) -> Result<()> {
+    match result {
+        Ok(0) => Err(Error::Truncated { operation, offset: *count as u64, needed: (length - *count) as u64 }),
+        Ok(read) => {
+            *count += read;
+            Ok(())
+        },
+        Err(ref error) if error.kind() == ErrorKind::Interrupted => Ok(()),
+        Err(error) => Err(Error::Io { operation, offset: *count as u64, error })
+    }
+}
+
+/// Accounts for one write towards writing a whole buffer
+///
+/// `count` is the number of bytes written so far. Interrupted writes are ignored so
+/// that they are retried, and a write of zero bytes means the writer is full
+pub(crate) fn record_write(result: io::Result<usize>, count: &mut usize, operation: &'static str) -> Result<()> {
+    match result {
+        Ok(0) => Err(Error::Io { operation, offset: *count as u64, error: ErrorKind::WriteZero.into() }),
+        Ok(written) => {
+            *count += written;
+            Ok(())
+        },
+        Err(ref error) if error.kind() == ErrorKind::Interrupted => Ok(()),
+        Err(error) => Err(Error::Io { operation, offset: *count as u64, error })
+    }
+}
+
+/// Decodes a single fixed-width number
+pub(crate) struct Fixed<T: Scalar> {
+    bytes: T::Bytes,
+    endian: Endian,
+    operation: &'static str
+}
+
+impl<T: Scalar> Fixed<T> {
+
+    /// Creates a decoder for a number stored in the given byte order
+    pub(crate) fn new(endian: Endian, operation: &'static str) -> Fixed<T> {
+        Fixed { bytes: T::Bytes::default(), endian, operation }
+    }
+
+}
+
+impl<T: Scalar> Decode for Fixed<T> {
+
+    type Output = T;
+
+    fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    fn consumed(&self) -> usize {
+        0
+    }
+
+    fn buffer(&mut self) -> &mut [u8] {
+        self.bytes.as_mut()
+    }
+
+    fn advance(&mut self) -> Result<Option<T>> {
+        Ok(Some(T::from_bytes(mem::take(&mut self.bytes), self.endian)))
+    }
+
+}
+
+/// Fills a slice of primitives with a single read, then converts them in place
+pub(crate) struct Primitives<'a, T> {
+    values: &'a mut [T],
+    endian: Endian,
+    operation: &'static str
+}
+
+impl<'a, T: Primitive> Primitives<'a, T> {
+
+    /// Creates a decoder for primitives stored in the given byte order
+    pub(crate) fn new(values: &'a mut [T], endian: Endian, operation: &'static str) -> Primitives<'a, T> {
+        Primitives { values, endian, operation }
+    }
+
+}
+
+impl<T: Primitive> Decode for Primitives<'_, T> {
+
+    type Output = ();
+
+    fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    fn consumed(&self) -> usize {
+        0
+    }
+
+    fn buffer(&mut self) -> &mut [u8] {
+        bulk::as_bytes_mut(self.values)
+    }
+
+    fn advance(&mut self) -> Result<Option<()>> {
+
+        if self.endian != Endian::NATIVE {
+            for value in self.values.iter_mut() {
+                *value = value.convert(self.endian);
+            }
+        }
+
+        Ok(Some(()))
+
+    }
+
+}
+
+/// Decodes a VarInt of the given width in bits one byte at a time
+pub(crate) struct VarInt {
+    decoder: varint::Decoder,
+    byte: [u8; 1],
+    operation: &'static str
+}
+
+impl VarInt {
+
+    /// Creates a decoder for a VarInt of the given width in bits
+    pub(crate) fn new(bits: u32, operation: &'static str) -> VarInt {
+        VarInt { decoder: varint::Decoder::new(bits), byte: [0], operation }
+    }
+
+}
+
+impl Decode for VarInt {
+
+    type Output = u64;
+
+    fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    fn consumed(&self) -> usize {
+        self.decoder.len()
+    }
+
+    fn buffer(&mut self) -> &mut [u8] {
+        &mut self.byte
+    }
+
+    fn advance(&mut self) -> Result<Option<u64>> {
+        self.decoder.push(self.byte[0], self.operation)
+    }
+
+}
+
+/// Decodes a length prefix, along with the number of bytes it took
+pub(crate) enum Length {
+    Fixed { prefix: LengthPrefix, width: usize, raw_buffer: [u8; 4], operation: &'static str },
+    VarInt(VarInt)
+}
+
+impl Length {
+
+    /// Creates a decoder for a length prefix of the given type
+    pub(crate) fn new(prefix: LengthPrefix, operation: &'static str) -> Length {
+        match prefix.width() {
+            Some(width) => Length::Fixed { prefix, width, raw_buffer: [0; 4], operation },
+            None => Length::VarInt(VarInt::new(32, operation))
+        }
+    }
+
+}
+
+impl Decode for Length {
+
+    type Output = (usize, usize);
+
+    fn operation(&self) -> &'static str {
+        match self {
+            Length::Fixed { operation, .. } => operation,
+            Length::VarInt(decoder) => decoder.operation()
+        }
+    }
+
+    fn consumed(&self) -> usize {
+        match self {
+            Length::Fixed { .. } => 0,
+            Length::VarInt(decoder) => decoder.consumed()
+        }
+    }
+
+    fn buffer(&mut self) -> &mut [u8] {
+        match self {
+            Length::Fixed { width, raw_buffer, .. } => &mut raw_buffer[..*width],
+            Length::VarInt(decoder) => decoder.buffer()
+        }
+    }
+
+    fn advance(&mut self) -> Result<Option<(usize, usize)>> {
+        match self {
+            Length::Fixed { prefix, width, raw_buffer, .. } => Ok(Some((prefix.decode(&raw_buffer[..*width]), *width))),
+            Length::VarInt(decoder) => Ok(decoder.advance()?.map(|length| (length as usize, decoder.consumed())))
+        }
+    }
+
+}
+
+/// Decodes a length prefix followed by that many units of `unit` bytes
+///
+/// The length is checked against the limit before the body is allocated, and errors
+/// from the body count from the start of the prefix
+pub(crate) struct Prefixed<F> {
+    length: Length,
+    prefix_length: Option<usize>,
+    limit: usize,
+    unit: usize,
+    body: Vec<u8>,
+    finish: F
+}
+
+impl<T, F: Fn(Vec<u8>, &'static str) -> Result<T>> Prefixed<F> {
+
+    /// Creates a decoder that passes the body to `finish` once it has been read
+    fn new(prefix: LengthPrefix, limit: usize, unit: usize, operation: &'static str, finish: F) -> Prefixed<F> {
+        Prefixed { length: Length::new(prefix, operation), prefix_length: None, limit, unit, body: Vec::new(), finish }
+    }
+
+}
+
+impl<T, F: Fn(Vec<u8>, &'static str) -> Result<T>> Decode for Prefixed<F> {
+
+    type Output = T;
+
+    fn operation(&self) -> &'static str {
+        self.length.operation()
+    }
+
+    fn consumed(&self) -> usize {
+        self.prefix_length.unwrap_or_else(|| self.length.consumed())
+    }
+
+    fn buffer(&mut self) -> &mut [u8] {
+        match self.prefix_length {
+            Some(_) => &mut self.body,
+            None => self.length.buffer()
+        }
+    }
+
+    fn advance(&mut self) -> Result<Option<T>> {
+
+        let operation = self.operation();
+
+        if let Some(prefix_length) = self.prefix_length {
+            return (self.finish)(mem::take(&mut self.body), operation)
+                .map(Some)
+                .map_err(|error| error.with_base_offset(prefix_length as u64));
+        }
+
+        if let Some((length, prefix_length)) = self.length.advance()? {
+            prefix::check_limit(length, self.limit, operation)?;
+            self.body = vec![0u8; length * self.unit];
+            self.prefix_length = Some(prefix_length);
+        }
+
+        Ok(None)
+
+    }
+
+}
+
+/// Decodes code units of `unit_width` bytes up to a NUL terminator, one at a time
+pub(crate) struct Terminated<F> {
+    collected: text::Terminated,
+    unit: [u8; 2],
+    unit_width: usize,
+    operation: &'static str,
+    finish: F
+}
+
+impl<T, F: Fn(Vec<u8>, &'static str) -> Result<T>> Terminated<F> {
+
+    /// Creates a decoder that passes the units before the terminator to `finish`
+    fn new(max_units: usize, unit_width: usize, operation: &'static str, finish: F) -> Terminated<F> {
+        Terminated { collected: text::Terminated::new(max_units, unit_width), unit: [0; 2], unit_width, operation, finish }
+    }
+
+}
+
+impl<T, F: Fn(Vec<u8>, &'static str) -> Result<T>> Decode for Terminated<F> {
+
+    type Output = T;
+
+    fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    fn consumed(&self) -> usize {
+        self.collected.len()
+    }
+
+    fn buffer(&mut self) -> &mut [u8] {
+        &mut self.unit[..self.unit_width]
+    }
+
+    fn advance(&mut self) -> Result<Option<T>> {
+
+        if !self.collected.push(&self.unit[..self.unit_width], self.operation)? {
+            return Ok(None);
+        }
+
+        (self.finish)(self.collected.take_bytes(), self.operation).map(Some)
+
+    }
+
+}
+
+/// Decodes a field of exactly `width` bytes
+pub(crate) struct Exact<F> {
+    body: Vec<u8>,
+    operation: &'static str,
+    finish: F
+}
+
+impl<T, F: Fn(Vec<u8>, &'static str) -> Result<T>> Decode for Exact<F> {
+
+    type Output = T;
+
+    fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    fn consumed(&self) -> usize {
+        0
+    }
+
+    fn buffer(&mut self) -> &mut [u8] {
+        &mut self.body
+    }
+
+    fn advance(&mut self) -> Result<Option<T>> {
+        (self.finish)(mem::take(&mut self.body), self.operation).map(Some)
+    }
+
+}
+
+/// Decodes a length-prefixed UTF-8 string of at most `max_length` bytes
+pub(crate) fn read_string(prefix: LengthPrefix, max_length: usize) -> impl Decode<Output = String> {
+    Prefixed::new(prefix, max_length, 1, "read_string", |bytes, operation| {
+        text::decode_text(bytes, TextEncoding::Utf8, operation)
+    })
+}
+
+/// Decodes a string written by Java's `DataOutput.writeUTF`
+pub(crate) fn read_java_utf() -> impl Decode<Output = String> {
+    Prefixed::new(LengthPrefix::BeU16, usize::MAX, 1, "read_java_utf", |bytes, operation| {
+        text::decode_java_utf(&bytes, operation)
+    })
+}
+
+/// Decodes a UTF-16 string prefixed with its length in code units
+pub(crate) fn read_utf16(
+    prefix: LengthPrefix,
+    max_units: usize,
+    endian: Endian,
+    detect_bom: bool,
+    policy: SurrogatePolicy
+) -> impl Decode<Output = String> {
+    Prefixed::new(prefix, max_units, 2, "read_utf16", move |bytes, operation| {
+        text::decode_utf16(&bytes, endian, detect_bom, policy, operation)
+    })
+}
+
+/// Decodes a UTF-16 string terminated by a NUL code unit
+pub(crate) fn read_utf16_nul(
+    max_units: usize,
+    endian: Endian,
+    detect_bom: bool,
+    policy: SurrogatePolicy
+) -> impl Decode<Output = String> {
+    Terminated::new(max_units, 2, "read_utf16_nul", move |bytes, operation| {
+        text::decode_utf16(&bytes, endian, detect_bom, policy, operation)
+    })
+}
+
+/// Decodes a NUL-terminated string of at most `max_length` bytes
+pub(crate) fn read_cstring(max_length: usize, encoding: TextEncoding) -> impl Decode<Output = String> {
+    Terminated::new(max_length, 1, "read_cstring", move |bytes, operation| {
+        text::decode_text(bytes, encoding, operation)
+    })
+}
+
+/// Decodes a string stored in a fixed-width field, removing trailing `pad_byte`s
+pub(crate) fn read_fixed_str(width: usize, pad_byte: u8, encoding: TextEncoding) -> impl Decode<Output = String> {
+    Exact { body: vec![0u8; width], operation: "read_fixed_str", finish: move |bytes, operation| {
+        text::decode_fixed(bytes, pad_byte, encoding, operation)
+    } }
+}
+
+/// The bytes of a write: a short header, such as a length prefix, followed by a body
+///
+/// Either part may be empty, and is then not written at all. Errors from writing the
+/// body count from the start of the header. Single fixed-width numbers need neither
+/// part; they are written straight from their `Scalar::to_bytes` array
+pub(crate) struct Encoded<'a> {
+    header: [u8; 16],
+    header_length: usize,
+    body: Cow<'a, [u8]>,
+    operation: &'static str
+}
+
+impl<'a> Encoded<'a> {
+
+    /// Creates a write of the given header and body
+    fn new(header: &[u8], body: Cow<'a, [u8]>, operation: &'static str) -> Encoded<'a> {
+
+        let mut raw_buffer = [0u8; 16];
+
+        raw_buffer[..header.len()].copy_from_slice(header);
+
+        Encoded { header: raw_buffer, header_length: header.len(), body, operation }
+
+    }
+
+    /// Encodes a slice of primitives in the given byte order
+    ///
+    /// Slices already in that byte order are borrowed; others are converted first
+    pub(crate) fn primitives<T: Primitive + Scalar>(values: &'a [T], endian: Endian, operation: &'static str) -> Encoded<'a> {
+
+        if endian == Endian::NATIVE {
+            return Encoded::new(&[], Cow::Borrowed(bulk::as_bytes(values)), operation);
+        }
+
+        let mut raw_buffer = Vec::with_capacity(mem::size_of_val(values));
+
+        for value in values {
+            raw_buffer.extend_from_slice(value.to_bytes(endian).as_ref());
+        }
+
+        Encoded::new(&[], Cow::Owned(raw_buffer), operation)
+
+    }
+
+    /// Encodes a VarInt
+    pub(crate) fn varint(value: u64, operation: &'static str) -> Encoded<'static> {
+
+        let mut raw_buffer = [0u8; varint::MAX_LENGTH_64];
+
+        let length = varint::encode(value, &mut raw_buffer);
+
+        Encoded::new(&raw_buffer[..length], Cow::Borrowed(&[]), operation)
+
+    }
+
+    /// Encodes a length prefix, rejecting lengths the prefix cannot hold
+    pub(crate) fn length(prefix: LengthPrefix, length: usize, operation: &'static str) -> Result<Encoded<'static>> {
+        Encoded::prefixed(prefix, length, Cow::Borrowed(&[]), operation)
+    }
+
+    /// Encodes a body after a prefix holding `length`, its length in units
+    pub(crate) fn prefixed(
+        prefix: LengthPrefix,
+        length: usize,
+        body: Cow<'a, [u8]>,
+        operation: &'static str
+    ) -> Result<Encoded<'a>> {
+
+        let mut raw_buffer = [0u8; varint::MAX_LENGTH_64];
+
+        let prefix_length = prefix.encode(length, &mut raw_buffer, operation)?;
+
+        Ok(Encoded::new(&raw_buffer[..prefix_length], body, operation))
+
+    }
+
+    /// Returns the name of the operation, for errors
+    pub(crate) fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    /// Returns the bytes written before the body
+    pub(crate) fn header(&self) -> &[u8] {
+        &self.header[..self.header_length]
+    }
+
+    /// Returns the bytes written after the header
+    pub(crate) fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+}
+
+/// Encodes a length-prefixed UTF-8 string
+pub(crate) fn write_string(value: &str, prefix: LengthPrefix) -> Result<Encoded<'_>> {
+    Encoded::prefixed(prefix, value.len(), Cow::Borrowed(value.as_bytes()), "write_string")
+}
+
+/// Encodes a string the way Java's `DataOutput.writeUTF` does
+pub(crate) fn write_java_utf(value: &str) -> Result<Encoded<'static>> {
+
+    let raw_buffer = text::encode_java_utf(value);
+
+    Encoded::prefixed(LengthPrefix::BeU16, raw_buffer.len(), Cow::Owned(raw_buffer), "write_java_utf")
+
+}
+
+/// Encodes a UTF-16 string prefixed with its length in code units
+pub(crate) fn write_utf16(value: &str, prefix: LengthPrefix, endian: Endian, bom: bool) -> Result<Encoded<'static>> {
+
+    let raw_buffer = text::encode_utf16(value, endian, bom);
+
+    Encoded::prefixed(prefix, raw_buffer.len() / 2, Cow::Owned(raw_buffer), "write_utf16")
+
+}
+
+/// Encodes a UTF-16 string terminated by a NUL code unit
+pub(crate) fn write_utf16_nul(value: &str, endian: Endian, bom: bool) -> Result<Encoded<'static>> {
+    text::encode_utf16_nul(value, endian, bom, "write_utf16_nul")
+        .map(|raw_buffer| Encoded::new(&[], Cow::Owned(raw_buffer), "write_utf16_nul"))
+}
+
+/// Encodes a NUL-terminated string
+pub(crate) fn write_cstring(value: &str, encoding: TextEncoding) -> Result<Encoded<'static>> {
+    text::encode_cstring(value, encoding, "write_cstring")
+        .map(|raw_buffer| Encoded::new(&[], Cow::Owned(raw_buffer), "write_cstring"))
+}
+
+/// Encodes a string into a fixed-width field padded with `pad_byte`
+pub(crate) fn write_fixed_str(value: &str, width: usize, pad_byte: u8, encoding: TextEncoding) -> Result<Encoded<'static>> {
+    text::encode_fixed(value, width, pad_byte, encoding, "write_fixed_str")
+        .map(|raw_buffer| Encoded::new(&[], Cow::Owned(raw_buffer), "write_fixed_str"))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Feeds the bytes to the decoder, returning how many it took before finishing
+    fn decoded<D: Decode>(mut decoder: D, bytes: &[u8]) -> Result<(D::Output, usize)> {
+
+        let mut position = 0;
+
+        loop {
+
+            let buffer = decoder.buffer();
+            let length = buffer.len();
+
+            buffer.copy_from_slice(&bytes[position..position + length]);
+            position += length;
+
+            if let Some(value) = decoder.advance()? {
+                return Ok((value, position));
+            }
+
+        }
+
+    }
+
+    #[test]
+    fn test_decode_without_io() {
+
+        assert_eq!(0x0102, decoded(Fixed::<u16>::new(Endian::Big, "decode"), &[1, 2]).unwrap().0);
+        assert_eq!((300, 2), decoded(VarInt::new(32, "decode"), &[0xAC, 0x02, 0xFF]).unwrap());
+        assert_eq!(((3, 1), 1), decoded(Length::new(LengthPrefix::VarInt, "decode"), &[3]).unwrap());
+        assert_eq!(("ab".to_string(), 3), decoded(read_cstring(4, TextEncoding::Ascii), b"ab\0\0").unwrap());
+        assert_eq!(("hi".to_string(), 4), decoded(read_string(LengthPrefix::BeU16, 2), b"\0\x02hi").unwrap());
+
+    }
+
+    #[test]
+    fn test_decode_errors_count_from_the_prefix() {
+
+        let error = decoded(read_string(LengthPrefix::VarInt, 8), &[0x02, b'a', 0xFF]).unwrap_err();
+
+        assert_eq!("read_string", error.operation());
+        assert_eq!(2, error.offset());
+
+        let error = decoded(read_string(LengthPrefix::U8, 1), &[0x02]).unwrap_err();
+
+        assert_eq!(0, error.offset());
+
+    }
+
+    #[test]
+    fn test_encode() {
+
+        let encoded = Encoded::primitives(&[0x0102u16, 0x0304], Endian::Little, "encode");
+
+        assert_eq!(&[] as &[u8], encoded.header());
+        assert_eq!(&[0x02, 0x01, 0x04, 0x03], encoded.body());
+
+        let encoded = write_string("abc", LengthPrefix::BeU16).unwrap();
+
+        assert_eq!(&[0x00, 0x03], encoded.header());
+        assert_eq!(b"abc", encoded.body());
+        assert_eq!(&[0x80, 0x01], Encoded::varint(128, "encode").header());
+        assert!(write_string(&"a".repeat(256), LengthPrefix::U8).is_err());
+
+    }
+
+}
@@ -0,0 +1,542 @@
+//! zlib and raw deflate compression
+//!
+//! InflateReader and DeflateWriter decompress and compress everything passing through a
+//! Reader or Writer. Decompression is capped at a maximum output size, so that a small
+//! hostile input cannot expand into gigabytes of memory.
+//!
+//! CompressedFrameReader and CompressedFrameWriter add per-frame compression to
+//! FrameReader and FrameWriter in the style of the Minecraft protocol. Each frame's
+//! payload starts with a VarInt holding the uncompressed length, followed by the data
+//! compressed with zlib; payloads smaller than the threshold are sent as-is, with an
+//! uncompressed length of zero.
+
+use std::io::{self, BufRead, Read, Write};
+
+use flate2::Compression;
+use flate2::bufread::{DeflateDecoder, ZlibDecoder};
+use flate2::write::{DeflateEncoder, ZlibEncoder};
+
+use crate::byte_reader::ByteReader;
+use crate::error::{Error, Result};
+use crate::frame::{FrameReader, FrameWriter};
+use crate::prefix;
+use crate::reader::Reader;
+use crate::value::DEFAULT_MAX_LENGTH;
+use crate::writer::Writer;
+
+/// The compression level used unless another is chosen
+const DEFAULT_LEVEL: u32 = 6;
+
+/// The container around compressed data
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CompressionFormat {
+
+    /// A zlib stream, with a two byte header and an Adler-32 trailer
+    Zlib,
+
+    /// Raw deflate data with no header or trailer
+    Deflate
+
+}
+
+/// The decoder behind an InflateReader
+#[derive(Debug)]
+enum Decoder<R> {
+    Zlib(ZlibDecoder<R>),
+    Deflate(DeflateDecoder<R>)
+}
+
+/// Decompresses everything read through a Reader
+///
+/// The underlying Reader must be buffered, so that bytes following the compressed data
+/// are left unread; wrap a File or TcpStream in a BufReader.
+#[derive(Debug)]
+pub struct InflateReader<R: BufRead> {
+    decoder: Decoder<R>,
+    max_size: u64,
+    total: u64
+}
+
+impl<R: BufRead> InflateReader<R> {
+
+    /// Creates an InflateReader that fails once more than `max_size` bytes have been
+    /// decompressed
+    pub fn new(reader: R, format: CompressionFormat, max_size: u64) -> InflateReader<R> {
+
+        let decoder = match format {
+            CompressionFormat::Zlib => Decoder::Zlib(ZlibDecoder::new(reader)),
+            CompressionFormat::Deflate => Decoder::Deflate(DeflateDecoder::new(reader))
+        };
+
+        InflateReader { decoder, max_size, total: 0 }
+
+    }
+
+    /// Returns the number of bytes decompressed so far
+    pub fn total_out(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns a reference to the underlying Reader
+    pub fn get_ref(&self) -> &R {
+        match self.decoder {
+            Decoder::Zlib(ref decoder) => decoder.get_ref(),
+            Decoder::Deflate(ref decoder) => decoder.get_ref()
+        }
+    }
+
+    /// Returns a mutable reference to the underlying Reader
+    ///
+    /// Reading from it directly corrupts the compressed stream
+    pub fn get_mut(&mut self) -> &mut R {
+        match self.decoder {
+            Decoder::Zlib(ref mut decoder) => decoder.get_mut(),
+            Decoder::Deflate(ref mut decoder) => decoder.get_mut()
+        }
+    }
+
+    /// Returns the underlying Reader
+    pub fn into_inner(self) -> R {
+        match self.decoder {
+            Decoder::Zlib(decoder) => decoder.into_inner(),
+            Decoder::Deflate(decoder) => decoder.into_inner()
+        }
+    }
+
+}
+
+impl<R: BufRead> Read for InflateReader<R> {
+
+    /// Decompresses into the buffer
+    ///
+    /// Exceeding the maximum size fails with a `LengthLimitExceeded` error wrapped in
+    /// an `io::Error` of kind `InvalidData`
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+
+        // Ask for at most one byte past the limit, so that going over it is noticed
+        // without decompressing any further
+        let allowed = (self.max_size - self.total).saturating_add(1).min(buffer.len() as u64) as usize;
+
+        let read = match self.decoder {
+            Decoder::Zlib(ref mut decoder) => decoder.read(&mut buffer[..allowed])?,
+            Decoder::Deflate(ref mut decoder) => decoder.read(&mut buffer[..allowed])?
+        };
+
+        if self.total + read as u64 > self.max_size {
+            return Err(Error::LengthLimitExceeded {
+                operation: "inflate",
+                offset: self.max_size,
+                length: self.total + read as u64,
+                limit: self.max_size
+            }.into());
+        }
+
+        self.total += read as u64;
+
+        Ok(read)
+
+    }
+
+}
+
+/// The encoder behind a DeflateWriter
+#[derive(Debug)]
+enum Encoder<W: Write> {
+    Zlib(ZlibEncoder<W>),
+    Deflate(DeflateEncoder<W>)
+}
+
+/// Compresses everything written through a Writer
+///
+/// Call `finish` once everything has been written, to flush the end of the compressed
+/// data and any error in doing so.
+#[derive(Debug)]
+pub struct DeflateWriter<W: Writer> {
+    encoder: Encoder<W>
+}
+
+impl<W: Writer> DeflateWriter<W> {
+
+    /// Creates a DeflateWriter at the default compression level
+    pub fn new(writer: W, format: CompressionFormat) -> DeflateWriter<W> {
+        DeflateWriter::with_level(writer, format, DEFAULT_LEVEL)
+    }
+
+    /// Creates a DeflateWriter at the given compression level, from 0 (none) to 9 (best)
+    pub fn with_level(writer: W, format: CompressionFormat, level: u32) -> DeflateWriter<W> {
+
+        let level = Compression::new(level.min(9));
+
+        let encoder = match format {
+            CompressionFormat::Zlib => Encoder::Zlib(ZlibEncoder::new(writer, level)),
+            CompressionFormat::Deflate => Encoder::Deflate(DeflateEncoder::new(writer, level))
+        };
+
+        DeflateWriter { encoder }
+
+    }
+
+    /// Returns a reference to the underlying Writer
+    pub fn get_ref(&self) -> &W {
+        match self.encoder {
+            Encoder::Zlib(ref encoder) => encoder.get_ref(),
+            Encoder::Deflate(ref encoder) => encoder.get_ref()
+        }
+    }
+
+    /// Finishes the compressed data and returns the underlying Writer
+    pub fn finish(self) -> Result<W> {
+
+        let finished = match self.encoder {
+            Encoder::Zlib(encoder) => encoder.finish(),
+            Encoder::Deflate(encoder) => encoder.finish()
+        };
+
+        finished.map_err(|error| Error::Io { operation: "deflate_finish", offset: 0, error })
+
+    }
+
+}
+
+impl<W: Writer> Write for DeflateWriter<W> {
+
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        match self.encoder {
+            Encoder::Zlib(ref mut encoder) => encoder.write(buffer),
+            Encoder::Deflate(ref mut encoder) => encoder.write(buffer)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.encoder {
+            Encoder::Zlib(ref mut encoder) => encoder.flush(),
+            Encoder::Deflate(ref mut encoder) => encoder.flush()
+        }
+    }
+
+}
+
+/// Reads frames whose payloads may be compressed
+///
+/// Error offsets count from the start of the frame, including its header, as they do
+/// for FrameReader
+#[derive(Debug)]
+pub struct CompressedFrameReader<R: Reader> {
+    frames: FrameReader<R>,
+    threshold: usize,
+    max_size: usize,
+    buffer: Vec<u8>
+}
+
+impl<R: Reader> CompressedFrameReader<R> {
+
+    /// Creates a CompressedFrameReader reading frames from the given FrameReader
+    ///
+    /// Compressed frames whose uncompressed length is below `threshold` are rejected, as
+    /// the writer should have sent them uncompressed. Uncompressed lengths are limited to
+    /// `DEFAULT_MAX_LENGTH` bytes
+    pub fn new(frames: FrameReader<R>, threshold: usize) -> CompressedFrameReader<R> {
+        CompressedFrameReader { frames, threshold, max_size: DEFAULT_MAX_LENGTH, buffer: Vec::new() }
+    }
+
+    /// Sets the largest uncompressed payload, in bytes, that will be read
+    ///
+    /// The length is checked before anything is decompressed
+    pub fn with_max_uncompressed_size(mut self, max_size: usize) -> CompressedFrameReader<R> {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Returns a reference to the underlying FrameReader
+    pub fn get_ref(&self) -> &FrameReader<R> {
+        &self.frames
+    }
+
+    /// Returns a mutable reference to the underlying FrameReader
+    pub fn get_mut(&mut self) -> &mut FrameReader<R> {
+        &mut self.frames
+    }
+
+    /// Returns the underlying FrameReader
+    pub fn into_inner(self) -> FrameReader<R> {
+        self.frames
+    }
+
+    /// Reads and decompresses the next frame's payload
+    ///
+    /// Returns None if the stream ends cleanly before the next frame starts
+    pub fn read_frame(&mut self) -> Result<Option<Vec<u8>>> {
+
+        let header_length = match self.frames.read_frame_and_header(&mut self.buffer)? {
+            Some(header_length) => header_length as u64,
+            None => return Ok(None)
+        };
+
+        self.decompress().map(Some).map_err(|error| error.with_base_offset(header_length))
+
+    }
+
+    /// Decompresses the payload in the buffer, reporting offsets from its start
+    fn decompress(&self) -> Result<Vec<u8>> {
+
+        let mut body = ByteReader::new(&self.buffer);
+        let length = body.read_varint_u32()? as usize;
+
+        if length == 0 {
+            return Ok(body.remaining_bytes().to_vec());
+        }
+
+        if length < self.threshold {
+            return Err(Error::InvalidEncoding {
+                operation: "read_frame",
+                offset: 0,
+                reason: "compressed frame is smaller than the threshold"
+            });
+        }
+
+        prefix::check_limit(length, self.max_size, "read_frame")?;
+
+        let start = body.position() as u64;
+        let mut decoder = ZlibDecoder::new(body.remaining_bytes());
+        let mut payload = Vec::with_capacity(length);
+
+        (&mut decoder)
+            .take(length as u64 + 1)
+            .read_to_end(&mut payload)
+            .map_err(|_| Error::InvalidEncoding { operation: "read_frame", offset: start, reason: "invalid zlib data" })?;
+
+        if payload.len() != length {
+            return Err(Error::InvalidEncoding {
+                operation: "read_frame",
+                offset: start,
+                reason: "decompressed size does not match the frame's uncompressed length"
+            });
+        }
+
+        let remaining = decoder.get_ref().len() as u64;
+
+        if remaining > 0 {
+            return Err(Error::TrailingBytes {
+                operation: "read_frame",
+                offset: self.buffer.len() as u64 - remaining,
+                remaining
+            });
+        }
+
+        Ok(payload)
+
+    }
+
+}
+
+/// Writes frames, compressing payloads at or above a threshold
+#[derive(Debug)]
+pub struct CompressedFrameWriter<W: Writer> {
+    frames: FrameWriter<W>,
+    threshold: usize,
+    level: u32
+}
+
+impl<W: Writer> CompressedFrameWriter<W> {
+
+    /// Creates a CompressedFrameWriter writing frames to the given FrameWriter
+    ///
+    /// Payloads of at least `threshold` bytes are compressed
+    pub fn new(frames: FrameWriter<W>, threshold: usize) -> CompressedFrameWriter<W> {
+        CompressedFrameWriter { frames, threshold, level: DEFAULT_LEVEL }
+    }
+
+    /// Sets the compression level, from 0 (none) to 9 (best)
+    pub fn with_level(mut self, level: u32) -> CompressedFrameWriter<W> {
+        self.level = level.min(9);
+        self
+    }
+
+    /// Returns a reference to the underlying FrameWriter
+    pub fn get_ref(&self) -> &FrameWriter<W> {
+        &self.frames
+    }
+
+    /// Returns a mutable reference to the underlying FrameWriter
+    pub fn get_mut(&mut self) -> &mut FrameWriter<W> {
+        &mut self.frames
+    }
+
+    /// Returns the underlying FrameWriter
+    pub fn into_inner(self) -> FrameWriter<W> {
+        self.frames
+    }
+
+    /// Writes a frame holding the given payload, compressed if it reaches the threshold
+    pub fn write_frame(&mut self, payload: &[u8]) -> Result<()> {
+
+        let mut body = Vec::new();
+
+        if payload.len() < self.threshold {
+            body.write_varint_u32(0)?;
+            body.extend_from_slice(payload);
+            return self.frames.write_frame(&body);
+        }
+
+        prefix::check_limit(payload.len(), u32::MAX as usize, "write_frame")?;
+        body.write_varint_u32(payload.len() as u32)?;
+
+        let mut encoder = ZlibEncoder::new(body, Compression::new(self.level));
+
+        let body = encoder.write_all(payload)
+            .and_then(|_| encoder.finish())
+            .map_err(|error| Error::Io { operation: "write_frame", offset: 0, error })?;
+
+        self.frames.write_frame(&body)
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::prefix::LengthPrefix;
+
+    use std::io::ErrorKind;
+
+    fn sample() -> Vec<u8> {
+        (0..10_000u32).flat_map(|value| (value % 251).to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+
+        for format in [CompressionFormat::Zlib, CompressionFormat::Deflate] {
+
+            let mut writer = DeflateWriter::new(Vec::new(), format);
+
+            writer.write_be_u32(0xCAFEBABE).unwrap();
+            writer.write_all(&sample()).unwrap();
+
+            let mut bytes = writer.finish().unwrap();
+
+            assert!(bytes.len() < sample().len());
+
+            bytes.extend_from_slice(b"after");
+
+            let mut reader = InflateReader::new(&bytes[..], format, 1 << 20);
+            let mut payload = vec![0u8; sample().len()];
+
+            assert_eq!(0xCAFEBABE, reader.read_be_u32().unwrap());
+            reader.read_exact(&mut payload).unwrap();
+            assert_eq!(sample(), payload);
+            assert_eq!(0, reader.read(&mut [0u8; 8]).unwrap());
+            assert_eq!(4 + sample().len() as u64, reader.total_out());
+            assert_eq!(b"after", reader.into_inner());
+
+        }
+
+    }
+
+    #[test]
+    fn test_size_limit() {
+
+        let mut writer = DeflateWriter::with_level(Vec::new(), CompressionFormat::Zlib, 9);
+        writer.write_all(&vec![0u8; 1 << 20]).unwrap();
+
+        let bytes = writer.finish().unwrap();
+
+        let mut reader = InflateReader::new(&bytes[..], CompressionFormat::Zlib, 1000);
+        let error = reader.read_to_end(&mut Vec::new()).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert!(reader.total_out() <= 1000);
+
+        let mut reader = InflateReader::new(&bytes[..], CompressionFormat::Zlib, 1 << 20);
+
+        assert_eq!(1 << 20, reader.read_to_end(&mut Vec::new()).unwrap());
+
+    }
+
+    #[test]
+    fn test_frames() {
+
+        let frames = FrameWriter::new(Vec::new(), LengthPrefix::VarInt);
+        let mut writer = CompressedFrameWriter::new(frames, 64);
+
+        writer.write_frame(b"small").unwrap();
+        writer.write_frame(&sample()).unwrap();
+
+        let bytes = writer.into_inner().into_inner();
+
+        assert_eq!(&[6, 0, b's', b'm', b'a', b'l', b'l'][..], &bytes[..7]);
+        assert!(bytes.len() < sample().len());
+
+        let mut reader = CompressedFrameReader::new(FrameReader::new(&bytes[..], LengthPrefix::VarInt), 64);
+
+        assert_eq!(b"small", &reader.read_frame().unwrap().unwrap()[..]);
+        assert_eq!(sample(), reader.read_frame().unwrap().unwrap());
+        assert_eq!(None, reader.read_frame().unwrap());
+
+        let mut reader = CompressedFrameReader::new(FrameReader::new(&bytes[..], LengthPrefix::VarInt), 64)
+            .with_max_uncompressed_size(1000);
+
+        reader.read_frame().unwrap();
+
+        assert!(matches!(reader.read_frame(), Err(Error::LengthLimitExceeded { length: 40000, limit: 1000, .. })));
+
+    }
+
+    #[test]
+    fn test_invalid_frames() {
+
+        let mut writer = CompressedFrameWriter::new(FrameWriter::new(Vec::new(), LengthPrefix::VarInt), 0);
+        writer.write_frame(b"tiny").unwrap();
+
+        let bytes = writer.into_inner().into_inner();
+
+        let mut reader = CompressedFrameReader::new(FrameReader::new(&bytes[..], LengthPrefix::VarInt), 64);
+        let error = reader.read_frame().unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!(1, error.offset());
+
+        // Claim five bytes of uncompressed data where there are four
+        let mut forged = bytes.clone();
+        forged[1] = 5;
+
+        let mut reader = CompressedFrameReader::new(FrameReader::new(&forged[..], LengthPrefix::VarInt), 0);
+        let error = reader.read_frame().unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!(2, error.offset());
+
+        // Follow the zlib stream with bytes it does not account for
+        let mut padded = bytes.clone();
+        padded[0] += 2;
+        padded.extend_from_slice(&[0, 0]);
+
+        let mut reader = CompressedFrameReader::new(FrameReader::new(&padded[..], LengthPrefix::VarInt), 0);
+
+        match reader.read_frame().unwrap_err() {
+            Error::TrailingBytes { operation, offset, remaining } => {
+                assert_eq!("read_frame", operation);
+                assert_eq!(bytes.len() as u64, offset);
+                assert_eq!(2, remaining);
+            },
+            error => panic!("unexpected error {}", error)
+        }
+
+        let mut reader = CompressedFrameReader::new(FrameReader::new(&[0u8, 1, 0x80][..], LengthPrefix::BeU16), 0);
+
+        assert_eq!(3, reader.read_frame().unwrap_err().offset());
+
+        let mut corrupt = bytes;
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xFF;
+
+        let mut reader = CompressedFrameReader::new(FrameReader::new(&corrupt[..], LengthPrefix::VarInt), 0);
+
+        assert_eq!(ErrorKind::InvalidData, reader.read_frame().unwrap_err().kind());
+
+    }
+
+}
@@ -0,0 +1,244 @@
+//! Support code for the io_operations_derive macros
+//!
+//! Everything here exists to be called from generated code and is not part of the
+//! public API; it may change in any release.
+
+use crate::endian::Endian;
+use crate::error::{Error, Result};
+use crate::prefix::LengthPrefix;
+use crate::reader::{self, Reader};
+use crate::text::TextEncoding;
+use crate::value::{self, Readable, Writable};
+use crate::writer::{self, Writer};
+
+/// Numbers with a byte order, for `#[io(be)]` and `#[io(le)]`
+pub trait EndianValue : Sized {
+
+    /// Reads a value in the given byte order
+    fn read_in<R: Reader + ?Sized>(reader: &mut R, endian: Endian) -> Result<Self>;
+
+    /// Writes this value in the given byte order
+    fn write_in<W: Writer + ?Sized>(&self, writer: &mut W, endian: Endian) -> Result<()>;
+
+}
+
+macro_rules! endian_value {
+    ($($type:ty => $read:ident, $write:ident);* $(;)*) => {
+        $(
+            impl EndianValue for $type {
+                fn read_in<R: Reader + ?Sized>(reader: &mut R, endian: Endian) -> Result<$type> {
+                    reader.$read(endian)
+                }
+
+                fn write_in<W: Writer + ?Sized>(&self, writer: &mut W, endian: Endian) -> Result<()> {
+                    writer.$write(*self, endian)
+                }
+            }
+        )*
+    }
+}
+
+endian_value! {
+    u16 => read_u16_in, write_u16_in;
+    i16 => read_i16_in, write_i16_in;
+    u32 => read_u32_in, write_u32_in;
+    i32 => read_i32_in, write_i32_in;
+    u64 => read_u64_in, write_u64_in;
+    i64 => read_i64_in, write_i64_in;
+    u128 => read_u128_in, write_u128_in;
+    i128 => read_i128_in, write_i128_in;
+    f32 => read_f32_in, write_f32_in;
+    f64 => read_f64_in, write_f64_in;
+}
+
+/// Integers stored as VarInts, for `#[io(varint)]`
+pub trait VarIntValue : Sized {
+
+    /// Reads a VarInt of this width
+    fn read_varint<R: Reader + ?Sized>(reader: &mut R) -> Result<Self>;
+
+    /// Writes this value as a VarInt
+    fn write_varint<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()>;
+
+}
+
+macro_rules! varint_value {
+    ($($type:ty => $read:ident, $write:ident);* $(;)*) => {
+        $(
+            impl VarIntValue for $type {
+                fn read_varint<R: Reader + ?Sized>(reader: &mut R) -> Result<$type> {
+                    reader.$read()
+                }
+
+                fn write_varint<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+                    writer.$write(*self)
+                }
+            }
+        )*
+    }
+}
+
+varint_value! {
+    u32 => read_varint_u32, write_varint_u32;
+    i32 => read_varint_i32, write_varint_i32;
+    u64 => read_varint_u64, write_varint_u64;
+    i64 => read_varint_i64, write_varint_i64;
+}
+
+/// Signed integers stored as ZigZag VarInts, for `#[io(zigzag)]`
+pub trait ZigZagValue : Sized {
+
+    /// Reads a ZigZag VarInt of this width
+    fn read_zigzag<R: Reader + ?Sized>(reader: &mut R) -> Result<Self>;
+
+    /// Writes this value as a ZigZag VarInt
+    fn write_zigzag<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()>;
+
+}
+
+impl ZigZagValue for i32 {
+
+    fn read_zigzag<R: Reader + ?Sized>(reader: &mut R) -> Result<i32> {
+        reader.read_zigzag_i32()
+    }
+
+    fn write_zigzag<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        writer.write_zigzag_i32(*self)
+    }
+
+}
+
+impl ZigZagValue for i64 {
+
+    fn read_zigzag<R: Reader + ?Sized>(reader: &mut R) -> Result<i64> {
+        reader.read_zigzag_i64()
+    }
+
+    fn write_zigzag<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        writer.write_zigzag_i64(*self)
+    }
+
+}
+
+/// Strings and sequences with a chosen length prefix, for `#[io(prefix = "...")]`
+pub trait ReadPrefixed : Sized {
+
+    /// Reads a value preceded by its length
+    fn read_prefixed<R: Reader + ?Sized>(reader: &mut R, prefix: LengthPrefix, max_length: usize) -> Result<Self>;
+
+}
+
+/// Strings and sequences with a chosen length prefix, for `#[io(prefix = "...")]`
+pub trait WritePrefixed {
+
+    /// Writes this value preceded by its length
+    fn write_prefixed<W: Writer + ?Sized>(&self, writer: &mut W, prefix: LengthPrefix, max_length: usize) -> Result<()>;
+
+}
+
+impl ReadPrefixed for String {
+
+    fn read_prefixed<R: Reader + ?Sized>(reader: &mut R, prefix: LengthPrefix, max_length: usize) -> Result<String> {
+        reader.read_string(prefix, max_length)
+    }
+
+}
+
+impl WritePrefixed for String {
+
+    fn write_prefixed<W: Writer + ?Sized>(&self, writer: &mut W, prefix: LengthPrefix, max_length: usize) -> Result<()> {
+        value::write_str(writer, self, prefix, max_length)
+    }
+
+}
+
+impl<T: Readable> ReadPrefixed for Vec<T> {
+
+    fn read_prefixed<R: Reader + ?Sized>(reader: &mut R, prefix: LengthPrefix, max_length: usize) -> Result<Vec<T>> {
+        value::read_vec(reader, prefix, max_length)
+    }
+
+}
+
+impl<T: Writable> WritePrefixed for Vec<T> {
+
+    fn write_prefixed<W: Writer + ?Sized>(&self, writer: &mut W, prefix: LengthPrefix, max_length: usize) -> Result<()> {
+        value::write_slice(writer, self, prefix, max_length)
+    }
+
+}
+
+/// Strings and sequences of a fixed size with no prefix, for `#[io(fixed = N)]`
+///
+/// Strings are UTF-8 padded with NUL bytes; sequences must have exactly N elements
+pub trait ReadFixed : Sized {
+
+    /// Reads a value of the given fixed size
+    fn read_fixed<R: Reader + ?Sized>(reader: &mut R, size: usize) -> Result<Self>;
+
+}
+
+/// Strings and sequences of a fixed size with no prefix, for `#[io(fixed = N)]`
+pub trait WriteFixed {
+
+    /// Writes this value at the given fixed size
+    fn write_fixed<W: Writer + ?Sized>(&self, writer: &mut W, size: usize) -> Result<()>;
+
+}
+
+impl ReadFixed for String {
+
+    fn read_fixed<R: Reader + ?Sized>(reader: &mut R, size: usize) -> Result<String> {
+        reader.read_fixed_str(size, 0, TextEncoding::Utf8)
+    }
+
+}
+
+impl WriteFixed for String {
+
+    fn write_fixed<W: Writer + ?Sized>(&self, writer: &mut W, size: usize) -> Result<()> {
+        writer.write_fixed_str(self, size, 0, TextEncoding::Utf8)
+    }
+
+}
+
+impl<T: Readable> ReadFixed for Vec<T> {
+
+    fn read_fixed<R: Reader + ?Sized>(reader: &mut R, size: usize) -> Result<Vec<T>> {
+        (0..size).map(|_| T::read_from(reader)).collect()
+    }
+
+}
+
+impl<T: Writable> WriteFixed for Vec<T> {
+
+    fn write_fixed<W: Writer + ?Sized>(&self, writer: &mut W, size: usize) -> Result<()> {
+
+        if self.len() != size {
+            return Err(Error::OutOfRange {
+                operation: "write_fixed",
+                offset: 0,
+                reason: "length does not match the fixed size"
+            });
+        }
+
+        self.iter().try_for_each(|value| value.write_to(writer))
+
+    }
+
+}
+
+/// Reads an enum tag
+pub fn read_tag<R: Reader + ?Sized>(reader: &mut R, prefix: LengthPrefix) -> Result<usize> {
+    reader::read_length(reader, prefix, "read_tag")
+}
+
+/// Writes an enum tag
+pub fn write_tag<W: Writer + ?Sized>(writer: &mut W, prefix: LengthPrefix, tag: usize) -> Result<()> {
+    writer::write_length(writer, prefix, tag, "write_tag")
+}
+
+/// Creates the error for a tag that matches no variant
+pub fn unknown_tag() -> Error {
+    Error::InvalidEncoding { operation: "read_tag", offset: 0, reason: "unknown enum tag" }
+}
@@ -0,0 +1,105 @@
+//! Byte orders
+//!
+//! A byte order is either picked at compile time with one of the marker types, as in
+//! `reader.read_u32::<BigEndian>()`, or at runtime with an `Endian` value, as in
+//! `reader.read_u32_in(endian)`. The latter suits formats that declare their own byte
+//! order in a header, such as TIFF, ELF or pcap.
+
+/// A byte order chosen at runtime
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endian {
+
+    /// Most significant byte first
+    Big,
+
+    /// Least significant byte first
+    Little
+
+}
+
+impl Endian {
+
+    /// The byte order of the target platform
+    #[cfg(target_endian = "little")]
+    pub const NATIVE: Endian = Endian::Little;
+
+    /// The byte order of the target platform
+    #[cfg(target_endian = "big")]
+    pub const NATIVE: Endian = Endian::Big;
+
+    /// The byte order used by network protocols
+    pub const NETWORK: Endian = Endian::Big;
+
+    /// Picks the name of the big-endian or little-endian variant of an operation
+    pub(crate) fn select(self, big: &'static str, little: &'static str) -> &'static str {
+        match self {
+            Endian::Big => big,
+            Endian::Little => little
+        }
+    }
+
+}
+
+/// A byte order known at compile time
+///
+/// This trait is sealed; the implementations are `BigEndian` and `LittleEndian`
+pub trait ByteOrder : private::Sealed {
+
+    /// The runtime equivalent of this byte order
+    const ENDIAN: Endian;
+
+}
+
+/// Most significant byte first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BigEndian { }
+
+/// Least significant byte first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LittleEndian { }
+
+/// The byte order used by network protocols
+pub type NetworkEndian = BigEndian;
+
+/// The byte order of the target platform
+#[cfg(target_endian = "little")]
+pub type NativeEndian = LittleEndian;
+
+/// The byte order of the target platform
+#[cfg(target_endian = "big")]
+pub type NativeEndian = BigEndian;
+
+impl ByteOrder for BigEndian {
+    const ENDIAN: Endian = Endian::Big;
+}
+
+impl ByteOrder for LittleEndian {
+    const ENDIAN: Endian = Endian::Little;
+}
+
+mod private {
+
+    /// Prevents ByteOrder from being implemented outside this crate
+    pub trait Sealed { }
+
+    impl Sealed for super::BigEndian { }
+
+    impl Sealed for super::LittleEndian { }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_aliases() {
+
+        assert_eq!(Endian::NETWORK, NetworkEndian::ENDIAN);
+        assert_eq!(Endian::NATIVE, NativeEndian::ENDIAN);
+        assert_eq!(Endian::NATIVE == Endian::Little, cfg!(target_endian = "little"));
+
+    }
+
+}
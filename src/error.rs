@@ -0,0 +1,289 @@
+//! Errors produced by I/O operations
+
+use std::error;
+use std::fmt;
+use std::io;
+
+/// A specialized Result type for Reader and Writer operations
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+/// An error produced by a Reader or Writer operation
+///
+/// Every variant records the name of the operation that failed, such as `read_be_u32`,
+/// and the offset at which it failed. A plain `Read` or `Write` stream cannot tell us
+/// where it is, so offsets count the bytes the failing operation had already consumed
+/// or produced; use `with_base_offset` to turn that into an absolute stream offset.
+///
+/// Errors convert into `std::io::Error`, so they can be propagated with `?` from
+/// functions that return `std::io::Result`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+
+    /// The stream ended before the value was complete
+    Truncated {
+        /// The operation that failed
+        operation: &'static str,
+        /// The offset at which the stream ended
+        offset: u64,
+        /// The number of bytes that were still needed
+        needed: u64
+    },
+
+    /// The bytes read do not form a valid encoding of the value
+    InvalidEncoding {
+        /// The operation that failed
+        operation: &'static str,
+        /// The offset of the offending byte
+        offset: u64,
+        /// Why the encoding was rejected
+        reason: &'static str
+    },
+
+    /// A value does not fit in the range the operation supports
+    OutOfRange {
+        /// The operation that failed
+        operation: &'static str,
+        /// The offset of the offending value
+        offset: u64,
+        /// Which range was exceeded
+        reason: &'static str
+    },
+
+    /// A length exceeds the limit allowed by the caller or the encoding
+    LengthLimitExceeded {
+        /// The operation that failed
+        operation: &'static str,
+        /// The offset of the offending length
+        offset: u64,
+        /// The length that was requested
+        length: u64,
+        /// The largest length that is allowed
+        limit: u64
+    },
+
+    /// A checksum computed over the data does not match the one stored with it
+    ChecksumMismatch {
+        /// The operation that failed
+        operation: &'static str,
+        /// The offset of the stored checksum
+        offset: u64,
+        /// The checksum stored with the data
+        expected: u32,
+        /// The checksum computed over the data
+        actual: u32
+    },
+
+    /// A message was not fully consumed
+    TrailingBytes {
+        /// The operation that failed
+        operation: &'static str,
+        /// The offset of the first unconsumed byte
+        offset: u64,
+        /// The number of bytes left unconsumed
+        remaining: u64
+    },
+
+    /// A caller-supplied implementation, such as a serde `Serialize`, reported an error
+    Custom {
+        /// The operation that failed
+        operation: &'static str,
+        /// The offset at which the error was reported
+        offset: u64,
+        /// The error message
+        message: String
+    },
+
+    /// The underlying stream reported an error
+    Io {
+        /// The operation that failed
+        operation: &'static str,
+        /// The offset at which the stream failed
+        offset: u64,
+        /// The error reported by the stream
+        error: io::Error
+    }
+
+}
+
+impl Error {
+
+    /// Returns the name of the operation that failed
+    pub fn operation(&self) -> &'static str {
+        match *self {
+            Error::Truncated { operation, .. } |
+            Error::InvalidEncoding { operation, .. } |
+            Error::OutOfRange { operation, .. } |
+            Error::LengthLimitExceeded { operation, .. } |
+            Error::ChecksumMismatch { operation, .. } |
+            Error::TrailingBytes { operation, .. } |
+            Error::Custom { operation, .. } |
+            Error::Io { operation, .. } => operation
+        }
+    }
+
+    /// Returns the offset at which the operation failed
+    pub fn offset(&self) -> u64 {
+        match *self {
+            Error::Truncated { offset, .. } |
+            Error::InvalidEncoding { offset, .. } |
+            Error::OutOfRange { offset, .. } |
+            Error::LengthLimitExceeded { offset, .. } |
+            Error::ChecksumMismatch { offset, .. } |
+            Error::TrailingBytes { offset, .. } |
+            Error::Custom { offset, .. } |
+            Error::Io { offset, .. } => offset
+        }
+    }
+
+    /// Returns the std::io::ErrorKind this error converts to
+    pub fn kind(&self) -> io::ErrorKind {
+        match *self {
+            Error::Truncated { .. } => io::ErrorKind::UnexpectedEof,
+            Error::InvalidEncoding { .. } |
+            Error::OutOfRange { .. } |
+            Error::LengthLimitExceeded { .. } |
+            Error::ChecksumMismatch { .. } |
+            Error::TrailingBytes { .. } |
+            Error::Custom { .. } => io::ErrorKind::InvalidData,
+            Error::Io { ref error, .. } => error.kind()
+        }
+    }
+
+    /// Shifts the offset of this error by the given number of bytes
+    ///
+    /// This is useful when the position at which the failing operation started is
+    /// known, for example from `Seek::stream_position`
+    pub fn with_base_offset(mut self, base: u64) -> Error {
+        match self {
+            Error::Truncated { ref mut offset, .. } |
+            Error::InvalidEncoding { ref mut offset, .. } |
+            Error::OutOfRange { ref mut offset, .. } |
+            Error::LengthLimitExceeded { ref mut offset, .. } |
+            Error::ChecksumMismatch { ref mut offset, .. } |
+            Error::TrailingBytes { ref mut offset, .. } |
+            Error::Custom { ref mut offset, .. } |
+            Error::Io { ref mut offset, .. } => *offset += base
+        }
+        self
+    }
+
+}
+
+impl fmt::Display for Error {
+
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(formatter, "{} failed at offset {}: ", self.operation(), self.offset())?;
+
+        match *self {
+            Error::Truncated { needed, .. } => {
+                write!(formatter, "stream ended {} bytes short (end of stream?)", needed)
+            },
+            Error::InvalidEncoding { reason, .. } => {
+                write!(formatter, "invalid encoding ({})", reason)
+            },
+            Error::OutOfRange { reason, .. } => {
+                write!(formatter, "value out of range ({})", reason)
+            },
+            Error::LengthLimitExceeded { length, limit, .. } => {
+                write!(formatter, "length {} exceeds the limit of {}", length, limit)
+            },
+            Error::ChecksumMismatch { expected, actual, .. } => {
+                write!(formatter, "checksum {:#010x} does not match the stored {:#010x}", actual, expected)
+            },
+            Error::TrailingBytes { remaining, .. } => {
+                write!(formatter, "{} bytes were left unconsumed", remaining)
+            },
+            Error::Custom { ref message, .. } => {
+                write!(formatter, "{}", message)
+            },
+            Error::Io { ref error, .. } => {
+                write!(formatter, "{}", error)
+            }
+        }
+
+    }
+
+}
+
+impl error::Error for Error {
+
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io { ref error, .. } => Some(error),
+            _ => None
+        }
+    }
+
+}
+
+impl From<Error> for io::Error {
+
+    fn from(error: Error) -> io::Error {
+        io::Error::new(error.kind(), error)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Error;
+
+    use std::io;
+
+    #[test]
+    fn test_into_io_error_keeps_kind() {
+
+        let error = Error::Truncated { operation: "read_be_u32", offset: 2, needed: 2 };
+        let error: io::Error = error.into();
+
+        assert_eq!(io::ErrorKind::UnexpectedEof, error.kind());
+
+        let error = Error::Io {
+            operation: "write_u8",
+            offset: 0,
+            error: io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed")
+        };
+        let error: io::Error = error.into();
+
+        assert_eq!(io::ErrorKind::BrokenPipe, error.kind());
+
+    }
+
+    #[test]
+    fn test_into_io_error_keeps_context() {
+
+        let error = Error::InvalidEncoding { operation: "read_string", offset: 7, reason: "invalid UTF-8" };
+        let error: io::Error = error.into();
+
+        let inner = error.get_ref().unwrap().downcast_ref::<Error>().unwrap();
+
+        assert_eq!("read_string", inner.operation());
+        assert_eq!(7, inner.offset());
+
+    }
+
+    #[test]
+    fn test_with_base_offset() {
+
+        let error = Error::LengthLimitExceeded { operation: "read_string", offset: 0, length: 9, limit: 4 };
+
+        assert_eq!(100, error.with_base_offset(100).offset());
+
+    }
+
+    #[test]
+    fn test_display() {
+
+        let error = Error::Truncated { operation: "read_be_u64", offset: 3, needed: 5 };
+
+        assert_eq!(
+            "read_be_u64 failed at offset 3: stream ended 5 bytes short (end of stream?)",
+            error.to_string()
+        );
+
+    }
+
+}
@@ -0,0 +1,470 @@
+//! Length-delimited frames for stream transports such as TCP
+//!
+//! A frame is a length header followed by that many bytes of payload. The header is any
+//! LengthPrefix, and may count either the payload alone or the payload and the header
+//! together; both ends of a connection must agree on the choice. Frame sizes are
+//! checked against a maximum as soon as the header is read, before anything is
+//! allocated, so a hostile peer cannot make a FrameReader reserve more than it allows.
+//!
+//! Frames can also end with a checksum of their payload, stored as a u32 trailer and
+//! verified as each frame is read. The length in the header then counts the trailer too.
+
+use std::io::{ErrorKind, Read};
+
+use crate::checksum::ChecksumAlgorithm;
+use crate::endian::Endian;
+use crate::error::{Error, Result};
+use crate::prefix::{self, LengthPrefix};
+use crate::reader::{self, Reader};
+use crate::value::DEFAULT_MAX_LENGTH;
+use crate::varint;
+use crate::writer::{self, Writer};
+
+/// Reads length-delimited frames from a Reader
+#[derive(Debug)]
+pub struct FrameReader<R: Reader> {
+    reader: R,
+    header: LengthPrefix,
+    header_in_length: bool,
+    max_frame_size: usize,
+    checksum: Option<(ChecksumAlgorithm, Endian)>
+}
+
+impl<R: Reader> FrameReader<R> {
+
+    /// Creates a FrameReader whose frames start with the given header
+    ///
+    /// The header counts the payload alone, and payloads are limited to
+    /// `DEFAULT_MAX_LENGTH` bytes
+    pub fn new(reader: R, header: LengthPrefix) -> FrameReader<R> {
+        FrameReader { reader, header, header_in_length: false, max_frame_size: DEFAULT_MAX_LENGTH, checksum: None }
+    }
+
+    /// Sets whether the length in the header counts the header itself
+    pub fn with_header_in_length(mut self, header_in_length: bool) -> FrameReader<R> {
+        self.header_in_length = header_in_length;
+        self
+    }
+
+    /// Sets the largest payload, in bytes, that will be read
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> FrameReader<R> {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Ends every frame with a checksum of its payload, stored in the given byte order
+    pub fn with_checksum(mut self, algorithm: ChecksumAlgorithm, endian: Endian) -> FrameReader<R> {
+        self.checksum = Some((algorithm, endian));
+        self
+    }
+
+    /// Returns a reference to the underlying Reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the underlying Reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the underlying Reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next frame's payload
+    ///
+    /// Returns None if the stream ends cleanly before the next frame starts. A stream
+    /// that ends part way through a frame is reported as truncated, and a payload that
+    /// does not match its checksum as a `ChecksumMismatch`
+    pub fn read_frame(&mut self) -> Result<Option<Vec<u8>>> {
+
+        let mut payload = Vec::new();
+
+        Ok(self.read_frame_into(&mut payload)?.then_some(payload))
+
+    }
+
+    /// Reads the next frame's payload into the given buffer, replacing its contents
+    ///
+    /// This lets a single buffer be reused for every frame. Returns false, leaving the
+    /// buffer empty, if the stream ends cleanly before the next frame starts
+    pub fn read_frame_into(&mut self, payload: &mut Vec<u8>) -> Result<bool> {
+        Ok(self.read_frame_and_header(payload)?.is_some())
+    }
+
+    /// Reads the next frame's payload into the given buffer, returning the length of its header
+    pub(crate) fn read_frame_and_header(&mut self, payload: &mut Vec<u8>) -> Result<Option<usize>> {
+
+        payload.clear();
+
+        let mut first = [0u8; 1];
+
+        loop {
+            match self.reader.read(&mut first) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(ref error) if error.kind() == ErrorKind::Interrupted => { },
+                Err(error) => return Err(Error::Io { operation: "read_frame", offset: 0, error })
+            }
+        }
+
+        let (length, header_length) = reader::read_length_counted(&mut first.chain(&mut self.reader), self.header, "read_frame")?;
+
+        let overhead = trailer_length(self.checksum) + if self.header_in_length { header_length } else { 0 };
+
+        let payload_length = length.checked_sub(overhead).ok_or(Error::InvalidEncoding {
+            operation: "read_frame",
+            offset: 0,
+            reason: "frame length is too short for its header and trailer"
+        })?;
+
+        prefix::check_limit(payload_length, self.max_frame_size, "read_frame")?;
+
+        payload.resize(payload_length, 0);
+
+        reader::fill_buffer(&mut self.reader, payload, "read_frame")
+            .map_err(|error| error.with_base_offset(header_length as u64))?;
+
+        if let Some((algorithm, endian)) = self.checksum {
+
+            let trailer_offset = (header_length + payload_length) as u64;
+
+            let mut raw_buffer = [0u8; 4];
+
+            reader::fill_buffer(&mut self.reader, &mut raw_buffer, "read_frame")
+                .map_err(|error| error.with_base_offset(trailer_offset))?;
+
+            let expected = match endian {
+                Endian::Big => u32::from_be_bytes(raw_buffer),
+                Endian::Little => u32::from_le_bytes(raw_buffer)
+            };
+            let actual = algorithm.checksum(payload);
+
+            if expected != actual {
+                return Err(Error::ChecksumMismatch { operation: "read_frame", offset: trailer_offset, expected, actual });
+            }
+
+        }
+
+        Ok(Some(header_length))
+
+    }
+
+}
+
+/// Writes length-delimited frames to a Writer
+#[derive(Debug)]
+pub struct FrameWriter<W: Writer> {
+    writer: W,
+    header: LengthPrefix,
+    header_in_length: bool,
+    max_frame_size: usize,
+    checksum: Option<(ChecksumAlgorithm, Endian)>
+}
+
+impl<W: Writer> FrameWriter<W> {
+
+    /// Creates a FrameWriter whose frames start with the given header
+    ///
+    /// The header counts the payload alone, and payloads are limited to
+    /// `DEFAULT_MAX_LENGTH` bytes
+    pub fn new(writer: W, header: LengthPrefix) -> FrameWriter<W> {
+        FrameWriter { writer, header, header_in_length: false, max_frame_size: DEFAULT_MAX_LENGTH, checksum: None }
+    }
+
+    /// Sets whether the length in the header counts the header itself
+    pub fn with_header_in_length(mut self, header_in_length: bool) -> FrameWriter<W> {
+        self.header_in_length = header_in_length;
+        self
+    }
+
+    /// Sets the largest payload, in bytes, that will be written
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> FrameWriter<W> {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Ends every frame with a checksum of its payload, stored in the given byte order
+    pub fn with_checksum(mut self, algorithm: ChecksumAlgorithm, endian: Endian) -> FrameWriter<W> {
+        self.checksum = Some((algorithm, endian));
+        self
+    }
+
+    /// Returns a reference to the underlying Writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the underlying Writer
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns the underlying Writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes a frame holding the given payload
+    ///
+    /// Payloads larger than the maximum frame size, or than the header can describe, are
+    /// rejected before anything is written
+    pub fn write_frame(&mut self, payload: &[u8]) -> Result<()> {
+
+        prefix::check_limit(payload.len(), self.max_frame_size, "write_frame")?;
+
+        let body_length = payload.len() + trailer_length(self.checksum);
+
+        let length = if self.header_in_length {
+            length_with_header(self.header, body_length)
+        } else {
+            body_length
+        };
+
+        let mut raw_buffer = [0u8; varint::MAX_LENGTH_64];
+        let header_length = self.header.encode(length, &mut raw_buffer, "write_frame")?;
+
+        writer::write_buffer(&mut self.writer, &raw_buffer[..header_length], "write_frame")?;
+
+        writer::write_buffer(&mut self.writer, payload, "write_frame")
+            .map_err(|error| error.with_base_offset(header_length as u64))?;
+
+        if let Some((algorithm, endian)) = self.checksum {
+
+            let raw_buffer = match endian {
+                Endian::Big => algorithm.checksum(payload).to_be_bytes(),
+                Endian::Little => algorithm.checksum(payload).to_le_bytes()
+            };
+
+            writer::write_buffer(&mut self.writer, &raw_buffer, "write_frame")
+                .map_err(|error| error.with_base_offset((header_length + payload.len()) as u64))?;
+
+        }
+
+        Ok(())
+
+    }
+
+}
+
+/// Returns the number of bytes in each frame's trailer
+fn trailer_length(checksum: Option<(ChecksumAlgorithm, Endian)>) -> usize {
+    if checksum.is_some() { 4 } else { 0 }
+}
+
+/// Returns the length of a frame including its header
+///
+/// A VarInt header can grow by a byte when the header's own size is added to the
+/// payload's, so the length is settled by re-measuring until it stops changing
+fn length_with_header(header: LengthPrefix, payload_length: usize) -> usize {
+
+    // Lengths beyond what the header holds are measured as its widest encoding, so the
+    // loop still settles; the header then rejects them when it is encoded
+    let widest = header.encoded_len(header.max_length()).unwrap_or_default();
+    let measure = |length: usize| payload_length.saturating_add(header.encoded_len(length).unwrap_or(widest));
+
+    let mut length = measure(payload_length);
+
+    while measure(length) != length {
+        length = measure(length);
+    }
+
+    length
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn test_round_trip() {
+
+        let headers = [
+            LengthPrefix::U8,
+            LengthPrefix::BeU16,
+            LengthPrefix::LeU16,
+            LengthPrefix::BeU32,
+            LengthPrefix::LeU32,
+            LengthPrefix::VarInt
+        ];
+
+        for header in headers {
+            for header_in_length in [false, true] {
+
+                let payloads = [&b""[..], &b"ping"[..], &[7u8; 200][..]];
+
+                let mut writer = FrameWriter::new(Vec::new(), header).with_header_in_length(header_in_length);
+
+                for payload in payloads {
+                    writer.write_frame(payload).unwrap();
+                }
+
+                let bytes = writer.into_inner();
+                let mut reader = FrameReader::new(&bytes[..], header).with_header_in_length(header_in_length);
+
+                for payload in payloads {
+                    assert_eq!(payload, &reader.read_frame().unwrap().unwrap()[..]);
+                }
+
+                assert_eq!(None, reader.read_frame().unwrap());
+
+            }
+        }
+
+    }
+
+    #[test]
+    fn test_layout() {
+
+        let mut writer = FrameWriter::new(Vec::new(), LengthPrefix::BeU16);
+        writer.write_frame(b"abc").unwrap();
+
+        assert_eq!(vec![0, 3, b'a', b'b', b'c'], writer.into_inner());
+
+        let mut writer = FrameWriter::new(Vec::new(), LengthPrefix::LeU32).with_header_in_length(true);
+        writer.write_frame(b"abc").unwrap();
+
+        assert_eq!(vec![7, 0, 0, 0, b'a', b'b', b'c'], writer.into_inner());
+
+        // 126 bytes of payload and a one-byte header make 127, which still fits in one
+        // byte; 127 bytes of payload push the VarInt header to two bytes
+        assert_eq!(127, length_with_header(LengthPrefix::VarInt, 126));
+        assert_eq!(129, length_with_header(LengthPrefix::VarInt, 127));
+        assert_eq!(u32::MAX as usize + 4, length_with_header(LengthPrefix::VarInt, u32::MAX as usize - 1));
+
+    }
+
+    #[test]
+    fn test_checksums() {
+
+        let algorithms = [ChecksumAlgorithm::Crc32, ChecksumAlgorithm::Crc32c, ChecksumAlgorithm::Adler32];
+
+        for algorithm in algorithms {
+
+            let mut writer = FrameWriter::new(Vec::new(), LengthPrefix::VarInt)
+                .with_header_in_length(true)
+                .with_checksum(algorithm, Endian::Little);
+
+            writer.write_frame(b"first").unwrap();
+            writer.write_frame(b"").unwrap();
+
+            let bytes = writer.into_inner();
+            let mut reader = FrameReader::new(&bytes[..], LengthPrefix::VarInt)
+                .with_header_in_length(true)
+                .with_checksum(algorithm, Endian::Little);
+
+            assert_eq!(b"first", &reader.read_frame().unwrap().unwrap()[..]);
+            assert_eq!(b"", &reader.read_frame().unwrap().unwrap()[..]);
+            assert_eq!(None, reader.read_frame().unwrap());
+
+        }
+
+        let mut writer = FrameWriter::new(Vec::new(), LengthPrefix::BeU16).with_checksum(ChecksumAlgorithm::Crc32, Endian::Big);
+        writer.write_frame(b"123456789").unwrap();
+
+        let mut bytes = writer.into_inner();
+
+        assert_eq!(&[0, 13][..], &bytes[..2]);
+        assert_eq!(&[0xCB, 0xF4, 0x39, 0x26][..], &bytes[11..]);
+
+        bytes[5] ^= 0x10;
+
+        let mut reader = FrameReader::new(&bytes[..], LengthPrefix::BeU16).with_checksum(ChecksumAlgorithm::Crc32, Endian::Big);
+
+        match reader.read_frame().unwrap_err() {
+            Error::ChecksumMismatch { operation, offset, expected, actual } => {
+                assert_eq!("read_frame", operation);
+                assert_eq!(11, offset);
+                assert_eq!(0xCBF43926, expected);
+                assert_eq!(ChecksumAlgorithm::Crc32.checksum(&bytes[2..11]), actual);
+            },
+            error => panic!("unexpected error {}", error)
+        }
+
+    }
+
+    #[test]
+    fn test_oversized_frames() {
+
+        let mut writer = FrameWriter::new(Vec::new(), LengthPrefix::VarInt).with_max_frame_size(4);
+        let error = writer.write_frame(b"too long").unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert!(writer.get_ref().is_empty());
+
+        let mut writer = FrameWriter::new(Vec::new(), LengthPrefix::U8);
+
+        assert!(writer.write_frame(&[0u8; 256]).is_err());
+        assert!(writer.get_ref().is_empty());
+
+        // A header claiming 4 GiB is rejected without reserving the memory
+        let mut reader = FrameReader::new(&[0xFF, 0xFF, 0xFF, 0xFF][..], LengthPrefix::BeU32);
+
+        match reader.read_frame().unwrap_err() {
+            Error::LengthLimitExceeded { operation, length, limit, .. } => {
+                assert_eq!("read_frame", operation);
+                assert_eq!(u32::MAX as u64, length);
+                assert_eq!(DEFAULT_MAX_LENGTH as u64, limit);
+            },
+            error => panic!("unexpected error {}", error)
+        }
+
+    }
+
+    #[test]
+    fn test_truncated_frames() {
+
+        let mut reader = FrameReader::new(Cursor::new(vec![0, 5, 1, 2]), LengthPrefix::BeU16);
+        let error = reader.read_frame().unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        assert_eq!(4, error.offset());
+
+        let mut reader = FrameReader::new(&[0u8][..], LengthPrefix::BeU16);
+
+        assert_eq!(ErrorKind::UnexpectedEof, reader.read_frame().unwrap_err().kind());
+
+        let mut reader = FrameReader::new(&[0u8][..], LengthPrefix::VarInt).with_header_in_length(true);
+
+        assert_eq!(ErrorKind::InvalidData, reader.read_frame().unwrap_err().kind());
+
+    }
+
+    #[test]
+    fn test_padded_headers() {
+
+        // A header of 6 padded out to two bytes, which would leave a four-byte payload
+        // if the header were assumed to take the single byte 6 encodes to
+        let bytes = [0x86u8, 0x00, 1, 2, 3, 4];
+        let mut reader = FrameReader::new(&bytes[..], LengthPrefix::VarInt).with_header_in_length(true);
+
+        match reader.read_frame().unwrap_err() {
+            Error::InvalidEncoding { operation, offset, .. } => {
+                assert_eq!("read_frame", operation);
+                assert_eq!(1, offset);
+            },
+            error => panic!("unexpected error {}", error)
+        }
+
+        let mut payload = vec![7u8; 300];
+        let mut writer = FrameWriter::new(Vec::new(), LengthPrefix::VarInt).with_header_in_length(true);
+
+        writer.write_frame(&payload).unwrap();
+        writer.write_frame(&[8]).unwrap();
+
+        let bytes = writer.into_inner();
+        let mut reader = FrameReader::new(&bytes[..], LengthPrefix::VarInt).with_header_in_length(true);
+
+        assert!(reader.read_frame_into(&mut payload).unwrap());
+        assert_eq!(vec![7u8; 300], payload);
+        assert_eq!(Some(vec![8]), reader.read_frame().unwrap());
+
+    }
+
+}
@@ -0,0 +1,120 @@
+//! Async reads and writes over futures-io's AsyncRead and AsyncWrite
+//!
+//! These are the traits used by async-std, smol and the `futures` crate. AsyncReader and
+//! AsyncWriter are defined by the same macro as their tokio counterparts, so the two
+//! integrations offer exactly the same methods, byte layouts and errors.
+//!
+//! These methods are not cancellation safe. Dropping one of their futures part way
+//! through leaves the stream at an unknown position.
+
+use std::future;
+use std::io;
+use std::pin::Pin;
+
+use futures_io::{AsyncRead, AsyncWrite};
+
+crate::async_io::async_extensions! {
+    read: futures_io::AsyncRead,
+    write: futures_io::AsyncWrite,
+    library: "futures-io's"
+}
+
+/// Reads whatever is available into the buffer
+async fn read_some<R: AsyncRead + Unpin + ?Sized>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    future::poll_fn(|context| Pin::new(&mut *reader).poll_read(context, buffer)).await
+}
+
+/// Writes as much of the buffer as the writer will take
+async fn write_some<W: AsyncWrite + Unpin + ?Sized>(writer: &mut W, buffer: &[u8]) -> io::Result<usize> {
+    future::poll_fn(|context| Pin::new(&mut *writer).poll_write(context, buffer)).await
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{AsyncReader, AsyncWriter};
+
+    use crate::endian::{BigEndian, Endian, LittleEndian};
+    use crate::prefix::LengthPrefix;
+    use crate::text::TextEncoding;
+    use crate::writer;
+
+    use futures::executor::block_on;
+
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_round_trip() {
+
+        block_on(async {
+
+            let mut bytes = Vec::new();
+
+            bytes.write_u8(1).await.unwrap();
+            bytes.write_le_i16(-2).await.unwrap();
+            bytes.write_u32::<BigEndian>(3).await.unwrap();
+            bytes.write_f64_in(4.5, Endian::Little).await.unwrap();
+            bytes.write_le_u16_slice(&[5, 6]).await.unwrap();
+            bytes.write_zigzag_i64(-7).await.unwrap();
+            bytes.write_string("futures", LengthPrefix::VarInt).await.unwrap();
+            bytes.write_cstring("io", TextEncoding::Ascii).await.unwrap();
+
+            let mut reader = &bytes[..];
+            let mut values = [0u16; 2];
+
+            assert_eq!(1, reader.read_u8().await.unwrap());
+            assert_eq!(-2, reader.read_le_i16().await.unwrap());
+            assert_eq!(3, reader.read_u32::<BigEndian>().await.unwrap());
+            assert_eq!(4.5, reader.read_f64_in(Endian::Little).await.unwrap());
+            reader.read_u16_into::<LittleEndian>(&mut values).await.unwrap();
+            assert_eq!([5, 6], values);
+            assert_eq!(-7, reader.read_zigzag_i64().await.unwrap());
+            assert_eq!("futures", reader.read_string(LengthPrefix::VarInt, 16).await.unwrap());
+            assert_eq!("io", reader.read_cstring(16, TextEncoding::Ascii).await.unwrap());
+            assert!(reader.is_empty());
+
+        });
+
+    }
+
+    #[test]
+    fn test_matches_sync_layout() {
+
+        let mut expected = Vec::new();
+
+        writer::Writer::write_be_u64(&mut expected, 0x0102030405060708).unwrap();
+        writer::Writer::write_java_utf(&mut expected, "layout\0").unwrap();
+
+        let mut actual = Vec::new();
+
+        block_on(async {
+            actual.write_be_u64(0x0102030405060708).await.unwrap();
+            actual.write_java_utf("layout\0").await.unwrap();
+        });
+
+        assert_eq!(expected, actual);
+
+    }
+
+    #[test]
+    fn test_errors() {
+
+        block_on(async {
+
+            let mut reader = &[0x80u8, 0x80][..];
+            let error = reader.read_varint_u32().await.unwrap_err();
+
+            assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+            assert_eq!("read_varint_u32", error.operation());
+            assert_eq!(2, error.offset());
+
+            let mut bytes = Vec::new();
+
+            assert!(bytes.write_fixed_str("too long", 4, 0, TextEncoding::Utf8).await.is_err());
+            assert!(bytes.is_empty());
+
+        });
+
+    }
+
+}
@@ -1,15 +1,84 @@
 //! Various I/O operations for Rust
 
+#[cfg(test)]
+#[macro_use]
+extern crate quickcheck;
+
+pub mod error;
+
 pub mod reader;
 
 pub mod writer;
 
+pub mod varint;
+
+pub mod prefix;
+
+pub mod endian;
+
+pub mod text;
+
+pub mod byte_reader;
+
+pub mod byte_buffer;
+
+pub mod bits;
+
+pub mod frame;
+
+pub mod checksum;
+
+pub mod limited;
+
+pub mod value;
+
+#[doc(hidden)]
+pub mod derive_support;
+
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+#[cfg(feature = "futures-io")]
+pub mod futures_io;
+
+#[cfg(feature = "serde")]
+pub mod serde;
+
+#[cfg(feature = "compression")]
+pub mod compression;
+
+mod bulk;
+
+mod codec;
+
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+mod async_io;
+
+pub use bits::{BitOrder, BitReader, BitWriter};
+pub use byte_buffer::{ByteBuffer, Reservation};
+pub use byte_reader::ByteReader;
+pub use checksum::{Checksum, ChecksumAlgorithm, ChecksumReader, ChecksumWriter};
+pub use endian::{BigEndian, ByteOrder, Endian, LittleEndian, NativeEndian, NetworkEndian};
+pub use error::{Error, Result};
+pub use frame::{FrameReader, FrameWriter};
+pub use limited::LimitedReader;
+pub use prefix::{LengthPrefix, PrefixKind};
+pub use text::{SurrogatePolicy, TextEncoding};
+pub use value::{Prefixed, Readable, Writable};
+
+#[cfg(feature = "derive")]
+pub use io_operations_derive::{Readable, Writable};
+
 #[cfg(test)]
 mod test {
 
     use super::reader::Reader;
     use super::writer::Writer;
+    use super::prefix::LengthPrefix;
+    use super::endian::{BigEndian, Endian, LittleEndian, NativeEndian, NetworkEndian};
+    use super::text::{SurrogatePolicy, TextEncoding};
 
+    use std::io;
     use std::io::Cursor;
 
     #[test]
@@ -222,4 +291,689 @@ mod test {
 
     }
 
+    #[test]
+    fn test_read_write_be_u128() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_be_u128(340282366920938463463374607431768211455).is_ok());
+        assert!(vector.write_be_u128(0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210).is_ok());
+
+        vector.set_position(0);
+
+        assert_eq!(340282366920938463463374607431768211455, vector.read_be_u128().unwrap());
+        assert_eq!(0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210, vector.read_be_u128().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_be_i128() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_be_i128(-170141183460469231731687303715884105728).is_ok());
+        assert!(vector.write_be_i128(0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210).is_ok());
+
+        vector.set_position(0);
+
+        assert_eq!(-170141183460469231731687303715884105728, vector.read_be_i128().unwrap());
+        assert_eq!(0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210, vector.read_be_i128().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_le_u128() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_le_u128(340282366920938463463374607431768211455).is_ok());
+        assert!(vector.write_le_u128(0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210).is_ok());
+
+        vector.set_position(0);
+
+        assert_eq!(340282366920938463463374607431768211455, vector.read_le_u128().unwrap());
+        assert_eq!(0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210, vector.read_le_u128().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_le_i128() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_le_i128(-170141183460469231731687303715884105728).is_ok());
+        assert!(vector.write_le_i128(0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210).is_ok());
+
+        vector.set_position(0);
+
+        assert_eq!(-170141183460469231731687303715884105728, vector.read_le_i128().unwrap());
+        assert_eq!(0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210, vector.read_le_i128().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_be_f32() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_be_f32(-1234.5678).is_ok());
+        assert!(vector.write_be_f32(::std::f32::consts::PI).is_ok());
+
+        vector.set_position(0);
+
+        assert_eq!(-1234.5678, vector.read_be_f32().unwrap());
+        assert_eq!(::std::f32::consts::PI, vector.read_be_f32().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_be_f32_special_values() {
+
+        let values = [
+            f32::from_bits(0x7FA0_0001),
+            0.0,
+            -0.0,
+            f32::INFINITY,
+            f32::NEG_INFINITY
+        ];
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        for value in values.iter() {
+            assert!(vector.write_be_f32(*value).is_ok());
+        }
+
+        vector.set_position(0);
+
+        for value in values.iter() {
+            assert_eq!(value.to_bits(), vector.read_be_f32().unwrap().to_bits());
+        }
+
+    }
+
+    #[test]
+    fn test_read_write_be_f64() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_be_f64(-1234.5678).is_ok());
+        assert!(vector.write_be_f64(::std::f64::consts::PI).is_ok());
+
+        vector.set_position(0);
+
+        assert_eq!(-1234.5678, vector.read_be_f64().unwrap());
+        assert_eq!(::std::f64::consts::PI, vector.read_be_f64().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_be_f64_special_values() {
+
+        let values = [
+            f64::from_bits(0xFFF0_0000_DEAD_BEEF),
+            0.0,
+            -0.0,
+            f64::INFINITY,
+            f64::NEG_INFINITY
+        ];
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        for value in values.iter() {
+            assert!(vector.write_be_f64(*value).is_ok());
+        }
+
+        vector.set_position(0);
+
+        for value in values.iter() {
+            assert_eq!(value.to_bits(), vector.read_be_f64().unwrap().to_bits());
+        }
+
+    }
+
+    #[test]
+    fn test_read_write_le_f32() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_le_f32(-1234.5678).is_ok());
+        assert!(vector.write_le_f32(::std::f32::consts::PI).is_ok());
+
+        vector.set_position(0);
+
+        assert_eq!(-1234.5678, vector.read_le_f32().unwrap());
+        assert_eq!(::std::f32::consts::PI, vector.read_le_f32().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_le_f32_special_values() {
+
+        let values = [
+            f32::from_bits(0x7FA0_0001),
+            0.0,
+            -0.0,
+            f32::INFINITY,
+            f32::NEG_INFINITY
+        ];
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        for value in values.iter() {
+            assert!(vector.write_le_f32(*value).is_ok());
+        }
+
+        vector.set_position(0);
+
+        for value in values.iter() {
+            assert_eq!(value.to_bits(), vector.read_le_f32().unwrap().to_bits());
+        }
+
+    }
+
+    #[test]
+    fn test_read_write_le_f64() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_le_f64(-1234.5678).is_ok());
+        assert!(vector.write_le_f64(::std::f64::consts::PI).is_ok());
+
+        vector.set_position(0);
+
+        assert_eq!(-1234.5678, vector.read_le_f64().unwrap());
+        assert_eq!(::std::f64::consts::PI, vector.read_le_f64().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_le_f64_special_values() {
+
+        let values = [
+            f64::from_bits(0xFFF0_0000_DEAD_BEEF),
+            0.0,
+            -0.0,
+            f64::INFINITY,
+            f64::NEG_INFINITY
+        ];
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        for value in values.iter() {
+            assert!(vector.write_le_f64(*value).is_ok());
+        }
+
+        vector.set_position(0);
+
+        for value in values.iter() {
+            assert_eq!(value.to_bits(), vector.read_le_f64().unwrap().to_bits());
+        }
+
+    }
+
+    #[test]
+    fn test_read_write_varint_u32() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_varint_u32(25565).is_ok());
+        assert!(vector.write_varint_u32(4000000000).is_ok());
+
+        assert_eq!(vec![0xDD, 0xC7, 0x01, 0x80, 0xD0, 0xAC, 0xF3, 0x0E], *vector.get_ref());
+
+        vector.set_position(0);
+
+        assert_eq!(25565, vector.read_varint_u32().unwrap());
+        assert_eq!(4000000000, vector.read_varint_u32().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_varint_i32() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_varint_i32(-1).is_ok());
+        assert!(vector.write_varint_i32(i32::MIN).is_ok());
+        assert!(vector.write_varint_i32(2147483647).is_ok());
+
+        assert_eq!(vec![
+            0xFF, 0xFF, 0xFF, 0xFF, 0x0F,
+            0x80, 0x80, 0x80, 0x80, 0x08,
+            0xFF, 0xFF, 0xFF, 0xFF, 0x07
+        ], *vector.get_ref());
+
+        vector.set_position(0);
+
+        assert_eq!(-1, vector.read_varint_i32().unwrap());
+        assert_eq!(i32::MIN, vector.read_varint_i32().unwrap());
+        assert_eq!(2147483647, vector.read_varint_i32().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_varint_u64() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_varint_u64(35754238465284).is_ok());
+        assert!(vector.write_varint_u64(u64::MAX).is_ok());
+
+        vector.set_position(0);
+
+        assert_eq!(35754238465284, vector.read_varint_u64().unwrap());
+        assert_eq!(u64::MAX, vector.read_varint_u64().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_varint_i64() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_varint_i64(-5754238465284).is_ok());
+        assert!(vector.write_varint_i64(i64::MIN).is_ok());
+
+        vector.set_position(0);
+
+        assert_eq!(-5754238465284, vector.read_varint_i64().unwrap());
+        assert_eq!(i64::MIN, vector.read_varint_i64().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_zigzag_i32() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_zigzag_i32(-1).is_ok());
+        assert!(vector.write_zigzag_i32(63).is_ok());
+        assert!(vector.write_zigzag_i32(i32::MIN).is_ok());
+        assert!(vector.write_zigzag_i32(i32::MAX).is_ok());
+
+        assert_eq!(vec![
+            0x01,
+            0x7E,
+            0xFF, 0xFF, 0xFF, 0xFF, 0x0F,
+            0xFE, 0xFF, 0xFF, 0xFF, 0x0F
+        ], *vector.get_ref());
+
+        vector.set_position(0);
+
+        assert_eq!(-1, vector.read_zigzag_i32().unwrap());
+        assert_eq!(63, vector.read_zigzag_i32().unwrap());
+        assert_eq!(i32::MIN, vector.read_zigzag_i32().unwrap());
+        assert_eq!(i32::MAX, vector.read_zigzag_i32().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_zigzag_i64() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_zigzag_i64(-2).is_ok());
+        assert!(vector.write_zigzag_i64(i64::MIN).is_ok());
+        assert!(vector.write_zigzag_i64(i64::MAX).is_ok());
+
+        assert_eq!(vec![0x03], vector.get_ref()[..1].to_vec());
+        assert_eq!(21, vector.get_ref().len());
+
+        vector.set_position(0);
+
+        assert_eq!(-2, vector.read_zigzag_i64().unwrap());
+        assert_eq!(i64::MIN, vector.read_zigzag_i64().unwrap());
+        assert_eq!(i64::MAX, vector.read_zigzag_i64().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_string() {
+
+        let prefixes = [
+            LengthPrefix::U8,
+            LengthPrefix::BeU16,
+            LengthPrefix::LeU16,
+            LengthPrefix::BeU32,
+            LengthPrefix::LeU32,
+            LengthPrefix::VarInt
+        ];
+
+        for prefix in prefixes.iter() {
+
+            let mut vector = Cursor::new(vec![0u8; 0]);
+
+            assert!(vector.write_string("Hello, wörld", *prefix).is_ok());
+            assert!(vector.write_string("", *prefix).is_ok());
+
+            assert_eq!(13 + 2 * prefix.encoded_len(0).unwrap(), vector.get_ref().len());
+
+            vector.set_position(0);
+
+            assert_eq!("Hello, wörld", vector.read_string(*prefix, 64).unwrap());
+            assert_eq!("", vector.read_string(*prefix, 64).unwrap());
+
+        }
+
+    }
+
+    #[test]
+    fn test_write_string_too_long_for_prefix() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        let value = "a".repeat(256);
+
+        let error = vector.write_string(&value, LengthPrefix::U8).unwrap_err();
+
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert!(vector.get_ref().is_empty());
+
+    }
+
+    #[test]
+    fn test_read_write_java_utf() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_java_utf("A\0€😀").is_ok());
+        assert!(vector.write_java_utf("").is_ok());
+
+        assert_eq!(vec![
+            0x00, 0x0C, 0x41, 0xC0, 0x80, 0xE2, 0x82, 0xAC, 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80,
+            0x00, 0x00
+        ], *vector.get_ref());
+
+        vector.set_position(0);
+
+        assert_eq!("A\0€😀", vector.read_java_utf().unwrap());
+        assert_eq!("", vector.read_java_utf().unwrap());
+
+    }
+
+    #[test]
+    fn test_write_java_utf_too_long() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        // 21845 three-byte characters fit exactly, one more does not
+        assert!(vector.write_java_utf(&"€".repeat(21845)).is_ok());
+        assert_eq!(65537, vector.get_ref().len());
+
+        let error = vector.write_java_utf(&"€".repeat(21846)).unwrap_err();
+
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert_eq!(65537, vector.get_ref().len());
+
+        // NUL takes two bytes, so this overflows even though the string is shorter
+        assert!(vector.write_java_utf(&"\0".repeat(32768)).is_err());
+
+    }
+
+    #[test]
+    fn test_read_write_utf16() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_utf16("Grüße 😀", LengthPrefix::BeU16, Endian::Big, false).is_ok());
+        assert!(vector.write_utf16("Grüße 😀", LengthPrefix::LeU16, Endian::Little, true).is_ok());
+
+        assert_eq!(vec![0x00, 0x08, 0x00, 0x47], vector.get_ref()[..4].to_vec());
+        assert_eq!(vec![0x09, 0x00, 0xFF, 0xFE, 0x47, 0x00], vector.get_ref()[18..24].to_vec());
+
+        vector.set_position(0);
+
+        assert_eq!(
+            "Grüße 😀",
+            vector.read_utf16(LengthPrefix::BeU16, 64, Endian::Big, false, SurrogatePolicy::Reject).unwrap()
+        );
+        assert_eq!(
+            "Grüße 😀",
+            vector.read_utf16(LengthPrefix::LeU16, 64, Endian::Big, true, SurrogatePolicy::Reject).unwrap()
+        );
+
+    }
+
+    #[test]
+    fn test_read_write_utf16_nul() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_utf16_nul("ABC", Endian::Little, false).is_ok());
+        assert!(vector.write_utf16_nul("", Endian::Big, true).is_ok());
+        assert!(vector.write_utf16_nul("A\0B", Endian::Big, false).is_err());
+
+        assert_eq!(vec![0x41, 0x00, 0x42, 0x00, 0x43, 0x00, 0x00, 0x00, 0xFE, 0xFF, 0x00, 0x00], *vector.get_ref());
+
+        vector.set_position(0);
+
+        assert_eq!("ABC", vector.read_utf16_nul(3, Endian::Little, false, SurrogatePolicy::Reject).unwrap());
+        assert_eq!("", vector.read_utf16_nul(3, Endian::Little, true, SurrogatePolicy::Reject).unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_cstring() {
+
+        let encodings = [TextEncoding::Utf8, TextEncoding::Ascii, TextEncoding::Latin1];
+
+        for encoding in encodings.iter() {
+
+            let mut vector = Cursor::new(vec![0u8; 0]);
+
+            assert!(vector.write_cstring("header", *encoding).is_ok());
+            assert!(vector.write_cstring("", *encoding).is_ok());
+            assert!(vector.write_cstring("a\0b", *encoding).is_err());
+
+            assert_eq!(b"header\0\0".to_vec(), *vector.get_ref());
+
+            vector.set_position(0);
+
+            assert_eq!("header", vector.read_cstring(32, *encoding).unwrap());
+            assert_eq!("", vector.read_cstring(32, *encoding).unwrap());
+
+        }
+
+    }
+
+    #[test]
+    fn test_read_write_fixed_str() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_fixed_str("Zoë", 8, 0, TextEncoding::Latin1).is_ok());
+        assert!(vector.write_fixed_str("Zoë", 8, b' ', TextEncoding::Utf8).is_ok());
+
+        assert_eq!(vec![0x5A, 0x6F, 0xEB, 0x00, 0x00, 0x00, 0x00, 0x00], vector.get_ref()[..8].to_vec());
+        assert_eq!(b"Zo\xC3\xAB    ".to_vec(), vector.get_ref()[8..].to_vec());
+
+        vector.set_position(0);
+
+        assert_eq!("Zoë", vector.read_fixed_str(8, 0, TextEncoding::Latin1).unwrap());
+        assert_eq!("Zoë", vector.read_fixed_str(8, b' ', TextEncoding::Utf8).unwrap());
+
+    }
+
+    #[test]
+    fn test_write_fixed_str_does_not_truncate() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_fixed_str("Zoë", 3, 0, TextEncoding::Utf8).is_err());
+        assert!(vector.write_fixed_str("Zoë", 3, 0, TextEncoding::Ascii).is_err());
+        assert!(vector.get_ref().is_empty());
+
+        assert!(vector.write_fixed_str("Zoë", 3, 0, TextEncoding::Latin1).is_ok());
+
+    }
+
+    #[test]
+    fn test_read_write_generic_endian() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.write_u32::<BigEndian>(0x0A0B0C0D).is_ok());
+        assert!(vector.write_i16::<LittleEndian>(-2).is_ok());
+        assert!(vector.write_u64::<NetworkEndian>(1).is_ok());
+        assert!(vector.write_f64::<NativeEndian>(-0.5).is_ok());
+
+        assert_eq!(vec![0x0A, 0x0B, 0x0C, 0x0D, 0xFE, 0xFF], vector.get_ref()[..6].to_vec());
+
+        vector.set_position(0);
+
+        assert_eq!(0x0A0B0C0D, vector.read_be_u32().unwrap());
+        assert_eq!(-2, vector.read_le_i16().unwrap());
+        assert_eq!(1, vector.read_be_u64().unwrap());
+        assert_eq!(-0.5, vector.read_f64::<NativeEndian>().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_write_runtime_endian() {
+
+        for endian in [Endian::Big, Endian::Little].iter() {
+
+            let mut vector = Cursor::new(vec![0u8; 0]);
+
+            assert!(vector.write_u16_in(0x4D4D, *endian).is_ok());
+            assert!(vector.write_i128_in(-42, *endian).is_ok());
+            assert!(vector.write_f32_in(1.5, *endian).is_ok());
+
+            vector.set_position(0);
+
+            assert_eq!(0x4D4D, vector.read_u16_in(*endian).unwrap());
+            assert_eq!(-42, vector.read_i128_in(*endian).unwrap());
+            assert_eq!(1.5, vector.read_f32_in(*endian).unwrap());
+
+        }
+
+        let mut vector = Cursor::new(vec![0x2A, 0x00]);
+
+        assert_eq!(42, vector.read_u16_in(Endian::Little).unwrap());
+
+    }
+
+    #[test]
+    fn test_generic_errors_name_byte_order() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert_eq!("read_le_u32", vector.read_u32::<LittleEndian>().unwrap_err().operation());
+        assert_eq!("read_be_f64", vector.read_f64_in(Endian::Big).unwrap_err().operation());
+
+    }
+
+    /// Checks that the bulk methods for one type and byte order match the scalar ones
+    macro_rules! bulk_matches_scalar {
+        ($name:ident, $type:ty, $read_into:ident, $read:ident, $write_slice:ident, $write:ident, $bits:expr) => {
+            quickcheck! {
+                fn $name(values: Vec<$type>) -> bool {
+
+                    let mut bulk = Cursor::new(vec![0u8; 0]);
+                    let mut scalar = Cursor::new(vec![0u8; 0]);
+
+                    bulk.$write_slice(&values).unwrap();
+                    for value in values.iter() {
+                        scalar.$write(*value).unwrap();
+                    }
+
+                    if bulk.get_ref() != scalar.get_ref() {
+                        return false;
+                    }
+
+                    bulk.set_position(0);
+                    scalar.set_position(0);
+
+                    let mut read = vec![<$type>::default(); values.len()];
+                    bulk.$read_into(&mut read).unwrap();
+
+                    read.iter().all(|value| $bits(*value) == $bits(scalar.$read().unwrap()))
+
+                }
+            }
+        }
+    }
+
+    bulk_matches_scalar!(prop_bulk_be_u16, u16, read_be_u16_into, read_be_u16, write_be_u16_slice, write_be_u16, |value| value);
+    bulk_matches_scalar!(prop_bulk_le_u16, u16, read_le_u16_into, read_le_u16, write_le_u16_slice, write_le_u16, |value| value);
+    bulk_matches_scalar!(prop_bulk_be_i16, i16, read_be_i16_into, read_be_i16, write_be_i16_slice, write_be_i16, |value| value);
+    bulk_matches_scalar!(prop_bulk_le_i16, i16, read_le_i16_into, read_le_i16, write_le_i16_slice, write_le_i16, |value| value);
+    bulk_matches_scalar!(prop_bulk_be_u32, u32, read_be_u32_into, read_be_u32, write_be_u32_slice, write_be_u32, |value| value);
+    bulk_matches_scalar!(prop_bulk_le_u32, u32, read_le_u32_into, read_le_u32, write_le_u32_slice, write_le_u32, |value| value);
+    bulk_matches_scalar!(prop_bulk_be_i32, i32, read_be_i32_into, read_be_i32, write_be_i32_slice, write_be_i32, |value| value);
+    bulk_matches_scalar!(prop_bulk_le_i32, i32, read_le_i32_into, read_le_i32, write_le_i32_slice, write_le_i32, |value| value);
+    bulk_matches_scalar!(prop_bulk_be_u64, u64, read_be_u64_into, read_be_u64, write_be_u64_slice, write_be_u64, |value| value);
+    bulk_matches_scalar!(prop_bulk_le_u64, u64, read_le_u64_into, read_le_u64, write_le_u64_slice, write_le_u64, |value| value);
+    bulk_matches_scalar!(prop_bulk_be_i64, i64, read_be_i64_into, read_be_i64, write_be_i64_slice, write_be_i64, |value| value);
+    bulk_matches_scalar!(prop_bulk_le_i64, i64, read_le_i64_into, read_le_i64, write_le_i64_slice, write_le_i64, |value| value);
+    bulk_matches_scalar!(prop_bulk_be_u128, u128, read_be_u128_into, read_be_u128, write_be_u128_slice, write_be_u128, |value| value);
+    bulk_matches_scalar!(prop_bulk_le_u128, u128, read_le_u128_into, read_le_u128, write_le_u128_slice, write_le_u128, |value| value);
+    bulk_matches_scalar!(prop_bulk_be_i128, i128, read_be_i128_into, read_be_i128, write_be_i128_slice, write_be_i128, |value| value);
+    bulk_matches_scalar!(prop_bulk_le_i128, i128, read_le_i128_into, read_le_i128, write_le_i128_slice, write_le_i128, |value| value);
+    bulk_matches_scalar!(prop_bulk_be_f32, f32, read_be_f32_into, read_be_f32, write_be_f32_slice, write_be_f32, f32::to_bits);
+    bulk_matches_scalar!(prop_bulk_le_f32, f32, read_le_f32_into, read_le_f32, write_le_f32_slice, write_le_f32, f32::to_bits);
+    bulk_matches_scalar!(prop_bulk_be_f64, f64, read_be_f64_into, read_be_f64, write_be_f64_slice, write_be_f64, f64::to_bits);
+    bulk_matches_scalar!(prop_bulk_le_f64, f64, read_le_f64_into, read_le_f64, write_le_f64_slice, write_le_f64, f64::to_bits);
+
+    #[test]
+    fn test_read_into_truncated() {
+
+        let mut vector = Cursor::new(vec![0u8; 10]);
+
+        let mut values = [0u32; 3];
+
+        let error = vector.read_be_u32_into(&mut values).unwrap_err();
+
+        assert_eq!(io::ErrorKind::UnexpectedEof, error.kind());
+        assert_eq!("read_be_u32_into", error.operation());
+        assert_eq!(10, error.offset());
+
+    }
+
+    #[test]
+    fn test_errors_propagate_as_io_errors() {
+
+        fn read_header(reader: &mut Cursor<Vec<u8>>) -> io::Result<u32> {
+            Ok(reader.read_be_u32()?)
+        }
+
+        let mut vector = Cursor::new(vec![0u8; 2]);
+
+        let error = read_header(&mut vector).unwrap_err();
+
+        assert_eq!(io::ErrorKind::UnexpectedEof, error.kind());
+
+    }
+
+    #[test]
+    fn test_writes_call_write_once_per_part() {
+
+        struct Calls(usize);
+
+        impl io::Write for Calls {
+            fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+                self.0 += 1;
+                Ok(buffer.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writer = Calls(0);
+
+        writer.write_u8(1).unwrap();
+        writer.write_be_u64(2).unwrap();
+        writer.write_varint_u32(300).unwrap();
+        writer.write_cstring("c", TextEncoding::Ascii).unwrap();
+        writer.write_string("", LengthPrefix::U8).unwrap();
+
+        assert_eq!(5, writer.0);
+
+        writer.write_string("ab", LengthPrefix::U8).unwrap();
+
+        assert_eq!(7, writer.0);
+
+    }
+
 }
@@ -0,0 +1,187 @@
+//! Readers that stop at a message boundary
+//!
+//! A LimitedReader lets a decoder read at most a fixed number of bytes, reporting the end
+//! of the stream once they are used up, so that a message of known length cannot be
+//! parsed past its end and into the next one. `finish` then deals with any bytes the
+//! decoder left behind.
+
+use std::io::{self, Read};
+
+use crate::error::{Error, Result};
+use crate::reader::Reader;
+
+/// A Reader that ends after a fixed number of bytes
+///
+/// Usually created with `Reader::take_limited`.
+#[derive(Debug)]
+pub struct LimitedReader<R: Reader> {
+    reader: R,
+    limit: u64,
+    remaining: u64
+}
+
+impl<R: Reader> LimitedReader<R> {
+
+    /// Creates a LimitedReader that reads at most `limit` bytes from the given Reader
+    pub fn new(reader: R, limit: u64) -> LimitedReader<R> {
+        LimitedReader { reader, limit, remaining: limit }
+    }
+
+    /// Returns the number of bytes left before the limit
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Returns a reference to the underlying Reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the underlying Reader
+    ///
+    /// Bytes read through it are not counted against the limit
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the underlying Reader, leaving any unconsumed bytes of the message unread
+    ///
+    /// Use `finish` to skip or reject them instead
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Deals with any unconsumed bytes and returns the underlying Reader
+    ///
+    /// When `strict` is set, unconsumed bytes fail with a `TrailingBytes` error. Otherwise
+    /// they are read and discarded, leaving the underlying Reader at the end of the
+    /// message; a stream that ends before then is reported as truncated. Error offsets
+    /// count from where the LimitedReader started
+    pub fn finish(mut self, strict: bool) -> Result<R> {
+
+        let consumed = self.limit - self.remaining;
+
+        if self.remaining == 0 {
+            return Ok(self.reader);
+        }
+
+        if strict {
+            return Err(Error::TrailingBytes { operation: "finish", offset: consumed, remaining: self.remaining });
+        }
+
+        let skipped = io::copy(&mut (&mut self.reader).take(self.remaining), &mut io::sink())
+            .map_err(|error| Error::Io { operation: "finish", offset: consumed, error })?;
+
+        if skipped < self.remaining {
+            return Err(Error::Truncated { operation: "finish", offset: consumed + skipped, needed: self.remaining - skipped });
+        }
+
+        Ok(self.reader)
+
+    }
+
+}
+
+impl<R: Reader> Read for LimitedReader<R> {
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+
+        let allowed = self.remaining.min(buffer.len() as u64) as usize;
+
+        let read = self.reader.read(&mut buffer[..allowed])?;
+
+        self.remaining -= read as u64;
+
+        Ok(read)
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::prefix::LengthPrefix;
+
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_stops_at_limit() {
+
+        let bytes = [0u8, 1, 2, 3, 4, 5];
+        let mut reader = &bytes[..];
+
+        let mut message = reader.take_limited(3);
+
+        assert_eq!(1, message.read_be_u16().unwrap());
+        assert_eq!(1, message.remaining());
+
+        let error = message.read_be_u16().unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        assert_eq!(1, error.offset());
+
+        message.finish(false).unwrap();
+
+        assert_eq!(&[3, 4, 5], reader);
+
+    }
+
+    #[test]
+    fn test_inner_reader() {
+
+        let bytes = [0u8, 1, 2, 3];
+        let mut message = LimitedReader::new(&bytes[..], 2);
+
+        assert_eq!(0, message.read_u8().unwrap());
+        assert_eq!(&[1, 2, 3], message.get_ref());
+        assert_eq!(1, message.get_mut().read_u8().unwrap());
+        assert_eq!(1, message.remaining());
+        assert_eq!(&[2, 3], message.into_inner());
+
+    }
+
+    #[test]
+    fn test_finish() {
+
+        let bytes = [5u8, b'h', b'e', b'l', b'l', b'o', 9];
+        let mut reader = &bytes[..];
+
+        let mut message = reader.take_limited(6);
+
+        assert_eq!("hello", message.read_string(LengthPrefix::U8, 16).unwrap());
+        assert_eq!(0, message.remaining());
+
+        message.finish(true).unwrap();
+
+        let message = reader.take_limited(1);
+
+        match message.finish(true).unwrap_err() {
+            Error::TrailingBytes { operation, offset, remaining } => {
+                assert_eq!("finish", operation);
+                assert_eq!(0, offset);
+                assert_eq!(1, remaining);
+            },
+            error => panic!("unexpected error {}", error)
+        }
+
+        assert_eq!(&[9], reader);
+
+        let mut message = reader.take_limited(4);
+
+        message.read_u8().unwrap();
+
+        let error = message.finish(false).unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        assert_eq!(1, error.offset());
+
+    }
+
+}
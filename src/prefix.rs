@@ -0,0 +1,245 @@
+//! Length prefixes for variable-length values
+
+use crate::error::{Error, Result};
+use crate::varint;
+
+/// The encoding of the length that precedes a string or other variable-length value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LengthPrefix {
+
+    /// An unsigned byte
+    U8,
+
+    /// An unsigned big-endian short
+    BeU16,
+
+    /// An unsigned little-endian short
+    LeU16,
+
+    /// An unsigned big-endian integer
+    BeU32,
+
+    /// An unsigned little-endian integer
+    LeU32,
+
+    /// An unsigned 32-bit VarInt
+    VarInt
+
+}
+
+impl LengthPrefix {
+
+    /// Returns the largest length this prefix can hold
+    pub fn max_length(self) -> usize {
+        match self {
+            LengthPrefix::U8 => u8::MAX as usize,
+            LengthPrefix::BeU16 | LengthPrefix::LeU16 => u16::MAX as usize,
+            LengthPrefix::BeU32 | LengthPrefix::LeU32 | LengthPrefix::VarInt => u32::MAX as usize
+        }
+    }
+
+    /// Returns the number of bytes this prefix occupies when holding the given length
+    ///
+    /// Returns None for lengths above `max_length`, which the prefix cannot hold
+    pub fn encoded_len(self, length: usize) -> Option<usize> {
+
+        if length > self.max_length() {
+            return None;
+        }
+
+        Some(match self {
+            LengthPrefix::U8 => 1,
+            LengthPrefix::BeU16 | LengthPrefix::LeU16 => 2,
+            LengthPrefix::BeU32 | LengthPrefix::LeU32 => 4,
+            LengthPrefix::VarInt => varint::encoded_len_u32(length as u32)
+        })
+
+    }
+
+    /// Returns the width of a fixed-size prefix, or None for a VarInt
+    pub(crate) fn width(self) -> Option<usize> {
+        match self {
+            LengthPrefix::U8 => Some(1),
+            LengthPrefix::BeU16 | LengthPrefix::LeU16 => Some(2),
+            LengthPrefix::BeU32 | LengthPrefix::LeU32 => Some(4),
+            LengthPrefix::VarInt => None
+        }
+    }
+
+    /// Decodes a fixed-size prefix from exactly `width()` bytes
+    pub(crate) fn decode(self, raw_buffer: &[u8]) -> usize {
+        match self {
+            LengthPrefix::U8 => raw_buffer[0] as usize,
+            LengthPrefix::BeU16 => u16::from_be_bytes([raw_buffer[0], raw_buffer[1]]) as usize,
+            LengthPrefix::LeU16 => u16::from_le_bytes([raw_buffer[0], raw_buffer[1]]) as usize,
+            LengthPrefix::BeU32 => {
+                u32::from_be_bytes([raw_buffer[0], raw_buffer[1], raw_buffer[2], raw_buffer[3]]) as usize
+            },
+            LengthPrefix::LeU32 => {
+                u32::from_le_bytes([raw_buffer[0], raw_buffer[1], raw_buffer[2], raw_buffer[3]]) as usize
+            },
+            LengthPrefix::VarInt => unreachable!("VarInt prefixes have no fixed width")
+        }
+    }
+
+    /// Encodes a length into the start of the buffer, returning the number of bytes used
+    ///
+    /// Lengths the prefix cannot hold are rejected
+    pub(crate) fn encode(
+        self,
+        length: usize,
+        raw_buffer: &mut [u8; varint::MAX_LENGTH_64],
+        operation: &'static str
+    ) -> Result<usize> {
+
+        check_limit(length, self.max_length(), operation)?;
+
+        Ok(match self {
+            LengthPrefix::U8 => {
+                raw_buffer[0] = length as u8;
+                1
+            },
+            LengthPrefix::BeU16 => {
+                raw_buffer[..2].copy_from_slice(&(length as u16).to_be_bytes());
+                2
+            },
+            LengthPrefix::LeU16 => {
+                raw_buffer[..2].copy_from_slice(&(length as u16).to_le_bytes());
+                2
+            },
+            LengthPrefix::BeU32 => {
+                raw_buffer[..4].copy_from_slice(&(length as u32).to_be_bytes());
+                4
+            },
+            LengthPrefix::LeU32 => {
+                raw_buffer[..4].copy_from_slice(&(length as u32).to_le_bytes());
+                4
+            },
+            LengthPrefix::VarInt => varint::encode(length as u64, raw_buffer)
+        })
+
+    }
+
+}
+
+/// A length prefix known at compile time
+///
+/// This trait is sealed; the implementations are the marker types below, one for each
+/// LengthPrefix variant
+pub trait PrefixKind : private::Sealed {
+
+    /// The runtime equivalent of this prefix
+    const PREFIX: LengthPrefix;
+
+}
+
+/// An unsigned byte length prefix
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum U8Length { }
+
+/// An unsigned big-endian short length prefix
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BeU16Length { }
+
+/// An unsigned little-endian short length prefix
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LeU16Length { }
+
+/// An unsigned big-endian integer length prefix
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BeU32Length { }
+
+/// An unsigned little-endian integer length prefix
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LeU32Length { }
+
+/// An unsigned 32-bit VarInt length prefix
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VarIntLength { }
+
+impl PrefixKind for U8Length {
+    const PREFIX: LengthPrefix = LengthPrefix::U8;
+}
+
+impl PrefixKind for BeU16Length {
+    const PREFIX: LengthPrefix = LengthPrefix::BeU16;
+}
+
+impl PrefixKind for LeU16Length {
+    const PREFIX: LengthPrefix = LengthPrefix::LeU16;
+}
+
+impl PrefixKind for BeU32Length {
+    const PREFIX: LengthPrefix = LengthPrefix::BeU32;
+}
+
+impl PrefixKind for LeU32Length {
+    const PREFIX: LengthPrefix = LengthPrefix::LeU32;
+}
+
+impl PrefixKind for VarIntLength {
+    const PREFIX: LengthPrefix = LengthPrefix::VarInt;
+}
+
+mod private {
+
+    /// Prevents PrefixKind from being implemented outside this crate
+    pub trait Sealed { }
+
+    impl Sealed for super::U8Length { }
+
+    impl Sealed for super::BeU16Length { }
+
+    impl Sealed for super::LeU16Length { }
+
+    impl Sealed for super::BeU32Length { }
+
+    impl Sealed for super::LeU32Length { }
+
+    impl Sealed for super::VarIntLength { }
+
+}
+
+/// Rejects lengths above the given limit
+pub(crate) fn check_limit(length: usize, limit: usize, operation: &'static str) -> Result<()> {
+
+    if length > limit {
+        return Err(Error::LengthLimitExceeded {
+            operation,
+            offset: 0,
+            length: length as u64,
+            limit: limit as u64
+        });
+    }
+
+    Ok(())
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::LengthPrefix;
+
+    #[test]
+    fn test_max_length() {
+
+        assert_eq!(255, LengthPrefix::U8.max_length());
+        assert_eq!(65535, LengthPrefix::LeU16.max_length());
+        assert_eq!(4294967295, LengthPrefix::VarInt.max_length());
+
+    }
+
+    #[test]
+    fn test_encoded_len() {
+
+        assert_eq!(Some(1), LengthPrefix::U8.encoded_len(200));
+        assert_eq!(Some(4), LengthPrefix::BeU32.encoded_len(200));
+        assert_eq!(Some(2), LengthPrefix::VarInt.encoded_len(200));
+        assert_eq!(Some(5), LengthPrefix::VarInt.encoded_len(u32::MAX as usize));
+        assert_eq!(None, LengthPrefix::U8.encoded_len(256));
+        assert_eq!(None, LengthPrefix::VarInt.encoded_len(u32::MAX as usize + 1));
+
+    }
+
+}
@@ -1,395 +1,620 @@
 //! Reading I/O operations
 
-use std::io::{Read, Error, ErrorKind};
+use std::io::Read;
+
+use crate::codec::{self, Decode, Fixed, Length, Primitives, VarInt};
+use crate::endian::{BigEndian, ByteOrder, Endian, LittleEndian};
+use crate::error::Result;
+use crate::limited::LimitedReader;
+use crate::prefix::LengthPrefix;
+use crate::text::{SurrogatePolicy, TextEncoding};
+use crate::value::Readable;
+use crate::varint;
 
 /// Extends the Read trait to provide common I/O reader operations
+///
+/// Multi-byte numbers can be read in a byte order picked at compile time, as in
+/// `read_u32::<BigEndian>()`, in one picked at runtime, as in `read_u32_in(endian)`,
+/// or with the `read_be_*` and `read_le_*` shorthands. The `read_*_into` methods fill a
+/// whole slice with a single read and convert the values in place
 pub trait Reader : Read {
 
     /// Reads an unsigned byte from this Reader
-    fn read_u8(&mut self) -> Result<u8, Error> {
+    fn read_u8(&mut self) -> Result<u8> {
+        decode(self, Fixed::new(Endian::NATIVE, "read_u8"))
+    }
 
-        let mut raw_buffer = vec![0u8; 1];
+    /// Reads a signed byte from this Reader
+    fn read_i8(&mut self) -> Result<i8> {
+        decode(self, Fixed::new(Endian::NATIVE, "read_i8"))
+    }
 
-        match self.read(&mut raw_buffer) {
+    /// Reads an unsigned short in the given byte order from this Reader
+    fn read_u16_in(&mut self, endian: Endian) -> Result<u16> {
+        decode(self, Fixed::new(endian, endian.select("read_be_u16", "read_le_u16")))
+    }
 
-            Ok(count) => {
-                if count == 1 {
-                    return Ok(raw_buffer[0]);
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read one byte (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Reads an unsigned short in the byte order `E` from this Reader
+    fn read_u16<E: ByteOrder>(&mut self) -> Result<u16> {
+        self.read_u16_in(E::ENDIAN)
+    }
 
-        }
+    /// Reads an unsigned big-endian short from this Reader
+    fn read_be_u16(&mut self) -> Result<u16> {
+        self.read_u16::<BigEndian>()
+    }
 
+    /// Reads an unsigned little-endian short from this Reader
+    fn read_le_u16(&mut self) -> Result<u16> {
+        self.read_u16::<LittleEndian>()
     }
 
-    /// Reads a signed byte from this Reader
-    fn read_i8(&mut self) -> Result<i8, Error> {
+    /// Reads a signed short in the given byte order from this Reader
+    fn read_i16_in(&mut self, endian: Endian) -> Result<i16> {
+        decode(self, Fixed::new(endian, endian.select("read_be_i16", "read_le_i16")))
+    }
 
-        let mut raw_buffer = vec![0u8; 1];
+    /// Reads a signed short in the byte order `E` from this Reader
+    fn read_i16<E: ByteOrder>(&mut self) -> Result<i16> {
+        self.read_i16_in(E::ENDIAN)
+    }
 
-        match self.read(&mut raw_buffer) {
+    /// Reads a signed big-endian short from this Reader
+    fn read_be_i16(&mut self) -> Result<i16> {
+        self.read_i16::<BigEndian>()
+    }
 
-            Ok(count) => {
-                if count == 1 {
-                    return Ok(raw_buffer[0] as i8);
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read one byte (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Reads a signed little-endian short from this Reader
+    fn read_le_i16(&mut self) -> Result<i16> {
+        self.read_i16::<LittleEndian>()
+    }
 
-        }
+    /// Reads an unsigned integer in the given byte order from this Reader
+    fn read_u32_in(&mut self, endian: Endian) -> Result<u32> {
+        decode(self, Fixed::new(endian, endian.select("read_be_u32", "read_le_u32")))
+    }
 
+    /// Reads an unsigned integer in the byte order `E` from this Reader
+    fn read_u32<E: ByteOrder>(&mut self) -> Result<u32> {
+        self.read_u32_in(E::ENDIAN)
     }
 
-    /// Reads an unsigned big-endian short from this Reader
-    fn read_be_u16(&mut self) -> Result<u16, Error> {
-
-        let mut raw_buffer = vec![0u8; 2];
-
-        match self.read(&mut raw_buffer) {
-
-            Ok(count) => {
-                if count == 2 {
-                    return Ok(
-                        ((raw_buffer[0] as u16) << 8) |
-                        raw_buffer[1] as u16
-                    );
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read two bytes (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Reads an unsigned big-endian integer from this Reader
+    fn read_be_u32(&mut self) -> Result<u32> {
+        self.read_u32::<BigEndian>()
+    }
 
-        }
+    /// Reads an unsigned little-endian integer from this Reader
+    fn read_le_u32(&mut self) -> Result<u32> {
+        self.read_u32::<LittleEndian>()
+    }
 
+    /// Reads a signed integer in the given byte order from this Reader
+    fn read_i32_in(&mut self, endian: Endian) -> Result<i32> {
+        decode(self, Fixed::new(endian, endian.select("read_be_i32", "read_le_i32")))
     }
 
-    /// Reads an unsigned little-endian short from this Reader
-    fn read_le_u16(&mut self) -> Result<u16, Error> {
-
-        let mut raw_buffer = vec![0u8; 2];
-
-        match self.read(&mut raw_buffer) {
-
-            Ok(count) => {
-                if count == 2 {
-                    return Ok(
-                        raw_buffer[0] as u16 |
-                        ((raw_buffer[1] as u16) << 8)
-                    );
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read two bytes (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Reads a signed integer in the byte order `E` from this Reader
+    fn read_i32<E: ByteOrder>(&mut self) -> Result<i32> {
+        self.read_i32_in(E::ENDIAN)
+    }
 
-        }
+    /// Reads a signed big-endian integer from this Reader
+    fn read_be_i32(&mut self) -> Result<i32> {
+        self.read_i32::<BigEndian>()
+    }
 
+    /// Reads a signed little-endian integer from this Reader
+    fn read_le_i32(&mut self) -> Result<i32> {
+        self.read_i32::<LittleEndian>()
     }
 
-    /// Reads a signed big-endian short from this Reader
-    fn read_be_i16(&mut self) -> Result<i16, Error> {
-
-        let mut raw_buffer = vec![0u8; 2];
-
-        match self.read(&mut raw_buffer) {
-
-            Ok(count) => {
-                if count == 2 {
-                    return Ok(
-                        ((raw_buffer[0] as i16) << 8) |
-                        raw_buffer[1] as i16
-                    );
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read two bytes (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Reads an unsigned long in the given byte order from this Reader
+    fn read_u64_in(&mut self, endian: Endian) -> Result<u64> {
+        decode(self, Fixed::new(endian, endian.select("read_be_u64", "read_le_u64")))
+    }
 
-        }
+    /// Reads an unsigned long in the byte order `E` from this Reader
+    fn read_u64<E: ByteOrder>(&mut self) -> Result<u64> {
+        self.read_u64_in(E::ENDIAN)
+    }
 
+    /// Reads an unsigned big-endian long from this Reader
+    fn read_be_u64(&mut self) -> Result<u64> {
+        self.read_u64::<BigEndian>()
     }
 
-    /// Reads a signed little-endian short from this Reader
-    fn read_le_i16(&mut self) -> Result<i16, Error> {
-
-        let mut raw_buffer = vec![0u8; 2];
-
-        match self.read(&mut raw_buffer) {
-
-            Ok(count) => {
-                if count == 2 {
-                    return Ok(
-                        raw_buffer[0] as i16 |
-                        ((raw_buffer[1] as i16) << 8)
-                    );
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read two bytes (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Reads an unsigned little-endian long from this Reader
+    fn read_le_u64(&mut self) -> Result<u64> {
+        self.read_u64::<LittleEndian>()
+    }
 
-        }
+    /// Reads a signed long in the given byte order from this Reader
+    fn read_i64_in(&mut self, endian: Endian) -> Result<i64> {
+        decode(self, Fixed::new(endian, endian.select("read_be_i64", "read_le_i64")))
+    }
 
+    /// Reads a signed long in the byte order `E` from this Reader
+    fn read_i64<E: ByteOrder>(&mut self) -> Result<i64> {
+        self.read_i64_in(E::ENDIAN)
     }
 
-    /// Reads an unsigned big-endian integer from this Reader
-    fn read_be_u32(&mut self) -> Result<u32, Error> {
-
-        let mut raw_buffer = vec![0u8; 4];
-
-        match self.read(&mut raw_buffer) {
-
-            Ok(count) => {
-                if count == 4 {
-                    return Ok(
-                        ((raw_buffer[0] as u32) << 24) |
-                        ((raw_buffer[1] as u32) << 16) |
-                        ((raw_buffer[2] as u32) << 8) |
-                        raw_buffer[3] as u32
-                    );
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read four bytes (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Reads a signed big-endian long from this Reader
+    fn read_be_i64(&mut self) -> Result<i64> {
+        self.read_i64::<BigEndian>()
+    }
 
-        }
+    /// Reads a signed little-endian long from this Reader
+    fn read_le_i64(&mut self) -> Result<i64> {
+        self.read_i64::<LittleEndian>()
+    }
 
+    /// Reads an unsigned 128-bit integer in the given byte order from this Reader
+    fn read_u128_in(&mut self, endian: Endian) -> Result<u128> {
+        decode(self, Fixed::new(endian, endian.select("read_be_u128", "read_le_u128")))
     }
 
-    /// Reads an unsigned little-endian integer from this Reader
-    fn read_le_u32(&mut self) -> Result<u32, Error> {
-
-        let mut raw_buffer = vec![0u8; 4];
-
-        match self.read(&mut raw_buffer) {
-
-            Ok(count) => {
-                if count == 4 {
-                    return Ok(
-                        raw_buffer[0] as u32 |
-                        ((raw_buffer[1] as u32) << 8) |
-                        ((raw_buffer[2] as u32) << 16) |
-                        ((raw_buffer[3] as u32) << 24)
-                    );
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read four bytes (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Reads an unsigned 128-bit integer in the byte order `E` from this Reader
+    fn read_u128<E: ByteOrder>(&mut self) -> Result<u128> {
+        self.read_u128_in(E::ENDIAN)
+    }
 
-        }
+    /// Reads an unsigned big-endian 128-bit integer from this Reader
+    fn read_be_u128(&mut self) -> Result<u128> {
+        self.read_u128::<BigEndian>()
+    }
 
+    /// Reads an unsigned little-endian 128-bit integer from this Reader
+    fn read_le_u128(&mut self) -> Result<u128> {
+        self.read_u128::<LittleEndian>()
     }
 
-    /// Reads a signed big-endian integer from this Reader
-    fn read_be_i32(&mut self) -> Result<i32, Error> {
-
-        let mut raw_buffer = vec![0u8; 4];
-
-        match self.read(&mut raw_buffer) {
-
-            Ok(count) => {
-                if count == 4 {
-                    return Ok(
-                        ((raw_buffer[0] as i32) << 24) |
-                        ((raw_buffer[1] as i32) << 16) |
-                        ((raw_buffer[2] as i32) << 8) |
-                        raw_buffer[3] as i32
-                    );
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read four bytes (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Reads a signed 128-bit integer in the given byte order from this Reader
+    fn read_i128_in(&mut self, endian: Endian) -> Result<i128> {
+        decode(self, Fixed::new(endian, endian.select("read_be_i128", "read_le_i128")))
+    }
 
-        }
+    /// Reads a signed 128-bit integer in the byte order `E` from this Reader
+    fn read_i128<E: ByteOrder>(&mut self) -> Result<i128> {
+        self.read_i128_in(E::ENDIAN)
+    }
 
+    /// Reads a signed big-endian 128-bit integer from this Reader
+    fn read_be_i128(&mut self) -> Result<i128> {
+        self.read_i128::<BigEndian>()
     }
 
-    /// Reads a signed little-endian integer from this Reader
-    fn read_le_i32(&mut self) -> Result<i32, Error> {
-
-        let mut raw_buffer = vec![0u8; 4];
-
-        match self.read(&mut raw_buffer) {
-
-            Ok(count) => {
-                if count == 4 {
-                    return Ok(
-                        raw_buffer[0] as i32 |
-                        ((raw_buffer[1] as i32) << 8) |
-                        ((raw_buffer[2] as i32) << 16) |
-                        ((raw_buffer[3] as i32) << 24)
-                    );
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read four bytes (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Reads a signed little-endian 128-bit integer from this Reader
+    fn read_le_i128(&mut self) -> Result<i128> {
+        self.read_i128::<LittleEndian>()
+    }
 
-        }
+    /// Reads a single-precision float in the given byte order from this Reader
+    fn read_f32_in(&mut self, endian: Endian) -> Result<f32> {
+        decode(self, Fixed::new(endian, endian.select("read_be_f32", "read_le_f32")))
+    }
 
+    /// Reads a single-precision float in the byte order `E` from this Reader
+    fn read_f32<E: ByteOrder>(&mut self) -> Result<f32> {
+        self.read_f32_in(E::ENDIAN)
     }
 
-    /// Reads an unsigned big-endian long from this Reader
-    fn read_be_u64(&mut self) -> Result<u64, Error> {
-
-        let mut raw_buffer = vec![0u8; 8];
-
-        match self.read(&mut raw_buffer) {
-
-            Ok(count) => {
-                if count == 8 {
-                    return Ok(
-                        ((raw_buffer[0] as u64) << 56) |
-                        ((raw_buffer[1] as u64) << 48) |
-                        ((raw_buffer[2] as u64) << 40) |
-                        ((raw_buffer[3] as u64) << 32) |
-                        ((raw_buffer[4] as u64) << 24) |
-                        ((raw_buffer[5] as u64) << 16) |
-                        ((raw_buffer[6] as u64) << 8) |
-                        raw_buffer[7] as u64
-                    );
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read eight bytes (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Reads a big-endian single-precision float from this Reader
+    fn read_be_f32(&mut self) -> Result<f32> {
+        self.read_f32::<BigEndian>()
+    }
 
-        }
+    /// Reads a little-endian single-precision float from this Reader
+    fn read_le_f32(&mut self) -> Result<f32> {
+        self.read_f32::<LittleEndian>()
+    }
 
+    /// Reads a double-precision float in the given byte order from this Reader
+    fn read_f64_in(&mut self, endian: Endian) -> Result<f64> {
+        decode(self, Fixed::new(endian, endian.select("read_be_f64", "read_le_f64")))
     }
 
-    /// Reads a signed big-endian long from this Reader
-    fn read_be_i64(&mut self) -> Result<i64, Error> {
-
-        let mut raw_buffer = vec![0u8; 8];
-
-        match self.read(&mut raw_buffer) {
-
-            Ok(count) => {
-                if count == 8 {
-                    return Ok(
-                        ((raw_buffer[0] as i64) << 56) |
-                        ((raw_buffer[1] as i64) << 48) |
-                        ((raw_buffer[2] as i64) << 40) |
-                        ((raw_buffer[3] as i64) << 32) |
-                        ((raw_buffer[4] as i64) << 24) |
-                        ((raw_buffer[5] as i64) << 16) |
-                        ((raw_buffer[6] as i64) << 8) |
-                        raw_buffer[7] as i64
-                    );
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read eight bytes (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Reads a double-precision float in the byte order `E` from this Reader
+    fn read_f64<E: ByteOrder>(&mut self) -> Result<f64> {
+        self.read_f64_in(E::ENDIAN)
+    }
 
-        }
+    /// Reads a big-endian double-precision float from this Reader
+    fn read_be_f64(&mut self) -> Result<f64> {
+        self.read_f64::<BigEndian>()
+    }
 
+    /// Reads a little-endian double-precision float from this Reader
+    fn read_le_f64(&mut self) -> Result<f64> {
+        self.read_f64::<LittleEndian>()
     }
 
-    /// Reads an unsigned little-endian long from this Reader
-    fn read_le_u64(&mut self) -> Result<u64, Error> {
-
-        let mut raw_buffer = vec![0u8; 8];
-
-        match self.read(&mut raw_buffer) {
-
-            Ok(count) => {
-                if count == 8 {
-                    return Ok(
-                        raw_buffer[0] as u64 |
-                        ((raw_buffer[1] as u64) << 8) |
-                        ((raw_buffer[2] as u64) << 16) |
-                        ((raw_buffer[3] as u64) << 24) |
-                        ((raw_buffer[4] as u64) << 32) |
-                        ((raw_buffer[5] as u64) << 40) |
-                        ((raw_buffer[6] as u64) << 48) |
-                        ((raw_buffer[7] as u64) << 56)
-                    );
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read eight bytes (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Fills `values` with unsigned shorts in the byte order `E` from this Reader
+    fn read_u16_into<E: ByteOrder>(&mut self, values: &mut [u16]) -> Result<()> {
+        decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_u16_into", "read_le_u16_into")))
+    }
 
-        }
+    /// Fills `values` with big-endian unsigned shorts from this Reader
+    fn read_be_u16_into(&mut self, values: &mut [u16]) -> Result<()> {
+        self.read_u16_into::<BigEndian>(values)
+    }
 
+    /// Fills `values` with little-endian unsigned shorts from this Reader
+    fn read_le_u16_into(&mut self, values: &mut [u16]) -> Result<()> {
+        self.read_u16_into::<LittleEndian>(values)
     }
 
-    /// Reads a signed little-endian long from this Reader
-    fn read_le_i64(&mut self) -> Result<i64, Error> {
-
-        let mut raw_buffer = vec![0u8; 8];
-
-        match self.read(&mut raw_buffer) {
-
-            Ok(count) => {
-                if count == 8 {
-                    return Ok(
-                        raw_buffer[0] as i64 |
-                        ((raw_buffer[1] as i64) << 8) |
-                        ((raw_buffer[2] as i64) << 16) |
-                        ((raw_buffer[3] as i64) << 24) |
-                        ((raw_buffer[4] as i64) << 32) |
-                        ((raw_buffer[5] as i64) << 40) |
-                        ((raw_buffer[6] as i64) << 48) |
-                        ((raw_buffer[7] as i64) << 56)
-                    );
-                } else {
-                    return Err(Error::new(ErrorKind::Other, "Could not read eight bytes (end of stream?)"));
-                }
-            },
-            Err(error) => {
-                return Err(error);
-            }
+    /// Fills `values` with signed shorts in the byte order `E` from this Reader
+    fn read_i16_into<E: ByteOrder>(&mut self, values: &mut [i16]) -> Result<()> {
+        decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_i16_into", "read_le_i16_into")))
+    }
 
-        }
+    /// Fills `values` with big-endian signed shorts from this Reader
+    fn read_be_i16_into(&mut self, values: &mut [i16]) -> Result<()> {
+        self.read_i16_into::<BigEndian>(values)
+    }
+
+    /// Fills `values` with little-endian signed shorts from this Reader
+    fn read_le_i16_into(&mut self, values: &mut [i16]) -> Result<()> {
+        self.read_i16_into::<LittleEndian>(values)
+    }
+
+    /// Fills `values` with unsigned integers in the byte order `E` from this Reader
+    fn read_u32_into<E: ByteOrder>(&mut self, values: &mut [u32]) -> Result<()> {
+        decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_u32_into", "read_le_u32_into")))
+    }
+
+    /// Fills `values` with big-endian unsigned integers from this Reader
+    fn read_be_u32_into(&mut self, values: &mut [u32]) -> Result<()> {
+        self.read_u32_into::<BigEndian>(values)
+    }
 
+    /// Fills `values` with little-endian unsigned integers from this Reader
+    fn read_le_u32_into(&mut self, values: &mut [u32]) -> Result<()> {
+        self.read_u32_into::<LittleEndian>(values)
     }
+
+    /// Fills `values` with signed integers in the byte order `E` from this Reader
+    fn read_i32_into<E: ByteOrder>(&mut self, values: &mut [i32]) -> Result<()> {
+        decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_i32_into", "read_le_i32_into")))
+    }
+
+    /// Fills `values` with big-endian signed integers from this Reader
+    fn read_be_i32_into(&mut self, values: &mut [i32]) -> Result<()> {
+        self.read_i32_into::<BigEndian>(values)
+    }
+
+    /// Fills `values` with little-endian signed integers from this Reader
+    fn read_le_i32_into(&mut self, values: &mut [i32]) -> Result<()> {
+        self.read_i32_into::<LittleEndian>(values)
+    }
+
+    /// Fills `values` with unsigned longs in the byte order `E` from this Reader
+    fn read_u64_into<E: ByteOrder>(&mut self, values: &mut [u64]) -> Result<()> {
+        decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_u64_into", "read_le_u64_into")))
+    }
+
+    /// Fills `values` with big-endian unsigned longs from this Reader
+    fn read_be_u64_into(&mut self, values: &mut [u64]) -> Result<()> {
+        self.read_u64_into::<BigEndian>(values)
+    }
+
+    /// Fills `values` with little-endian unsigned longs from this Reader
+    fn read_le_u64_into(&mut self, values: &mut [u64]) -> Result<()> {
+        self.read_u64_into::<LittleEndian>(values)
+    }
+
+    /// Fills `values` with signed longs in the byte order `E` from this Reader
+    fn read_i64_into<E: ByteOrder>(&mut self, values: &mut [i64]) -> Result<()> {
+        decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_i64_into", "read_le_i64_into")))
+    }
+
+    /// Fills `values` with big-endian signed longs from this Reader
+    fn read_be_i64_into(&mut self, values: &mut [i64]) -> Result<()> {
+        self.read_i64_into::<BigEndian>(values)
+    }
+
+    /// Fills `values` with little-endian signed longs from this Reader
+    fn read_le_i64_into(&mut self, values: &mut [i64]) -> Result<()> {
+        self.read_i64_into::<LittleEndian>(values)
+    }
+
+    /// Fills `values` with unsigned 128-bit integers in the byte order `E` from this Reader
+    fn read_u128_into<E: ByteOrder>(&mut self, values: &mut [u128]) -> Result<()> {
+        decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_u128_into", "read_le_u128_into")))
+    }
+
+    /// Fills `values` with big-endian unsigned 128-bit integers from this Reader
+    fn read_be_u128_into(&mut self, values: &mut [u128]) -> Result<()> {
+        self.read_u128_into::<BigEndian>(values)
+    }
+
+    /// Fills `values` with little-endian unsigned 128-bit integers from this Reader
+    fn read_le_u128_into(&mut self, values: &mut [u128]) -> Result<()> {
+        self.read_u128_into::<LittleEndian>(values)
+    }
+
+    /// Fills `values` with signed 128-bit integers in the byte order `E` from this Reader
+    fn read_i128_into<E: ByteOrder>(&mut self, values: &mut [i128]) -> Result<()> {
+        decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_i128_into", "read_le_i128_into")))
+    }
+
+    /// Fills `values` with big-endian signed 128-bit integers from this Reader
+    fn read_be_i128_into(&mut self, values: &mut [i128]) -> Result<()> {
+        self.read_i128_into::<BigEndian>(values)
+    }
+
+    /// Fills `values` with little-endian signed 128-bit integers from this Reader
+    fn read_le_i128_into(&mut self, values: &mut [i128]) -> Result<()> {
+        self.read_i128_into::<LittleEndian>(values)
+    }
+
+    /// Fills `values` with single-precision floats in the byte order `E` from this Reader
+    fn read_f32_into<E: ByteOrder>(&mut self, values: &mut [f32]) -> Result<()> {
+        decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_f32_into", "read_le_f32_into")))
+    }
+
+    /// Fills `values` with big-endian single-precision floats from this Reader
+    fn read_be_f32_into(&mut self, values: &mut [f32]) -> Result<()> {
+        self.read_f32_into::<BigEndian>(values)
+    }
+
+    /// Fills `values` with little-endian single-precision floats from this Reader
+    fn read_le_f32_into(&mut self, values: &mut [f32]) -> Result<()> {
+        self.read_f32_into::<LittleEndian>(values)
+    }
+
+    /// Fills `values` with double-precision floats in the byte order `E` from this Reader
+    fn read_f64_into<E: ByteOrder>(&mut self, values: &mut [f64]) -> Result<()> {
+        decode(self, Primitives::new(values, E::ENDIAN, E::ENDIAN.select("read_be_f64_into", "read_le_f64_into")))
+    }
+
+    /// Fills `values` with big-endian double-precision floats from this Reader
+    fn read_be_f64_into(&mut self, values: &mut [f64]) -> Result<()> {
+        self.read_f64_into::<BigEndian>(values)
+    }
+
+    /// Fills `values` with little-endian double-precision floats from this Reader
+    fn read_le_f64_into(&mut self, values: &mut [f64]) -> Result<()> {
+        self.read_f64_into::<LittleEndian>(values)
+    }
+
+    /// Reads an unsigned 32-bit VarInt from this Reader
+    fn read_varint_u32(&mut self) -> Result<u32> {
+        decode(self, VarInt::new(32, "read_varint_u32")).map(|value| value as u32)
+    }
+
+    /// Reads a signed 32-bit VarInt, stored in two's complement, from this Reader
+    fn read_varint_i32(&mut self) -> Result<i32> {
+        decode(self, VarInt::new(32, "read_varint_i32")).map(|value| value as u32 as i32)
+    }
+
+    /// Reads an unsigned 64-bit VarLong from this Reader
+    fn read_varint_u64(&mut self) -> Result<u64> {
+        decode(self, VarInt::new(64, "read_varint_u64"))
+    }
+
+    /// Reads a signed 64-bit VarLong, stored in two's complement, from this Reader
+    fn read_varint_i64(&mut self) -> Result<i64> {
+        decode(self, VarInt::new(64, "read_varint_i64")).map(|value| value as i64)
+    }
+
+    /// Reads a signed 32-bit ZigZag-encoded VarInt from this Reader
+    fn read_zigzag_i32(&mut self) -> Result<i32> {
+        decode(self, VarInt::new(32, "read_zigzag_i32")).map(|value| varint::decode_zigzag_i32(value as u32))
+    }
+
+    /// Reads a signed 64-bit ZigZag-encoded VarLong from this Reader
+    fn read_zigzag_i64(&mut self) -> Result<i64> {
+        decode(self, VarInt::new(64, "read_zigzag_i64")).map(varint::decode_zigzag_i64)
+    }
+
+    /// Reads a length prefix of the given type from this Reader
+    fn read_length_prefix(&mut self, prefix: LengthPrefix) -> Result<usize> {
+        decode(self, Length::new(prefix, "read_length_prefix")).map(|(length, _)| length)
+    }
+
+    /// Reads a length-prefixed UTF-8 string from this Reader
+    ///
+    /// Lengths above `max_length` bytes are rejected before anything is allocated, so a
+    /// hostile peer cannot make us reserve more memory than the caller allows
+    fn read_string(&mut self, prefix: LengthPrefix, max_length: usize) -> Result<String> {
+        decode(self, codec::read_string(prefix, max_length))
+    }
+
+    /// Reads a string written by Java's `DataOutput.writeUTF` from this Reader
+    ///
+    /// That is a big-endian u16 byte length followed by modified UTF-8
+    fn read_java_utf(&mut self) -> Result<String> {
+        decode(self, codec::read_java_utf())
+    }
+
+    /// Reads a UTF-16 string prefixed with its length in code units from this Reader
+    ///
+    /// When `detect_bom` is set, a leading byte order mark overrides `endian`; it still
+    /// counts towards the length. Lengths above `max_units` are rejected before anything
+    /// is allocated
+    fn read_utf16(
+        &mut self,
+        prefix: LengthPrefix,
+        max_units: usize,
+        endian: Endian,
+        detect_bom: bool,
+        policy: SurrogatePolicy
+    ) -> Result<String> {
+        decode(self, codec::read_utf16(prefix, max_units, endian, detect_bom, policy))
+    }
+
+    /// Reads a UTF-16 string terminated by a NUL code unit from this Reader
+    ///
+    /// When `detect_bom` is set, a leading byte order mark overrides `endian`. Strings
+    /// with more than `max_units` code units before the terminator are rejected
+    fn read_utf16_nul(
+        &mut self,
+        max_units: usize,
+        endian: Endian,
+        detect_bom: bool,
+        policy: SurrogatePolicy
+    ) -> Result<String> {
+        decode(self, codec::read_utf16_nul(max_units, endian, detect_bom, policy))
+    }
+
+    /// Reads a NUL-terminated string from this Reader
+    ///
+    /// The string is read one byte at a time so that nothing past the terminator is
+    /// consumed; wrap unbuffered streams in a `BufReader`. Strings with more than
+    /// `max_length` bytes before the terminator are rejected
+    fn read_cstring(&mut self, max_length: usize, encoding: TextEncoding) -> Result<String> {
+        decode(self, codec::read_cstring(max_length, encoding))
+    }
+
+    /// Reads a string stored in a fixed-width field from this Reader
+    ///
+    /// Exactly `width` bytes are consumed and any trailing `pad_byte`s are removed
+    fn read_fixed_str(&mut self, width: usize, pad_byte: u8, encoding: TextEncoding) -> Result<String> {
+        decode(self, codec::read_fixed_str(width, pad_byte, encoding))
+    }
+
+    /// Reads a value of any Readable type from this Reader
+    ///
+    /// Named `read_value` rather than `read` so that it cannot be confused with `Read::read`
+    fn read_value<T: Readable>(&mut self) -> Result<T> {
+        T::read_from(self)
+    }
+
+    /// Borrows this Reader as one that ends after `limit` more bytes
+    ///
+    /// Use it to parse a message of known length without reading into the next one
+    fn take_limited(&mut self, limit: u64) -> LimitedReader<&mut Self> {
+        LimitedReader::new(self, limit)
+    }
+
 }
 
 impl<T> Reader for T where T: Read { }
 
+/// Fills the whole buffer from the given reader
+///
+/// Short reads are retried until the buffer is full, as are reads that fail with
+/// `ErrorKind::Interrupted`. If the stream ends first, a `Truncated` error is returned
+/// whose offset is the number of bytes consumed before the end
+pub(crate) fn fill_buffer<R: Read + ?Sized>(reader: &mut R, buffer: &mut [u8], operation: &'static str) -> Result<()> {
+
+    let mut count = 0;
+
+    while count < buffer.len() {
+        let result = reader.read(&mut buffer[count..]);
+        codec::record_read(result, &mut count, buffer.len(), operation)?;
+    }
+
+    Ok(())
+
+}
+
+/// Runs a decoder to completion, filling each buffer it asks for from the given reader
+fn decode<R: Read + ?Sized, D: Decode>(reader: &mut R, mut decoder: D) -> Result<D::Output> {
+
+    loop {
+
+        let consumed = decoder.consumed() as u64;
+        let operation = decoder.operation();
+
+        fill_buffer(reader, decoder.buffer(), operation)
+            .map_err(|error| error.with_base_offset(consumed))?;
+
+        if let Some(value) = decoder.advance()? {
+            return Ok(value);
+        }
+
+    }
+
+}
+
+/// Reads a length prefix of the given type
+pub(crate) fn read_length<R: Read + ?Sized>(reader: &mut R, prefix: LengthPrefix, operation: &'static str) -> Result<usize> {
+    read_length_counted(reader, prefix, operation).map(|(length, _)| length)
+}
+
+/// Reads a length prefix of the given type, also returning the number of bytes it took
+pub(crate) fn read_length_counted<R: Read + ?Sized>(reader: &mut R, prefix: LengthPrefix, operation: &'static str) -> Result<(usize, usize)> {
+    decode(reader, Length::new(prefix, operation))
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::Reader;
 
-    use std::io::Cursor;
+    use crate::endian::Endian;
+    use crate::prefix::LengthPrefix;
+    use crate::text::{SurrogatePolicy, TextEncoding};
+
+    use std::io::{Cursor, Error, ErrorKind, Read};
+
+    /// A reader that hands out at most one byte per call
+    struct OneByteReader {
+        inner: Cursor<Vec<u8>>
+    }
+
+    impl Read for OneByteReader {
+        fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+            let length = buffer.len().min(1);
+            self.inner.read(&mut buffer[..length])
+        }
+    }
+
+    /// A reader that is interrupted before every successful read
+    struct InterruptedReader {
+        inner: Cursor<Vec<u8>>,
+        interrupted: bool
+    }
+
+    impl Read for InterruptedReader {
+        fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+            self.interrupted = !self.interrupted;
+            if self.interrupted {
+                return Err(Error::new(ErrorKind::Interrupted, "interrupted"));
+            }
+            self.inner.read(buffer)
+        }
+    }
+
+    #[test]
+    fn test_read_one_byte_at_a_time() {
+
+        let mut reader = OneByteReader { inner: Cursor::new(vec![
+            0xFA, 0xF1,
+            0xEE, 0x6B, 0x28, 0x00,
+            0x04, 0x23, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00
+        ]) };
+
+        assert_eq!(64241, reader.read_be_u16().unwrap());
+        assert_eq!(2649070, reader.read_le_i32().unwrap());
+        assert_eq!(8964, reader.read_le_u64().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_retries_interrupted() {
+
+        let mut reader = InterruptedReader { inner: Cursor::new(vec![0x00, 0x00, 0x01, 0x00]), interrupted: false };
+
+        assert_eq!(256, reader.read_be_u32().unwrap());
+
+    }
+
+    #[test]
+    fn test_read_truncated_is_unexpected_eof() {
+
+        let mut reader = OneByteReader { inner: Cursor::new(vec![0x01, 0x02, 0x03]) };
+
+        let error = reader.read_be_u64().unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        assert_eq!("read_be_u64", error.operation());
+        assert_eq!(3, error.offset());
+
+    }
 
     #[test]
     fn test_read_empty_u8() {
@@ -517,4 +742,244 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_read_empty_be_u128() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.read_be_u128().is_err());
+
+    }
+
+    #[test]
+    fn test_read_empty_be_i128() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.read_be_i128().is_err());
+
+    }
+
+    #[test]
+    fn test_read_empty_le_u128() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.read_le_u128().is_err());
+
+    }
+
+    #[test]
+    fn test_read_empty_le_i128() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.read_le_i128().is_err());
+
+    }
+
+    #[test]
+    fn test_read_empty_be_f32() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.read_be_f32().is_err());
+
+    }
+
+    #[test]
+    fn test_read_empty_le_f32() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.read_le_f32().is_err());
+
+    }
+
+    #[test]
+    fn test_read_empty_be_f64() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.read_be_f64().is_err());
+
+    }
+
+    #[test]
+    fn test_read_empty_le_f64() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.read_le_f64().is_err());
+
+    }
+
+    #[test]
+    fn test_read_empty_varint_u32() {
+
+        let mut vector = Cursor::new(vec![0u8; 0]);
+
+        assert!(vector.read_varint_u32().is_err());
+
+    }
+
+    #[test]
+    fn test_read_truncated_varint() {
+
+        let mut vector = Cursor::new(vec![0x80, 0x80]);
+
+        let error = vector.read_varint_u64().unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        assert_eq!(2, error.offset());
+
+    }
+
+    #[test]
+    fn test_read_varint_rejects_sixth_byte() {
+
+        let mut vector = Cursor::new(vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]);
+
+        let error = vector.read_varint_i32().unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!("read_varint_i32", error.operation());
+        assert_eq!(4, error.offset());
+
+    }
+
+    #[test]
+    fn test_read_varint_rejects_overflow() {
+
+        let mut vector = Cursor::new(vec![0xFF, 0xFF, 0xFF, 0xFF, 0x10]);
+
+        assert_eq!(ErrorKind::InvalidData, vector.read_varint_u32().unwrap_err().kind());
+
+    }
+
+    #[test]
+    fn test_read_string_rejects_long_length() {
+
+        let mut vector = Cursor::new(vec![0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+
+        let error = vector.read_string(LengthPrefix::VarInt, 1024).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!("read_string", error.operation());
+
+    }
+
+    #[test]
+    fn test_read_string_rejects_invalid_utf8() {
+
+        let mut vector = Cursor::new(vec![0x00, 0x03, 0x61, 0xC3, 0x28]);
+
+        let error = vector.read_string(LengthPrefix::BeU16, 1024).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!(3, error.offset());
+
+    }
+
+    #[test]
+    fn test_read_truncated_string() {
+
+        let mut vector = Cursor::new(vec![0x05, 0x61, 0x62]);
+
+        let error = vector.read_string(LengthPrefix::U8, 1024).unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        assert_eq!(3, error.offset());
+
+    }
+
+    #[test]
+    fn test_read_java_utf_rejects_malformed() {
+
+        let mut vector = Cursor::new(vec![0x00, 0x03, 0x41, 0xC0, 0x41]);
+
+        let error = vector.read_java_utf().unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!(3, error.offset());
+
+    }
+
+    #[test]
+    fn test_read_utf16_rejects_long_length() {
+
+        let mut vector = Cursor::new(vec![0x00, 0x00, 0x01, 0x00]);
+
+        let result = vector.read_utf16(LengthPrefix::BeU32, 255, Endian::Big, false, SurrogatePolicy::Reject);
+
+        assert_eq!(ErrorKind::InvalidData, result.unwrap_err().kind());
+
+    }
+
+    #[test]
+    fn test_read_utf16_nul_rejects_missing_terminator() {
+
+        let mut vector = Cursor::new(vec![0x41, 0x00, 0x42, 0x00, 0x43, 0x00, 0x00, 0x00]);
+
+        let result = vector.read_utf16_nul(2, Endian::Little, false, SurrogatePolicy::Reject);
+
+        assert_eq!(ErrorKind::InvalidData, result.unwrap_err().kind());
+
+        let mut vector = Cursor::new(vec![0x41, 0x00, 0x42]);
+
+        let result = vector.read_utf16_nul(8, Endian::Little, false, SurrogatePolicy::Reject);
+
+        assert_eq!(ErrorKind::UnexpectedEof, result.unwrap_err().kind());
+
+    }
+
+    #[test]
+    fn test_read_utf16_unpaired_surrogate_offset() {
+
+        let mut vector = Cursor::new(vec![0x02, 0x00, 0x41, 0xD8, 0x3D]);
+
+        let result = vector.read_utf16(LengthPrefix::U8, 8, Endian::Big, false, SurrogatePolicy::Reject);
+
+        assert_eq!(3, result.unwrap_err().offset());
+
+    }
+
+    #[test]
+    fn test_read_cstring_stops_at_terminator() {
+
+        let mut vector = Cursor::new(vec![0x61, 0x62, 0x00, 0x63]);
+
+        assert_eq!("ab", vector.read_cstring(16, TextEncoding::Ascii).unwrap());
+        assert_eq!(3, vector.position());
+
+    }
+
+    #[test]
+    fn test_read_cstring_rejects_long_string() {
+
+        let mut vector = Cursor::new(vec![0x61, 0x62, 0x63, 0x00]);
+
+        let error = vector.read_cstring(2, TextEncoding::Utf8).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!(2, error.offset());
+
+        let mut vector = Cursor::new(vec![0x61, 0x62]);
+
+        assert_eq!(ErrorKind::UnexpectedEof, vector.read_cstring(16, TextEncoding::Utf8).unwrap_err().kind());
+
+    }
+
+    #[test]
+    fn test_read_fixed_str() {
+
+        let mut vector = Cursor::new(vec![0x61, 0x20, 0x62, 0x20, 0x20, 0x63]);
+
+        assert_eq!("a b", vector.read_fixed_str(5, 0x20, TextEncoding::Latin1).unwrap());
+        assert_eq!(5, vector.position());
+
+        assert!(vector.read_fixed_str(2, 0x20, TextEncoding::Latin1).is_err());
+
+    }
+
 }
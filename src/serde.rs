@@ -0,0 +1,1101 @@
+//! A serde data format with the byte layout of Reader and Writer
+//!
+//! Values are written field by field with no names, tags or padding, so the format is
+//! not self-describing: `deserialize_any` and `deserialize_ignored_any` are rejected, and
+//! a value must be read back as the type it was written as. A `Config` chooses the byte
+//! order of numbers, whether integers of 16 bits and up are fixed-width or VarInts, and
+//! the length prefix of strings, byte strings, sequences and maps.
+//!
+//! The default configuration produces the same bytes as Readable and Writable: big-endian
+//! fixed-width numbers and VarInt lengths. In either integer encoding, `bool` and the tag
+//! of an `Option` are a single byte that must be 0 or 1, a `char` is its code point as a
+//! u32, enum variants are their index as a u32, and 128-bit integers are always fixed.
+//! Signed VarInts are ZigZag encoded.
+//!
+//! Error offsets count from the first byte the Serializer or Deserializer handled. Errors
+//! that Serialize and Deserialize implementations raise through `custom` are placed at
+//! the start of the value they were raised for.
+
+use std::fmt::Display;
+use std::io::{self, Read, Write};
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize};
+
+use crate::endian::Endian;
+use crate::error::{Error, Result};
+use crate::prefix::{self, LengthPrefix};
+use crate::reader::{self, Reader};
+use crate::text::{self, TextEncoding};
+use crate::value::DEFAULT_MAX_LENGTH;
+use crate::writer::{self, Writer};
+
+/// How integers of 16 bits and wider are stored
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IntegerEncoding {
+
+    /// At their full width, in the configured byte order
+    Fixed,
+
+    /// As VarInts, ZigZag encoded if signed
+    VarInt
+
+}
+
+/// The layout options of the format
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Config {
+
+    endian: Endian,
+
+    integers: IntegerEncoding,
+
+    length_prefix: LengthPrefix,
+
+    max_length: usize
+
+}
+
+impl Config {
+
+    /// Creates the default configuration, which matches Readable and Writable
+    ///
+    /// That is big-endian fixed-width integers, VarInt length prefixes and a limit of
+    /// `DEFAULT_MAX_LENGTH` on lengths
+    pub fn new() -> Config {
+        Config {
+            endian: Endian::Big,
+            integers: IntegerEncoding::Fixed,
+            length_prefix: LengthPrefix::VarInt,
+            max_length: DEFAULT_MAX_LENGTH
+        }
+    }
+
+    /// Sets the byte order of fixed-width numbers
+    pub fn with_endian(mut self, endian: Endian) -> Config {
+        self.endian = endian;
+        self
+    }
+
+    /// Sets how integers of 16 bits and wider are stored
+    pub fn with_integers(mut self, integers: IntegerEncoding) -> Config {
+        self.integers = integers;
+        self
+    }
+
+    /// Sets the prefix of strings, byte strings, sequences and maps
+    pub fn with_length_prefix(mut self, length_prefix: LengthPrefix) -> Config {
+        self.length_prefix = length_prefix;
+        self
+    }
+
+    /// Sets the largest length allowed in either direction
+    ///
+    /// Strings and byte strings are measured in bytes, sequences in elements and maps in
+    /// entries. Lengths are checked before anything is allocated
+    pub fn with_max_length(mut self, max_length: usize) -> Config {
+        self.max_length = max_length;
+        self
+    }
+
+}
+
+impl Default for Config {
+
+    fn default() -> Config {
+        Config::new()
+    }
+
+}
+
+/// Writes a value to the given Writer
+pub fn to_writer<W: Writer, T: Serialize + ?Sized>(writer: W, value: &T, config: Config) -> Result<()> {
+    value.serialize(&mut Serializer::new(writer, config))
+}
+
+/// Writes a value to a new Vec
+pub fn to_vec<T: Serialize + ?Sized>(value: &T, config: Config) -> Result<Vec<u8>> {
+
+    let mut bytes = Vec::new();
+
+    to_writer(&mut bytes, value, config)?;
+
+    Ok(bytes)
+
+}
+
+/// Reads a value from the given Reader
+pub fn from_reader<R: Reader, T: DeserializeOwned>(reader: R, config: Config) -> Result<T> {
+    T::deserialize(&mut Deserializer::new(reader, config))
+}
+
+/// Reads a value from the start of the given bytes
+///
+/// Any bytes after the value are ignored
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8], config: Config) -> Result<T> {
+    from_reader(bytes, config)
+}
+
+/// Counts the bytes passing through a stream, so that errors can be given absolute offsets
+struct Counter<T> {
+
+    inner: T,
+
+    count: u64
+
+}
+
+impl<T: Read> Read for Counter<T> {
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buffer)?;
+        self.count += read as u64;
+        Ok(read)
+    }
+
+}
+
+impl<T: Write> Write for Counter<T> {
+
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buffer)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+}
+
+/// A serde Serializer writing to a Writer
+pub struct Serializer<W> {
+
+    writer: Counter<W>,
+
+    config: Config
+
+}
+
+impl<W: Writer> Serializer<W> {
+
+    /// Creates a Serializer writing to the given Writer
+    pub fn new(writer: W, config: Config) -> Serializer<W> {
+        Serializer { writer: Counter { inner: writer, count: 0 }, config }
+    }
+
+    /// Returns the underlying Writer
+    pub fn into_inner(self) -> W {
+        self.writer.inner
+    }
+
+    /// Runs a write, making the offset of any error absolute
+    fn track<F: FnOnce(&mut Counter<W>) -> Result<()>>(&mut self, operation: F) -> Result<()> {
+        let start = self.writer.count;
+        operation(&mut self.writer).map_err(|error| error.with_base_offset(start))
+    }
+
+    /// Serializes a value nested in the current one, locating any custom error it raises
+    fn nested<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let start = self.writer.count;
+        value.serialize(&mut *self).map_err(|error| locate(error, start))
+    }
+
+    /// Writes an integer in the configured encoding
+    fn integer<F, V>(&mut self, fixed: F, varint: V) -> Result<()>
+        where F: FnOnce(&mut Counter<W>, Endian) -> Result<()>, V: FnOnce(&mut Counter<W>) -> Result<()>
+    {
+
+        let endian = self.config.endian;
+
+        match self.config.integers {
+            IntegerEncoding::Fixed => self.track(|writer| fixed(writer, endian)),
+            IntegerEncoding::VarInt => self.track(varint)
+        }
+
+    }
+
+    /// Writes a length in the configured prefix, rejecting lengths the reader would refuse
+    fn length(&mut self, length: Option<usize>, operation: &'static str) -> Result<()> {
+
+        let length = length.ok_or_else(|| Error::Custom {
+            operation,
+            offset: 0,
+            message: String::from("the length of sequences and maps must be known up front")
+        }).map_err(|error| error.with_base_offset(self.writer.count))?;
+
+        let config = self.config;
+
+        self.track(|writer| {
+            prefix::check_limit(length, config.max_length, operation)?;
+            writer::write_length(writer, config.length_prefix, length, operation)
+        })
+
+    }
+
+    /// Writes length-prefixed bytes
+    fn bytes(&mut self, bytes: &[u8], operation: &'static str) -> Result<()> {
+
+        self.length(Some(bytes.len()), operation)?;
+
+        self.track(|writer| writer::write_buffer(writer, bytes, operation))
+
+    }
+
+    /// Writes an enum variant's index
+    fn variant(&mut self, index: u32) -> Result<()> {
+        self.integer(|writer, endian| writer.write_u32_in(index, endian), |writer| writer.write_varint_u32(index))
+    }
+
+}
+
+impl<W: Writer> ser::Serializer for &mut Serializer<W> {
+
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, value: bool) -> Result<()> {
+        self.track(|writer| writer.write_u8(value as u8))
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<()> {
+        self.track(|writer| writer.write_i8(value))
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<()> {
+        self.integer(|writer, endian| writer.write_i16_in(value, endian), |writer| writer.write_zigzag_i32(value as i32))
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<()> {
+        self.integer(|writer, endian| writer.write_i32_in(value, endian), |writer| writer.write_zigzag_i32(value))
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<()> {
+        self.integer(|writer, endian| writer.write_i64_in(value, endian), |writer| writer.write_zigzag_i64(value))
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<()> {
+        let endian = self.config.endian;
+        self.track(|writer| writer.write_i128_in(value, endian))
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<()> {
+        self.track(|writer| writer.write_u8(value))
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<()> {
+        self.integer(|writer, endian| writer.write_u16_in(value, endian), |writer| writer.write_varint_u32(value as u32))
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<()> {
+        self.integer(|writer, endian| writer.write_u32_in(value, endian), |writer| writer.write_varint_u32(value))
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<()> {
+        self.integer(|writer, endian| writer.write_u64_in(value, endian), |writer| writer.write_varint_u64(value))
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<()> {
+        let endian = self.config.endian;
+        self.track(|writer| writer.write_u128_in(value, endian))
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<()> {
+        let endian = self.config.endian;
+        self.track(|writer| writer.write_f32_in(value, endian))
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<()> {
+        let endian = self.config.endian;
+        self.track(|writer| writer.write_f64_in(value, endian))
+    }
+
+    fn serialize_char(self, value: char) -> Result<()> {
+        self.serialize_u32(value as u32)
+    }
+
+    fn serialize_str(self, value: &str) -> Result<()> {
+        self.bytes(value.as_bytes(), "serialize_str")
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<()> {
+        self.bytes(value, "serialize_bytes")
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        self.serialize_u8(0)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        self.serialize_u8(1)?;
+        self.nested(value)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, index: u32, _variant: &'static str) -> Result<()> {
+        self.variant(index)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<()> {
+        self.nested(value)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        index: u32,
+        _variant: &'static str,
+        value: &T
+    ) -> Result<()> {
+        self.variant(index)?;
+        self.nested(value)
+    }
+
+    fn serialize_seq(self, length: Option<usize>) -> Result<Self> {
+        self.length(length, "serialize_seq")?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _length: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _length: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, index: u32, _variant: &'static str, _length: usize) -> Result<Self> {
+        self.variant(index)?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, length: Option<usize>) -> Result<Self> {
+        self.length(length, "serialize_map")?;
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _length: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, index: u32, _variant: &'static str, _length: usize) -> Result<Self> {
+        self.variant(index)?;
+        Ok(self)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+}
+
+impl<W: Writer> ser::SerializeSeq for &mut Serializer<W> {
+
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.nested(value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+
+}
+
+impl<W: Writer> ser::SerializeTuple for &mut Serializer<W> {
+
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.nested(value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+
+}
+
+impl<W: Writer> ser::SerializeTupleStruct for &mut Serializer<W> {
+
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.nested(value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+
+}
+
+impl<W: Writer> ser::SerializeTupleVariant for &mut Serializer<W> {
+
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.nested(value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+
+}
+
+impl<W: Writer> ser::SerializeMap for &mut Serializer<W> {
+
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.nested(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.nested(value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+
+}
+
+impl<W: Writer> ser::SerializeStruct for &mut Serializer<W> {
+
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _name: &'static str, value: &T) -> Result<()> {
+        self.nested(value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+
+}
+
+impl<W: Writer> ser::SerializeStructVariant for &mut Serializer<W> {
+
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _name: &'static str, value: &T) -> Result<()> {
+        self.nested(value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+
+}
+
+impl ser::Error for Error {
+
+    fn custom<T: Display>(message: T) -> Error {
+        Error::Custom { operation: "serialize", offset: 0, message: message.to_string() }
+    }
+
+}
+
+/// A serde Deserializer reading from a Reader
+pub struct Deserializer<R> {
+
+    reader: Counter<R>,
+
+    config: Config
+
+}
+
+impl<R: Reader> Deserializer<R> {
+
+    /// Creates a Deserializer reading from the given Reader
+    pub fn new(reader: R, config: Config) -> Deserializer<R> {
+        Deserializer { reader: Counter { inner: reader, count: 0 }, config }
+    }
+
+    /// Returns the underlying Reader
+    pub fn into_inner(self) -> R {
+        self.reader.inner
+    }
+
+    /// Runs a read, making the offset of any error absolute
+    fn track<T, F: FnOnce(&mut Counter<R>) -> Result<T>>(&mut self, operation: F) -> Result<T> {
+        let start = self.reader.count;
+        operation(&mut self.reader).map_err(|error| error.with_base_offset(start))
+    }
+
+    /// Deserializes a value nested in the current one, locating any custom error it raises
+    fn nested<'de, T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value> {
+        let start = self.reader.count;
+        seed.deserialize(&mut *self).map_err(|error| locate(error, start))
+    }
+
+    /// Reads an integer in the configured encoding
+    fn integer<T, F, V>(&mut self, fixed: F, varint: V) -> Result<T>
+        where F: FnOnce(&mut Counter<R>, Endian) -> Result<T>, V: FnOnce(&mut Counter<R>) -> Result<T>
+    {
+
+        let endian = self.config.endian;
+
+        match self.config.integers {
+            IntegerEncoding::Fixed => self.track(|reader| fixed(reader, endian)),
+            IntegerEncoding::VarInt => self.track(varint)
+        }
+
+    }
+
+    /// Reads a length in the configured prefix and checks it against the limit
+    fn length(&mut self, operation: &'static str) -> Result<usize> {
+
+        let config = self.config;
+
+        self.track(|reader| {
+            let length = reader::read_length(reader, config.length_prefix, operation)?;
+            prefix::check_limit(length, config.max_length, operation)?;
+            Ok(length)
+        })
+
+    }
+
+    /// Reads length-prefixed bytes
+    fn bytes(&mut self, operation: &'static str) -> Result<Vec<u8>> {
+
+        let length = self.length(operation)?;
+        let mut bytes = vec![0u8; length];
+
+        self.track(|reader| reader::fill_buffer(reader, &mut bytes, operation))?;
+
+        Ok(bytes)
+
+    }
+
+    /// Reads a byte that must be 0 or 1
+    fn flag(&mut self, operation: &'static str) -> Result<bool> {
+        self.track(|reader| match reader.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Error::InvalidEncoding { operation, offset: 0, reason: "expected 0 or 1" })
+        })
+    }
+
+    /// Reads an enum variant's index
+    fn variant(&mut self) -> Result<u32> {
+        self.integer(|reader, endian| reader.read_u32_in(endian), |reader| reader.read_varint_u32())
+    }
+
+}
+
+/// Narrows a VarInt to the integer type being deserialized
+fn narrow<T: TryFrom<U>, U>(value: U, operation: &'static str) -> Result<T> {
+    T::try_from(value).map_err(|_| Error::OutOfRange { operation, offset: 0, reason: "VarInt does not fit the type" })
+}
+
+/// Places an error raised through `custom`, which cannot know its offset, at `start`
+///
+/// Such errors are created at offset 0. Every other error, and every custom error
+/// located already, is left alone: one located at offset 0 can only have come from a
+/// value that started there, so every value enclosing it starts there too.
+fn locate(error: Error, start: u64) -> Error {
+    match error {
+        Error::Custom { operation, offset: 0, message } => Error::Custom { operation, offset: start, message },
+        error => error
+    }
+}
+
+/// Rejects calls that need a self-describing format
+fn not_self_describing(operation: &'static str) -> Error {
+    Error::Custom { operation, offset: 0, message: String::from("the format is not self-describing") }
+}
+
+impl<'de, R: Reader> de::Deserializer<'de> for &mut Deserializer<R> {
+
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(not_self_describing("deserialize_any").with_base_offset(self.reader.count))
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_bool(self.flag("deserialize_bool")?)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i8(self.track(|reader| reader.read_i8())?)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i16(self.integer(
+            |reader, endian| reader.read_i16_in(endian),
+            |reader| narrow(reader.read_zigzag_i32()?, "deserialize_i16")
+        )?)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i32(self.integer(|reader, endian| reader.read_i32_in(endian), |reader| reader.read_zigzag_i32())?)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i64(self.integer(|reader, endian| reader.read_i64_in(endian), |reader| reader.read_zigzag_i64())?)
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let endian = self.config.endian;
+        visitor.visit_i128(self.track(|reader| reader.read_i128_in(endian))?)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u8(self.track(|reader| reader.read_u8())?)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u16(self.integer(
+            |reader, endian| reader.read_u16_in(endian),
+            |reader| narrow(reader.read_varint_u32()?, "deserialize_u16")
+        )?)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u32(self.integer(|reader, endian| reader.read_u32_in(endian), |reader| reader.read_varint_u32())?)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u64(self.integer(|reader, endian| reader.read_u64_in(endian), |reader| reader.read_varint_u64())?)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let endian = self.config.endian;
+        visitor.visit_u128(self.track(|reader| reader.read_u128_in(endian))?)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let endian = self.config.endian;
+        visitor.visit_f32(self.track(|reader| reader.read_f32_in(endian))?)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let endian = self.config.endian;
+        visitor.visit_f64(self.track(|reader| reader.read_f64_in(endian))?)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+
+        let start = self.reader.count;
+        let code_point = self.integer(|reader, endian| reader.read_u32_in(endian), |reader| reader.read_varint_u32())?;
+
+        let value = char::from_u32(code_point).ok_or(Error::InvalidEncoding {
+            operation: "deserialize_char",
+            offset: 0,
+            reason: "not a Unicode scalar value"
+        }).map_err(|error| error.with_base_offset(start))?;
+
+        visitor.visit_char(value)
+
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+
+        let length = self.length("deserialize_string")?;
+        let mut bytes = vec![0u8; length];
+
+        let value = self.track(|reader| {
+            reader::fill_buffer(reader, &mut bytes, "deserialize_string")?;
+            text::decode_text(bytes, TextEncoding::Utf8, "deserialize_string")
+        })?;
+
+        visitor.visit_string(value)
+
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_byte_buf(self.bytes("deserialize_bytes")?)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.flag("deserialize_option")? {
+            true => {
+                let start = self.reader.count;
+                visitor.visit_some(self).map_err(|error| locate(error, start))
+            },
+            false => visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        let start = self.reader.count;
+        visitor.visit_newtype_struct(self).map_err(|error| locate(error, start))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let remaining = self.length("deserialize_seq")?;
+        visitor.visit_seq(Access { deserializer: self, remaining })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, length: usize, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(Access { deserializer: self, remaining: length })
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, length: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_tuple(length, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let remaining = self.length("deserialize_map")?;
+        visitor.visit_map(Access { deserializer: self, remaining })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value> {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(not_self_describing("deserialize_ignored_any").with_base_offset(self.reader.count))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+}
+
+/// Hands out a known number of sequence elements or map entries
+struct Access<'a, R> {
+
+    deserializer: &'a mut Deserializer<R>,
+
+    remaining: usize
+
+}
+
+impl<'de, 'a, R: Reader> de::SeqAccess<'de> for Access<'a, R> {
+
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+
+        self.deserializer.nested(seed).map(Some)
+
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+
+}
+
+impl<'de, 'a, R: Reader> de::MapAccess<'de> for Access<'a, R> {
+
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+
+        self.deserializer.nested(seed).map(Some)
+
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        self.deserializer.nested(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+
+}
+
+impl<'de, R: Reader> de::EnumAccess<'de> for &mut Deserializer<R> {
+
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+        let start = self.reader.count;
+        let index = self.variant()?;
+        let value = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(index))
+            .map_err(|error| locate(error, start))?;
+        Ok((value, self))
+    }
+
+}
+
+impl<'de, R: Reader> de::VariantAccess<'de> for &mut Deserializer<R> {
+
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        self.nested(seed)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, length: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_tuple(self, length, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+
+}
+
+impl de::Error for Error {
+
+    fn custom<T: Display>(message: T) -> Error {
+        Error::Custom { operation: "deserialize", offset: 0, message: message.to_string() }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::value::Prefixed;
+    use crate::prefix::U8Length;
+
+    use serde::{Deserialize, Serialize};
+
+    use std::collections::{BTreeMap, HashMap};
+    use std::fmt::Debug;
+    use std::io::ErrorKind;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Player {
+        id: u64,
+        name: String,
+        position: (f32, f32, f32),
+        inventory: Vec<Item>,
+        guild: Option<Guild>,
+        stats: BTreeMap<String, i32>
+    }
+
+    struct Unserializable;
+
+    impl Serialize for Unserializable {
+        fn serialize<S: ser::Serializer>(&self, _serializer: S) -> std::result::Result<S::Ok, S::Error> {
+            Err(ser::Error::custom("cannot be serialized"))
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Item {
+        kind: Kind,
+        count: u16
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Kind {
+        Empty,
+        Block(u32),
+        Tool { durability: i16, enchanted: bool },
+        Banner(char, i64)
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Guild(String);
+
+    fn player() -> Player {
+        Player {
+            id: 0x0102030405060708,
+            name: String::from("Notch"),
+            position: (1.5, -64.0, 1e6),
+            inventory: vec![
+                Item { kind: Kind::Empty, count: 0 },
+                Item { kind: Kind::Block(3), count: 64 },
+                Item { kind: Kind::Tool { durability: -1, enchanted: true }, count: 1 },
+                Item { kind: Kind::Banner('ß', i64::MIN), count: 300 }
+            ],
+            guild: Some(Guild(String::from("Mojang"))),
+            stats: [(String::from("deaths"), -3), (String::from("kills"), 70000)].into_iter().collect()
+        }
+    }
+
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: &T, config: Config) {
+        let bytes = to_vec(value, config).unwrap();
+        assert_eq!(*value, from_slice::<T>(&bytes, config).unwrap());
+    }
+
+    #[test]
+    fn test_round_trip() {
+
+        let configs = [
+            Config::new(),
+            Config::new().with_endian(Endian::Little).with_integers(IntegerEncoding::VarInt),
+            Config::new().with_length_prefix(LengthPrefix::LeU32).with_integers(IntegerEncoding::VarInt),
+            Config::new().with_endian(Endian::Little).with_length_prefix(LengthPrefix::BeU16)
+        ];
+
+        for config in configs {
+
+            round_trip(&player(), config);
+            round_trip(&Player { guild: None, inventory: Vec::new(), ..player() }, config);
+
+            let map: HashMap<u32, Option<Vec<u8>>> = [(1, None), (2, Some(vec![3, 4]))].into_iter().collect();
+            round_trip(&map, config);
+            round_trip(&(u128::MAX, i128::MIN, (), 'x'), config);
+
+        }
+
+    }
+
+    #[test]
+    fn test_default_matches_readable() {
+
+        let value = (7u32, -2i64, Some(String::from("same")), vec![1u16, 2], 'é', true);
+
+        let mut expected = Vec::new();
+        expected.write_value(&value).unwrap();
+
+        assert_eq!(expected, to_vec(&value, Config::default()).unwrap());
+
+    }
+
+    #[test]
+    fn test_layout() {
+
+        let config = Config::new()
+            .with_endian(Endian::Little)
+            .with_integers(IntegerEncoding::VarInt)
+            .with_length_prefix(LengthPrefix::U8);
+
+        assert_eq!(vec![0xAC, 0x02], to_vec(&300u16, config).unwrap());
+        assert_eq!(vec![0x03], to_vec(&-2i32, config).unwrap());
+        assert_eq!(vec![0x00, 0x00, 0xC0, 0x3F], to_vec(&1.5f32, config).unwrap());
+        assert_eq!(vec![0x02, b'h', b'i'], to_vec("hi", config).unwrap());
+        assert_eq!(vec![0x01, 0x00], to_vec(&Kind::Block(0), config).unwrap());
+        assert_eq!(vec![0x01, 0x01, 0x05], to_vec(&Some(Kind::Block(5)), config).unwrap());
+
+        let prefixed = Prefixed::<String, U8Length>::new(String::from("hi"));
+        let mut expected = Vec::new();
+        expected.write_value(&prefixed).unwrap();
+
+        assert_eq!(expected, to_vec("hi", config).unwrap());
+
+    }
+
+    #[test]
+    fn test_errors() {
+
+        let config = Config::new().with_max_length(4);
+
+        let error = to_vec("too long", config).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!("serialize_str", error.operation());
+
+        let bytes = to_vec(&(1u32, "too long"), Config::new()).unwrap();
+        let error = from_slice::<(u32, String)>(&bytes, config).unwrap_err();
+
+        assert_eq!("deserialize_string", error.operation());
+        assert_eq!(4, error.offset());
+
+        let error = from_slice::<(u32, u64)>(&bytes[..6], Config::new()).unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        assert_eq!(6, error.offset());
+
+        let error = from_slice::<Kind>(&[0, 0, 0, 9], Config::new()).unwrap_err();
+
+        assert_eq!("deserialize", error.operation());
+        assert_eq!(0, error.offset());
+
+        let error = from_slice::<Vec<Kind>>(&[2, 0, 0, 0, 0, 0, 0, 0, 9], Config::new()).unwrap_err();
+
+        assert_eq!("deserialize", error.operation());
+        assert_eq!(5, error.offset());
+
+        let error = from_slice::<(u8, char)>(&[1, 0, 0, 0xD8, 0x00], Config::new()).unwrap_err();
+
+        assert_eq!("deserialize_char", error.operation());
+        assert_eq!(1, error.offset());
+
+        let error = to_vec(&(1u32, Unserializable), Config::new()).unwrap_err();
+
+        assert_eq!("serialize", error.operation());
+        assert_eq!(4, error.offset());
+
+        let error = from_slice::<bool>(&[2], Config::new()).unwrap_err();
+
+        assert_eq!("deserialize_bool", error.operation());
+
+        let config = Config::new().with_integers(IntegerEncoding::VarInt);
+        let error = from_slice::<u16>(&[0x80, 0x80, 0x04], config).unwrap_err();
+
+        assert_eq!("deserialize_u16", error.operation());
+
+    }
+
+}
@@ -0,0 +1,502 @@
+//! Text encodings used by string operations
+
+use std::mem;
+
+use crate::endian::Endian;
+use crate::error::{Error, Result};
+use crate::prefix;
+
+/// How unpaired UTF-16 surrogates are handled when decoding
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SurrogatePolicy {
+
+    /// Fail with an InvalidEncoding error
+    Reject,
+
+    /// Replace each unpaired surrogate with U+FFFD REPLACEMENT CHARACTER
+    Replace
+
+}
+
+/// An ASCII-compatible text encoding for NUL-terminated and fixed-width strings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextEncoding {
+
+    /// UTF-8
+    Utf8,
+
+    /// 7-bit US-ASCII
+    Ascii,
+
+    /// ISO-8859-1, where every byte is the code point of the same value
+    Latin1
+
+}
+
+/// The largest number of bytes `writeUTF` can store after its u16 length
+pub const JAVA_UTF_MAX_LENGTH: usize = u16::MAX as usize;
+
+/// Encodes a string as Java's modified UTF-8
+///
+/// NUL is stored as `C0 80` and supplementary characters as a surrogate pair of three
+/// byte sequences, exactly as `DataOutputStream.writeUTF` does
+pub(crate) fn encode_java_utf(value: &str) -> Vec<u8> {
+
+    let mut bytes = Vec::with_capacity(value.len());
+
+    for unit in value.encode_utf16() {
+        match unit {
+            0x0001..=0x007F => {
+                bytes.push(unit as u8);
+            },
+            0x0000 | 0x0080..=0x07FF => {
+                bytes.push(0xC0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            },
+            _ => {
+                bytes.push(0xE0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+
+    bytes
+
+}
+
+/// Decodes Java's modified UTF-8, as read by `DataInputStream.readUTF`
+///
+/// Error offsets are relative to the start of `bytes`. Unpaired surrogates are
+/// rejected, since they cannot be represented in a Rust string
+pub(crate) fn decode_java_utf(bytes: &[u8], operation: &'static str) -> Result<String> {
+
+    let mut value = String::with_capacity(bytes.len());
+    let mut high_surrogate: Option<(u16, usize)> = None;
+    let mut index = 0;
+
+    while index < bytes.len() {
+
+        let start = index;
+        let first = bytes[index] as u16;
+
+        let (unit, length) = match first >> 4 {
+            0..=7 => (first, 1),
+            12 | 13 => match bytes.get(index + 1) {
+                Some(&second) if second & 0xC0 == 0x80 => {
+                    (((first & 0x1F) << 6) | (second as u16 & 0x3F), 2)
+                },
+                _ => return Err(malformed(operation, start, "malformed modified UTF-8"))
+            },
+            14 => match (bytes.get(index + 1), bytes.get(index + 2)) {
+                (Some(&second), Some(&third)) if second & 0xC0 == 0x80 && third & 0xC0 == 0x80 => {
+                    (((first & 0x0F) << 12) | ((second as u16 & 0x3F) << 6) | (third as u16 & 0x3F), 3)
+                },
+                _ => return Err(malformed(operation, start, "malformed modified UTF-8"))
+            },
+            _ => return Err(malformed(operation, start, "malformed modified UTF-8"))
+        };
+
+        index += length;
+
+        match (high_surrogate.take(), unit) {
+            (Some((high, _)), 0xDC00..=0xDFFF) => {
+                let code_point = 0x10000 + (((high as u32) - 0xD800) << 10) + ((unit as u32) - 0xDC00);
+                value.push(::std::char::from_u32(code_point).unwrap());
+            },
+            (Some((_, offset)), _) => {
+                return Err(malformed(operation, offset, "unpaired surrogate"));
+            },
+            (None, 0xD800..=0xDBFF) => {
+                high_surrogate = Some((unit, start));
+            },
+            (None, 0xDC00..=0xDFFF) => {
+                return Err(malformed(operation, start, "unpaired surrogate"));
+            },
+            (None, _) => {
+                value.push(::std::char::from_u32(unit as u32).unwrap());
+            }
+        }
+
+    }
+
+    if let Some((_, offset)) = high_surrogate {
+        return Err(malformed(operation, offset, "unpaired surrogate"));
+    }
+
+    Ok(value)
+
+}
+
+/// Encodes a string as UTF-16 code units in the given byte order
+///
+/// When `bom` is set, a byte order mark is written before the string
+pub(crate) fn encode_utf16(value: &str, endian: Endian, bom: bool) -> Vec<u8> {
+
+    let mut bytes = Vec::with_capacity(value.len() * 2 + 2);
+
+    let units = if bom { Some(0xFEFF) } else { None };
+
+    for unit in units.into_iter().chain(value.encode_utf16()) {
+        match endian {
+            Endian::Big => bytes.extend_from_slice(&unit.to_be_bytes()),
+            Endian::Little => bytes.extend_from_slice(&unit.to_le_bytes())
+        }
+    }
+
+    bytes
+
+}
+
+/// Decodes UTF-16 code units stored in the given byte order
+///
+/// When `detect_bom` is set, a leading byte order mark is consumed and overrides
+/// `endian`. Error offsets are relative to the start of `bytes`
+pub(crate) fn decode_utf16(
+    bytes: &[u8],
+    mut endian: Endian,
+    detect_bom: bool,
+    policy: SurrogatePolicy,
+    operation: &'static str
+) -> Result<String> {
+
+    let mut start = 0;
+
+    if detect_bom && bytes.len() >= 2 {
+        match (bytes[0], bytes[1]) {
+            (0xFE, 0xFF) => { endian = Endian::Big; start = 2; },
+            (0xFF, 0xFE) => { endian = Endian::Little; start = 2; },
+            _ => { }
+        }
+    }
+
+    let units = bytes[start..].chunks(2).map(|pair| match endian {
+        Endian::Big => u16::from_be_bytes([pair[0], pair[1]]),
+        Endian::Little => u16::from_le_bytes([pair[0], pair[1]])
+    });
+
+    let mut value = String::with_capacity(bytes.len() / 2);
+    let mut offset = start;
+
+    for character in ::std::char::decode_utf16(units) {
+        match character {
+            Ok(character) => {
+                value.push(character);
+                offset += 2 * character.len_utf16();
+            },
+            Err(_) if policy == SurrogatePolicy::Replace => {
+                value.push(::std::char::REPLACEMENT_CHARACTER);
+                offset += 2;
+            },
+            Err(_) => {
+                return Err(malformed(operation, offset, "unpaired surrogate"));
+            }
+        }
+    }
+
+    Ok(value)
+
+}
+
+/// Encodes a string in the given ASCII-compatible encoding
+///
+/// Characters the encoding cannot represent are rejected with an OutOfRange error
+/// whose offset is the character's byte index within `value`
+pub(crate) fn encode_text(value: &str, encoding: TextEncoding, operation: &'static str) -> Result<Vec<u8>> {
+
+    let limit = match encoding {
+        TextEncoding::Utf8 => return Ok(value.as_bytes().to_vec()),
+        TextEncoding::Ascii => 0x7F,
+        TextEncoding::Latin1 => 0xFF
+    };
+
+    let mut bytes = Vec::with_capacity(value.len());
+
+    for (index, character) in value.char_indices() {
+
+        if character as u32 > limit {
+            return Err(Error::OutOfRange {
+                operation,
+                offset: index as u64,
+                reason: if encoding == TextEncoding::Ascii {
+                    "character is not ASCII"
+                } else {
+                    "character is not Latin-1"
+                }
+            });
+        }
+
+        bytes.push(character as u8);
+
+    }
+
+    Ok(bytes)
+
+}
+
+/// Decodes bytes in the given ASCII-compatible encoding
+///
+/// Error offsets are relative to the start of `bytes`
+pub(crate) fn decode_text(bytes: Vec<u8>, encoding: TextEncoding, operation: &'static str) -> Result<String> {
+
+    match encoding {
+        TextEncoding::Utf8 => {
+            String::from_utf8(bytes).map_err(|error| {
+                malformed(operation, error.utf8_error().valid_up_to(), "invalid UTF-8")
+            })
+        },
+        TextEncoding::Ascii => {
+            match bytes.iter().position(|byte| *byte > 0x7F) {
+                Some(index) => Err(malformed(operation, index, "invalid ASCII")),
+                None => Ok(String::from_utf8(bytes).unwrap())
+            }
+        },
+        TextEncoding::Latin1 => {
+            Ok(bytes.iter().map(|byte| *byte as char).collect())
+        }
+    }
+
+}
+
+/// Encodes a string followed by a NUL terminator
+///
+/// Strings that contain NUL are rejected, since they could not be read back
+pub(crate) fn encode_cstring(value: &str, encoding: TextEncoding, operation: &'static str) -> Result<Vec<u8>> {
+
+    reject_nul(value, operation)?;
+
+    let mut bytes = encode_text(value, encoding, operation)?;
+
+    bytes.push(0);
+
+    Ok(bytes)
+
+}
+
+/// Encodes a string as UTF-16 followed by a NUL code unit
+///
+/// Strings that contain NUL are rejected, since they could not be read back
+pub(crate) fn encode_utf16_nul(value: &str, endian: Endian, bom: bool, operation: &'static str) -> Result<Vec<u8>> {
+
+    reject_nul(value, operation)?;
+
+    let mut bytes = encode_utf16(value, endian, bom);
+
+    bytes.extend_from_slice(&[0, 0]);
+
+    Ok(bytes)
+
+}
+
+/// Encodes a string into a field of exactly `width` bytes, padded with `pad_byte`
+///
+/// Values whose encoding is longer than `width` are rejected rather than truncated
+pub(crate) fn encode_fixed(
+    value: &str,
+    width: usize,
+    pad_byte: u8,
+    encoding: TextEncoding,
+    operation: &'static str
+) -> Result<Vec<u8>> {
+
+    let mut bytes = encode_text(value, encoding, operation)?;
+
+    prefix::check_limit(bytes.len(), width, operation)?;
+
+    bytes.resize(width, pad_byte);
+
+    Ok(bytes)
+
+}
+
+/// Decodes a fixed-width field after removing any trailing `pad_byte`s
+pub(crate) fn decode_fixed(
+    mut bytes: Vec<u8>,
+    pad_byte: u8,
+    encoding: TextEncoding,
+    operation: &'static str
+) -> Result<String> {
+
+    let length = bytes.iter().rposition(|byte| *byte != pad_byte).map_or(0, |index| index + 1);
+
+    bytes.truncate(length);
+
+    decode_text(bytes, encoding, operation)
+
+}
+
+/// Collects the code units of a terminated string as they are read
+///
+/// Strings are limited to a number of code units before the terminator, which is an
+/// all-zero code unit
+pub(crate) struct Terminated {
+    bytes: Vec<u8>,
+    max_units: usize,
+    unit_width: usize
+}
+
+impl Terminated {
+
+    /// Creates an empty collector for code units of the given width in bytes
+    pub(crate) fn new(max_units: usize, unit_width: usize) -> Terminated {
+        Terminated { bytes: Vec::new(), max_units, unit_width }
+    }
+
+    /// Returns the number of bytes collected so far
+    pub(crate) fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Adds a code unit, returning true if it was the terminator
+    pub(crate) fn push(&mut self, unit: &[u8], operation: &'static str) -> Result<bool> {
+
+        if unit.iter().all(|byte| *byte == 0) {
+            return Ok(true);
+        }
+
+        if self.bytes.len() / self.unit_width == self.max_units {
+            return Err(Error::LengthLimitExceeded {
+                operation,
+                offset: self.bytes.len() as u64,
+                length: self.max_units as u64 + 1,
+                limit: self.max_units as u64
+            });
+        }
+
+        self.bytes.extend_from_slice(unit);
+
+        Ok(false)
+
+    }
+
+    /// Takes the collected bytes, without the terminator
+    pub(crate) fn take_bytes(&mut self) -> Vec<u8> {
+        mem::take(&mut self.bytes)
+    }
+
+}
+
+/// Rejects strings containing NUL
+fn reject_nul(value: &str, operation: &'static str) -> Result<()> {
+
+    match value.find('\0') {
+        Some(index) => Err(malformed(operation, index, "NUL inside a NUL-terminated string")),
+        None => Ok(())
+    }
+
+}
+
+/// Creates an InvalidEncoding error at the given offset
+fn malformed(operation: &'static str, offset: usize, reason: &'static str) -> Error {
+    Error::InvalidEncoding { operation, offset: offset as u64, reason }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_encode_text() {
+
+        assert_eq!(vec![0xC3, 0xA9], encode_text("é", TextEncoding::Utf8, "encode").unwrap());
+        assert_eq!(vec![0xE9], encode_text("é", TextEncoding::Latin1, "encode").unwrap());
+        assert_eq!(1, encode_text("aé", TextEncoding::Ascii, "encode").unwrap_err().offset());
+        assert_eq!(1, encode_text("a€", TextEncoding::Latin1, "encode").unwrap_err().offset());
+
+    }
+
+    #[test]
+    fn test_decode_text() {
+
+        assert_eq!("é", decode_text(vec![0xC3, 0xA9], TextEncoding::Utf8, "decode").unwrap());
+        assert_eq!("Ã©", decode_text(vec![0xC3, 0xA9], TextEncoding::Latin1, "decode").unwrap());
+        assert_eq!(0, decode_text(vec![0xC3, 0xA9], TextEncoding::Ascii, "decode").unwrap_err().offset());
+        assert_eq!(1, decode_text(vec![0x61, 0xA9], TextEncoding::Utf8, "decode").unwrap_err().offset());
+
+    }
+
+    #[test]
+    fn test_encode_utf16() {
+
+        assert_eq!(vec![0x00, 0x41, 0xD8, 0x3D, 0xDE, 0x00], encode_utf16("A😀", Endian::Big, false));
+        assert_eq!(vec![0xFF, 0xFE, 0x41, 0x00], encode_utf16("A", Endian::Little, true));
+
+    }
+
+    #[test]
+    fn test_decode_utf16() {
+
+        let bytes = [0x00, 0x41, 0xD8, 0x3D, 0xDE, 0x00];
+
+        assert_eq!("A😀", decode_utf16(&bytes, Endian::Big, false, SurrogatePolicy::Reject, "decode").unwrap());
+
+    }
+
+    #[test]
+    fn test_decode_utf16_detects_bom() {
+
+        let little = [0xFF, 0xFE, 0x41, 0x00];
+        let big = [0xFE, 0xFF, 0x00, 0x41];
+
+        assert_eq!("A", decode_utf16(&little, Endian::Big, true, SurrogatePolicy::Reject, "decode").unwrap());
+        assert_eq!("A", decode_utf16(&big, Endian::Little, true, SurrogatePolicy::Reject, "decode").unwrap());
+        assert_eq!("\u{FEFF}A", decode_utf16(&big, Endian::Big, false, SurrogatePolicy::Reject, "decode").unwrap());
+
+    }
+
+    #[test]
+    fn test_decode_utf16_unpaired_surrogates() {
+
+        let bytes = [0x41, 0x00, 0x3D, 0xD8, 0x42, 0x00, 0x00, 0xDE];
+
+        let error = decode_utf16(&bytes, Endian::Little, false, SurrogatePolicy::Reject, "decode").unwrap_err();
+
+        assert_eq!(2, error.offset());
+        assert_eq!(
+            "A\u{FFFD}B\u{FFFD}",
+            decode_utf16(&bytes, Endian::Little, false, SurrogatePolicy::Replace, "decode").unwrap()
+        );
+
+    }
+
+    #[test]
+    fn test_encode_java_utf() {
+
+        assert_eq!(
+            vec![0x41, 0xC0, 0x80, 0xE2, 0x82, 0xAC, 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80],
+            encode_java_utf("A\0€😀")
+        );
+        assert_eq!(vec![0xC3, 0xA9], encode_java_utf("é"));
+
+    }
+
+    #[test]
+    fn test_decode_java_utf() {
+
+        let bytes = [0x41, 0xC0, 0x80, 0xE2, 0x82, 0xAC, 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80];
+
+        assert_eq!("A\0€😀", decode_java_utf(&bytes, "decode").unwrap());
+        assert_eq!("\0", decode_java_utf(&[0x00], "decode").unwrap());
+
+    }
+
+    #[test]
+    fn test_decode_java_utf_rejects_malformed() {
+
+        assert_eq!(1, decode_java_utf(&[0x41, 0x80], "decode").unwrap_err().offset());
+        assert_eq!(1, decode_java_utf(&[0x41, 0xE2, 0x82], "decode").unwrap_err().offset());
+        assert_eq!(0, decode_java_utf(&[0xF0, 0x9F, 0x98, 0x80], "decode").unwrap_err().offset());
+
+    }
+
+    #[test]
+    fn test_decode_java_utf_rejects_unpaired_surrogates() {
+
+        assert_eq!(0, decode_java_utf(&[0xED, 0xA0, 0xBD, 0x41], "decode").unwrap_err().offset());
+        assert_eq!(1, decode_java_utf(&[0x41, 0xED, 0xB8, 0x80], "decode").unwrap_err().offset());
+        assert_eq!(0, decode_java_utf(&[0xED, 0xA0, 0xBD], "decode").unwrap_err().offset());
+
+    }
+
+}
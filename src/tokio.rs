@@ -0,0 +1,233 @@
+//! Async reads and writes over tokio's AsyncRead and AsyncWrite
+//!
+//! AsyncReader and AsyncWriter mirror Reader and Writer method for method, apart from
+//! `read_value`, `write_value` and `take_limited`. Each method runs the same encoding
+//! and decoding steps as its blocking counterpart, so the names, byte layouts, length
+//! limits and errors are the same; only the waiting differs. Each method awaits until
+//! the whole value has been transferred.
+//!
+//! The traits cover every stream, Send or not, and their futures are Send whenever the
+//! stream is. Generic code that must prove it, for example to spawn a read on a
+//! multi-threaded runtime, can bound on SendAsyncReader and SendAsyncWriter instead:
+//! they have the same methods but are only implemented for Send streams. Import one
+//! pair or the other, since a Send stream has both.
+//!
+//! tokio's own AsyncReadExt and AsyncWriteExt have methods with some of the same names,
+//! such as `read_u8`, so import one or the other into a given scope.
+//!
+//! These methods are not cancellation safe. Dropping one of their futures part way
+//! through, as `tokio::select!` does, leaves the stream at an unknown position.
+
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+crate::async_io::async_extensions! {
+    read: tokio::io::AsyncRead,
+    write: tokio::io::AsyncWrite,
+    library: "tokio's"
+}
+
+/// Reads whatever is available into the buffer
+async fn read_some<R: AsyncRead + Unpin + ?Sized>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    tokio::io::AsyncReadExt::read(reader, buffer).await
+}
+
+/// Writes as much of the buffer as the writer will take
+async fn write_some<W: AsyncWrite + Unpin + ?Sized>(writer: &mut W, buffer: &[u8]) -> io::Result<usize> {
+    tokio::io::AsyncWriteExt::write(writer, buffer).await
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{AsyncReader, AsyncWriter};
+
+    use crate::endian::{BigEndian, Endian, LittleEndian};
+    use crate::prefix::LengthPrefix;
+    use crate::text::{SurrogatePolicy, TextEncoding};
+    use crate::writer::Writer;
+
+    use std::io::{self, ErrorKind};
+    use std::marker::PhantomData;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::task::{Context, Poll};
+
+    use tokio::io::{duplex, AsyncRead, ReadBuf};
+
+    fn assert_send<T: Send>(_: T) { }
+
+    #[tokio::test]
+    async fn test_primitives() {
+
+        let (mut client, mut server) = duplex(256);
+
+        client.write_u8(1).await.unwrap();
+        client.write_i8(-2).await.unwrap();
+        client.write_be_u16(0x0304).await.unwrap();
+        client.write_i32::<LittleEndian>(-5).await.unwrap();
+        client.write_u64_in(6, Endian::Little).await.unwrap();
+        client.write_be_i128(-7).await.unwrap();
+        client.write_le_f32(8.5).await.unwrap();
+        client.write_f64::<BigEndian>(-9.25).await.unwrap();
+
+        assert_eq!(1, server.read_u8().await.unwrap());
+        assert_eq!(-2, server.read_i8().await.unwrap());
+        assert_eq!(0x0304, server.read_be_u16().await.unwrap());
+        assert_eq!(-5, server.read_i32::<LittleEndian>().await.unwrap());
+        assert_eq!(6, server.read_u64_in(Endian::Little).await.unwrap());
+        assert_eq!(-7, server.read_be_i128().await.unwrap());
+        assert_eq!(8.5, server.read_le_f32().await.unwrap());
+        assert_eq!(-9.25, server.read_f64::<BigEndian>().await.unwrap());
+
+    }
+
+    #[tokio::test]
+    async fn test_slices() {
+
+        let (mut client, mut server) = duplex(256);
+        let mut values = [0u32; 3];
+
+        client.write_be_u32_slice(&[1, 2, 3]).await.unwrap();
+        server.read_be_u32_into(&mut values).await.unwrap();
+
+        assert_eq!([1, 2, 3], values);
+
+    }
+
+    #[tokio::test]
+    async fn test_varints() {
+
+        let (mut client, mut server) = duplex(256);
+
+        client.write_varint_u32(25565).await.unwrap();
+        client.write_varint_i32(-1).await.unwrap();
+        client.write_varint_u64(u64::MAX).await.unwrap();
+        client.write_varint_i64(-2).await.unwrap();
+        client.write_zigzag_i32(-3).await.unwrap();
+        client.write_zigzag_i64(4).await.unwrap();
+
+        assert_eq!(25565, server.read_varint_u32().await.unwrap());
+        assert_eq!(-1, server.read_varint_i32().await.unwrap());
+        assert_eq!(u64::MAX, server.read_varint_u64().await.unwrap());
+        assert_eq!(-2, server.read_varint_i64().await.unwrap());
+        assert_eq!(-3, server.read_zigzag_i32().await.unwrap());
+        assert_eq!(4, server.read_zigzag_i64().await.unwrap());
+
+    }
+
+    #[tokio::test]
+    async fn test_strings() {
+
+        let (mut client, mut server) = duplex(1024);
+
+        client.write_length_prefix(LengthPrefix::LeU16, 300).await.unwrap();
+        client.write_string("hello", LengthPrefix::VarInt).await.unwrap();
+        client.write_java_utf("nul\0").await.unwrap();
+        client.write_utf16("\u{1F600}", LengthPrefix::BeU16, Endian::Little, true).await.unwrap();
+        client.write_utf16_nul("utf", Endian::Big, false).await.unwrap();
+        client.write_cstring("caf\u{E9}", TextEncoding::Latin1).await.unwrap();
+        client.write_fixed_str("ab", 4, b' ', TextEncoding::Ascii).await.unwrap();
+
+        assert_eq!(300, server.read_length_prefix(LengthPrefix::LeU16).await.unwrap());
+        assert_eq!("hello", server.read_string(LengthPrefix::VarInt, 16).await.unwrap());
+        assert_eq!("nul\0", server.read_java_utf().await.unwrap());
+        assert_eq!(
+            "\u{1F600}",
+            server.read_utf16(LengthPrefix::BeU16, 8, Endian::Big, true, SurrogatePolicy::Reject).await.unwrap()
+        );
+        assert_eq!("utf", server.read_utf16_nul(8, Endian::Big, false, SurrogatePolicy::Reject).await.unwrap());
+        assert_eq!("caf\u{E9}", server.read_cstring(8, TextEncoding::Latin1).await.unwrap());
+        assert_eq!("ab", server.read_fixed_str(4, b' ', TextEncoding::Ascii).await.unwrap());
+
+    }
+
+    #[tokio::test]
+    async fn test_matches_sync_layout() {
+
+        let (mut client, mut server) = duplex(256);
+        let mut expected = Vec::new();
+
+        Writer::write_be_u32(&mut expected, 0xDEADBEEF).unwrap();
+        Writer::write_varint_i64(&mut expected, -300).unwrap();
+        Writer::write_string(&mut expected, "layout", LengthPrefix::BeU32).unwrap();
+
+        client.write_be_u32(0xDEADBEEF).await.unwrap();
+        client.write_varint_i64(-300).await.unwrap();
+        client.write_string("layout", LengthPrefix::BeU32).await.unwrap();
+        drop(client);
+
+        let mut actual = Vec::new();
+
+        tokio::io::AsyncReadExt::read_to_end(&mut server, &mut actual).await.unwrap();
+
+        assert_eq!(expected, actual);
+
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+
+        let (mut client, mut server) = duplex(256);
+
+        tokio::io::AsyncWriteExt::write_all(&mut client, &[0x05, b'a', b'b']).await.unwrap();
+        drop(client);
+
+        assert_eq!(ErrorKind::InvalidData, server.read_string(LengthPrefix::U8, 0).await.unwrap_err().kind());
+
+        let (mut client, mut server) = duplex(256);
+
+        tokio::io::AsyncWriteExt::write_all(&mut client, &[0x05, b'a', b'b']).await.unwrap();
+        drop(client);
+
+        let error = server.read_string(LengthPrefix::U8, 16).await.unwrap_err();
+
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        assert_eq!("read_string", error.operation());
+        assert_eq!(3, error.offset());
+
+        let (mut client, _server) = duplex(256);
+
+        assert!(client.write_string(&"a".repeat(256), LengthPrefix::U8).await.is_err());
+
+    }
+
+    #[test]
+    fn test_futures_are_send() {
+
+        let (mut client, mut server) = duplex(256);
+
+        assert_send(server.read_be_u32());
+        assert_send(client.write_string("send", LengthPrefix::VarInt));
+
+        generic(&mut server, &mut client);
+
+        fn generic<R: super::SendAsyncReader, W: super::SendAsyncWriter>(reader: &mut R, writer: &mut W) {
+            assert_send(super::SendAsyncReader::read_u64::<BigEndian>(reader));
+            assert_send(super::SendAsyncReader::read_utf16_nul(reader, 8, Endian::Big, false, SurrogatePolicy::Reject));
+            assert_send(super::SendAsyncWriter::write_be_u32_slice(writer, &[1, 2]));
+            assert_send(super::SendAsyncWriter::write_cstring(writer, "send", TextEncoding::Ascii));
+        }
+
+    }
+
+    #[tokio::test]
+    async fn test_streams_that_are_not_send() {
+
+        struct Local<'a>(&'a [u8], PhantomData<Rc<()>>);
+
+        impl AsyncRead for Local<'_> {
+            fn poll_read(mut self: Pin<&mut Self>, context: &mut Context<'_>, buffer: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+                Pin::new(&mut self.0).poll_read(context, buffer)
+            }
+        }
+
+        let mut reader = Local(&[0x01, 0x02, 0x03], PhantomData);
+
+        assert_eq!(0x0102, reader.read_be_u16().await.unwrap());
+        assert_eq!(3, reader.read_u8().await.unwrap());
+
+    }
+
+}
@@ -0,0 +1,488 @@
+//! Types that know how to read and write themselves
+//!
+//! Readable and Writable describe a type's byte layout once, so that it can be read with
+//! `reader.read_value::<T>()` and written with `writer.write_value(&value)`. They are
+//! implemented for the primitives, `bool`, `char`, `Option`, tuples, arrays, `Vec` and
+//! `String`, and compose: a tuple of Vecs of Options is readable if its parts are.
+//!
+//! Multi-byte numbers are big-endian, as in most network protocols. `bool` and the tag
+//! of an `Option` are a single byte that must be 0 or 1, and a `char` is its code point
+//! as a big-endian u32. `Vec` and `String` are preceded by a VarInt length, counting
+//! elements or bytes respectively, and are limited to `DEFAULT_MAX_LENGTH`; wrap them in
+//! `Prefixed` to pick another prefix or limit.
+
+use std::marker::PhantomData;
+
+use crate::error::{Error, Result};
+use crate::prefix::{self, LengthPrefix, PrefixKind, VarIntLength};
+use crate::reader::{self, Reader};
+use crate::writer::Writer;
+
+/// The largest length a `Vec` or `String` may have unless `Prefixed` says otherwise
+///
+/// Lengths are checked before anything is allocated, so a hostile peer cannot make a
+/// reader reserve more than this many elements or bytes
+pub const DEFAULT_MAX_LENGTH: usize = 1 << 24;
+
+/// A type that can be read from a Reader
+pub trait Readable : Sized {
+
+    /// Reads a value of this type from the given Reader
+    fn read_from<R: Reader + ?Sized>(reader: &mut R) -> Result<Self>;
+
+}
+
+/// A type that can be written to a Writer
+pub trait Writable {
+
+    /// Writes this value to the given Writer
+    fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()>;
+
+}
+
+/// A `Vec` or `String` with a length prefix of type `P` and a limit of `MAX` on its length
+///
+/// For example, `Prefixed<String, BeU16Length>` is a string preceded by its length in
+/// bytes as a big-endian u16, and `Prefixed<Vec<u32>, U8Length, 16>` is at most 16
+/// big-endian integers preceded by their count as a single byte
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Prefixed<T, P: PrefixKind = VarIntLength, const MAX: usize = DEFAULT_MAX_LENGTH> {
+
+    /// The wrapped value
+    pub value: T,
+
+    prefix: PhantomData<P>
+
+}
+
+impl<T, P: PrefixKind, const MAX: usize> Prefixed<T, P, MAX> {
+
+    /// Wraps the given value
+    pub fn new(value: T) -> Prefixed<T, P, MAX> {
+        Prefixed { value, prefix: PhantomData }
+    }
+
+    /// Returns the wrapped value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+}
+
+impl<T, P: PrefixKind, const MAX: usize> From<T> for Prefixed<T, P, MAX> {
+
+    fn from(value: T) -> Prefixed<T, P, MAX> {
+        Prefixed::new(value)
+    }
+
+}
+
+macro_rules! primitive_value {
+    ($($type:ty => $read:ident, $write:ident);* $(;)*) => {
+        $(
+            impl Readable for $type {
+                fn read_from<R: Reader + ?Sized>(reader: &mut R) -> Result<$type> {
+                    reader.$read()
+                }
+            }
+
+            impl Writable for $type {
+                fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+                    writer.$write(*self)
+                }
+            }
+        )*
+    }
+}
+
+primitive_value! {
+    u8 => read_u8, write_u8;
+    i8 => read_i8, write_i8;
+    u16 => read_be_u16, write_be_u16;
+    i16 => read_be_i16, write_be_i16;
+    u32 => read_be_u32, write_be_u32;
+    i32 => read_be_i32, write_be_i32;
+    u64 => read_be_u64, write_be_u64;
+    i64 => read_be_i64, write_be_i64;
+    u128 => read_be_u128, write_be_u128;
+    i128 => read_be_i128, write_be_i128;
+    f32 => read_be_f32, write_be_f32;
+    f64 => read_be_f64, write_be_f64;
+}
+
+impl Readable for bool {
+
+    fn read_from<R: Reader + ?Sized>(reader: &mut R) -> Result<bool> {
+        read_flag(reader, "read_bool")
+    }
+
+}
+
+impl Writable for bool {
+
+    fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u8(*self as u8)
+    }
+
+}
+
+impl Readable for char {
+
+    fn read_from<R: Reader + ?Sized>(reader: &mut R) -> Result<char> {
+
+        let value = reader.read_be_u32()?;
+
+        char::from_u32(value).ok_or(Error::InvalidEncoding {
+            operation: "read_char",
+            offset: 0,
+            reason: "not a Unicode scalar value"
+        })
+
+    }
+
+}
+
+impl Writable for char {
+
+    fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        writer.write_be_u32(*self as u32)
+    }
+
+}
+
+impl<T: Readable> Readable for Option<T> {
+
+    fn read_from<R: Reader + ?Sized>(reader: &mut R) -> Result<Option<T>> {
+
+        match read_flag(reader, "read_option")? {
+            true => T::read_from(reader).map(Some),
+            false => Ok(None)
+        }
+
+    }
+
+}
+
+impl<T: Writable> Writable for Option<T> {
+
+    fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+
+        match *self {
+            Some(ref value) => {
+                writer.write_u8(1)?;
+                value.write_to(writer)
+            },
+            None => writer.write_u8(0)
+        }
+
+    }
+
+}
+
+impl<T: Readable, const N: usize> Readable for [T; N] {
+
+    fn read_from<R: Reader + ?Sized>(reader: &mut R) -> Result<[T; N]> {
+
+        let mut values = Vec::with_capacity(N);
+
+        for _ in 0..N {
+            values.push(T::read_from(reader)?);
+        }
+
+        match values.try_into() {
+            Ok(array) => Ok(array),
+            Err(_) => unreachable!("exactly N values were read")
+        }
+
+    }
+
+}
+
+impl<T: Writable, const N: usize> Writable for [T; N] {
+
+    fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        self.iter().try_for_each(|value| value.write_to(writer))
+    }
+
+}
+
+impl<T: Readable> Readable for Vec<T> {
+
+    fn read_from<R: Reader + ?Sized>(reader: &mut R) -> Result<Vec<T>> {
+        read_vec(reader, LengthPrefix::VarInt, DEFAULT_MAX_LENGTH)
+    }
+
+}
+
+impl<T: Writable> Writable for Vec<T> {
+
+    fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        write_slice(writer, self, LengthPrefix::VarInt, DEFAULT_MAX_LENGTH)
+    }
+
+}
+
+impl<T: Writable> Writable for [T] {
+
+    /// Writes the slice the way a `Vec` is written
+    fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        write_slice(writer, self, LengthPrefix::VarInt, DEFAULT_MAX_LENGTH)
+    }
+
+}
+
+impl Readable for String {
+
+    fn read_from<R: Reader + ?Sized>(reader: &mut R) -> Result<String> {
+        reader.read_string(LengthPrefix::VarInt, DEFAULT_MAX_LENGTH)
+    }
+
+}
+
+impl Writable for String {
+
+    fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        write_str(writer, self, LengthPrefix::VarInt, DEFAULT_MAX_LENGTH)
+    }
+
+}
+
+impl Writable for str {
+
+    /// Writes the string the way a `String` is written
+    fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        write_str(writer, self, LengthPrefix::VarInt, DEFAULT_MAX_LENGTH)
+    }
+
+}
+
+impl<T: Writable + ?Sized> Writable for &T {
+
+    fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        (**self).write_to(writer)
+    }
+
+}
+
+impl<T: Readable, P: PrefixKind, const MAX: usize> Readable for Prefixed<Vec<T>, P, MAX> {
+
+    fn read_from<R: Reader + ?Sized>(reader: &mut R) -> Result<Prefixed<Vec<T>, P, MAX>> {
+        read_vec(reader, P::PREFIX, MAX).map(Prefixed::new)
+    }
+
+}
+
+impl<T: Writable, P: PrefixKind, const MAX: usize> Writable for Prefixed<Vec<T>, P, MAX> {
+
+    fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        write_slice(writer, &self.value, P::PREFIX, MAX)
+    }
+
+}
+
+impl<P: PrefixKind, const MAX: usize> Readable for Prefixed<String, P, MAX> {
+
+    fn read_from<R: Reader + ?Sized>(reader: &mut R) -> Result<Prefixed<String, P, MAX>> {
+        reader.read_string(P::PREFIX, MAX).map(Prefixed::new)
+    }
+
+}
+
+impl<P: PrefixKind, const MAX: usize> Writable for Prefixed<String, P, MAX> {
+
+    fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        write_str(writer, &self.value, P::PREFIX, MAX)
+    }
+
+}
+
+macro_rules! tuple_value {
+    ($(($($name:ident),+)),*) => {
+        $(
+            impl<$($name: Readable),+> Readable for ($($name,)+) {
+                fn read_from<R: Reader + ?Sized>(reader: &mut R) -> Result<($($name,)+)> {
+                    Ok(($($name::read_from(reader)?,)+))
+                }
+            }
+
+            impl<$($name: Writable),+> Writable for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn write_to<W: Writer + ?Sized>(&self, writer: &mut W) -> Result<()> {
+                    let ($(ref $name,)+) = *self;
+                    $($name.write_to(writer)?;)+
+                    Ok(())
+                }
+            }
+        )*
+    }
+}
+
+tuple_value! {
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+    (A, B, C, D, E, F, G),
+    (A, B, C, D, E, F, G, H),
+    (A, B, C, D, E, F, G, H, I),
+    (A, B, C, D, E, F, G, H, I, J),
+    (A, B, C, D, E, F, G, H, I, J, K),
+    (A, B, C, D, E, F, G, H, I, J, K, L)
+}
+
+/// Reads a byte that must be 0 or 1
+fn read_flag<R: Reader + ?Sized>(reader: &mut R, operation: &'static str) -> Result<bool> {
+
+    match reader.read_u8()? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(Error::InvalidEncoding { operation, offset: 0, reason: "expected 0 or 1" })
+    }
+
+}
+
+/// Reads a length-prefixed sequence of values
+///
+/// Space is reserved for at most a few thousand values up front, so that a large
+/// length backed by a short stream fails on the stream rather than on allocation
+pub(crate) fn read_vec<R: Reader + ?Sized, T: Readable>(reader: &mut R, prefix: LengthPrefix, max_length: usize) -> Result<Vec<T>> {
+
+    let length = reader::read_length(reader, prefix, "read_vec")?;
+
+    prefix::check_limit(length, max_length, "read_vec")?;
+
+    let mut values = Vec::with_capacity(length.min(4096));
+
+    for _ in 0..length {
+        values.push(T::read_from(reader)?);
+    }
+
+    Ok(values)
+
+}
+
+/// Writes a length-prefixed sequence of values
+pub(crate) fn write_slice<W: Writer + ?Sized, T: Writable>(
+    writer: &mut W,
+    values: &[T],
+    prefix: LengthPrefix,
+    max_length: usize
+) -> Result<()> {
+
+    prefix::check_limit(values.len(), max_length, "write_vec")?;
+
+    writer.write_length_prefix(prefix, values.len())?;
+
+    values.iter().try_for_each(|value| value.write_to(writer))
+
+}
+
+/// Writes a length-prefixed string, rejecting strings the reader would refuse
+pub(crate) fn write_str<W: Writer + ?Sized>(writer: &mut W, value: &str, prefix: LengthPrefix, max_length: usize) -> Result<()> {
+
+    prefix::check_limit(value.len(), max_length, "write_string")?;
+
+    writer.write_string(value, prefix)
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::prefix::{BeU16Length, U8Length};
+
+    use std::fmt::Debug;
+    use std::io::{Cursor, ErrorKind};
+
+    fn round_trip<T: Readable + Writable + PartialEq + Debug>(value: T, expected: &[u8]) {
+
+        let mut bytes = Vec::new();
+
+        value.write_to(&mut bytes).unwrap();
+
+        assert_eq!(expected, &bytes[..]);
+
+        let mut cursor = Cursor::new(bytes);
+
+        assert_eq!(value, T::read_from(&mut cursor).unwrap());
+        assert_eq!(expected.len() as u64, cursor.position());
+
+    }
+
+    #[test]
+    fn test_primitives() {
+
+        round_trip(0xABu8, &[0xAB]);
+        round_trip(-2i16, &[0xFF, 0xFE]);
+        round_trip(0x01020304u32, &[0x01, 0x02, 0x03, 0x04]);
+        round_trip(1.0f32, &[0x3F, 0x80, 0x00, 0x00]);
+        round_trip(true, &[0x01]);
+        round_trip('é', &[0x00, 0x00, 0x00, 0xE9]);
+
+    }
+
+    #[test]
+    fn test_compound() {
+
+        round_trip(Some(7u16), &[0x01, 0x00, 0x07]);
+        round_trip(None::<u16>, &[0x00]);
+        round_trip([1u8, 2, 3], &[0x01, 0x02, 0x03]);
+        round_trip((1u8, -1i8, false), &[0x01, 0xFF, 0x00]);
+        round_trip(vec![1u16, 2], &[0x02, 0x00, 0x01, 0x00, 0x02]);
+        round_trip(String::from("hi"), &[0x02, b'h', b'i']);
+        round_trip(vec![Some(String::from("a")), None], &[0x02, 0x01, 0x01, b'a', 0x00]);
+
+    }
+
+    #[test]
+    fn test_prefixed() {
+
+        round_trip(Prefixed::<String, BeU16Length>::new(String::from("hi")), &[0x00, 0x02, b'h', b'i']);
+        round_trip(Prefixed::<Vec<u8>, U8Length, 4>::new(vec![9, 8]), &[0x02, 0x09, 0x08]);
+
+        let mut bytes = Vec::new();
+        let value: Prefixed<Vec<u8>, U8Length, 1> = Prefixed::new(vec![1, 2]);
+
+        assert_eq!(ErrorKind::InvalidData, value.write_to(&mut bytes).unwrap_err().kind());
+        assert!(bytes.is_empty());
+
+        let error = Prefixed::<Vec<u8>, U8Length, 1>::read_from(&mut Cursor::new(vec![0x02, 1, 2])).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+
+    }
+
+    #[test]
+    fn test_entry_points() {
+
+        let mut bytes = Vec::new();
+
+        assert!(bytes.write_value(&(5u32, "five")).is_ok());
+        assert!(bytes.write_value("slice").is_ok());
+
+        let mut cursor = Cursor::new(bytes);
+
+        assert_eq!((5, String::from("five")), cursor.read_value::<(u32, String)>().unwrap());
+        assert_eq!("slice", cursor.read_value::<String>().unwrap());
+
+    }
+
+    #[test]
+    fn test_invalid_values() {
+
+        assert!(bool::read_from(&mut Cursor::new(vec![2])).is_err());
+        assert!(Option::<u8>::read_from(&mut Cursor::new(vec![3, 0])).is_err());
+        assert!(char::read_from(&mut Cursor::new(vec![0x00, 0x00, 0xD8, 0x00])).is_err());
+
+        let error = Vec::<u64>::read_from(&mut Cursor::new(vec![0xFF, 0xFF, 0xFF, 0xFF, 0x0F])).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!("read_vec", error.operation());
+
+    }
+
+}
@@ -0,0 +1,299 @@
+//! Variable-length integer encoding
+//!
+//! A VarInt stores an integer seven bits at a time, least significant group first,
+//! with the high bit of each byte set when another byte follows (unsigned LEB128).
+//! Signed values are either stored as their two's complement bit pattern, so negative
+//! numbers always take the maximum length, or ZigZag-encoded first so that values
+//! close to zero stay short whatever their sign (as with protobuf `sint32`/`sint64`).
+
+use crate::error::{Error, Result};
+
+/// The largest number of bytes a 32-bit VarInt may occupy
+pub const MAX_LENGTH_32: usize = 5;
+
+/// The largest number of bytes a 64-bit VarInt may occupy
+pub const MAX_LENGTH_64: usize = 10;
+
+/// Returns the number of bytes an unsigned 32-bit value occupies as a VarInt
+pub fn encoded_len_u32(value: u32) -> usize {
+    encoded_len_u64(value as u64)
+}
+
+/// Returns the number of bytes an unsigned 64-bit value occupies as a VarInt
+pub fn encoded_len_u64(value: u64) -> usize {
+    let bits = 64 - (value | 1).leading_zeros() as usize;
+    bits.div_ceil(7)
+}
+
+/// Returns the number of bytes a signed 32-bit value occupies as a VarInt
+pub fn encoded_len_i32(value: i32) -> usize {
+    encoded_len_u32(value as u32)
+}
+
+/// Returns the number of bytes a signed 64-bit value occupies as a VarInt
+pub fn encoded_len_i64(value: i64) -> usize {
+    encoded_len_u64(value as u64)
+}
+
+/// Returns the number of bytes a signed 32-bit value occupies as a ZigZag VarInt
+pub fn encoded_len_zigzag_i32(value: i32) -> usize {
+    encoded_len_u32(encode_zigzag_i32(value))
+}
+
+/// Returns the number of bytes a signed 64-bit value occupies as a ZigZag VarLong
+pub fn encoded_len_zigzag_i64(value: i64) -> usize {
+    encoded_len_u64(encode_zigzag_i64(value))
+}
+
+/// Maps a signed 32-bit value onto an unsigned one, interleaving negative and positive values
+pub fn encode_zigzag_i32(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+/// Reverses `encode_zigzag_i32`
+pub fn decode_zigzag_i32(value: u32) -> i32 {
+    (value >> 1) as i32 ^ -((value & 1) as i32)
+}
+
+/// Maps a signed 64-bit value onto an unsigned one, interleaving negative and positive values
+pub fn encode_zigzag_i64(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Reverses `encode_zigzag_i64`
+pub fn decode_zigzag_i64(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Encodes a value into the given buffer, returning the number of bytes used
+pub(crate) fn encode(mut value: u64, buffer: &mut [u8; MAX_LENGTH_64]) -> usize {
+
+    let mut length = 0;
+
+    loop {
+
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            buffer[length] = byte;
+            return length + 1;
+        }
+
+        buffer[length] = byte | 0x80;
+        length += 1;
+
+    }
+
+}
+
+/// Decodes a VarInt one byte at a time
+///
+/// Encodings that need more bytes than the value's width allows, whose final byte
+/// carries bits beyond that width, or that end in a redundant zero group are rejected,
+/// so every value has exactly one accepted encoding.
+pub(crate) struct Decoder {
+    bits: u32,
+    value: u64,
+    length: usize
+}
+
+impl Decoder {
+
+    /// Creates a decoder for a value of the given width in bits (32 or 64)
+    pub(crate) fn new(bits: u32) -> Decoder {
+        Decoder { bits, value: 0, length: 0 }
+    }
+
+    /// Returns the number of bytes consumed so far
+    pub(crate) fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Feeds the next byte, returning the value once the last byte has been seen
+    pub(crate) fn push(&mut self, byte: u8, operation: &'static str) -> Result<Option<u64>> {
+
+        let shift = 7 * self.length as u32;
+        let last = self.length + 1 == (self.bits as usize).div_ceil(7);
+
+        if last {
+
+            if byte & 0x80 != 0 {
+                return Err(Error::InvalidEncoding {
+                    operation,
+                    offset: self.length as u64,
+                    reason: if self.bits == 32 {
+                        "VarInt is longer than 5 bytes"
+                    } else {
+                        "VarLong is longer than 10 bytes"
+                    }
+                });
+            }
+
+            if (byte as u64) >> (self.bits - shift) != 0 {
+                return Err(Error::OutOfRange {
+                    operation,
+                    offset: self.length as u64,
+                    reason: if self.bits == 32 {
+                        "VarInt overflows 32 bits"
+                    } else {
+                        "VarLong overflows 64 bits"
+                    }
+                });
+            }
+
+        }
+
+        if byte == 0 && self.length > 0 {
+            return Err(Error::InvalidEncoding {
+                operation,
+                offset: self.length as u64,
+                reason: if self.bits == 32 { "non-minimal VarInt" } else { "non-minimal VarLong" }
+            });
+        }
+
+        self.value |= ((byte & 0x7F) as u64) << shift;
+        self.length += 1;
+
+        if byte & 0x80 == 0 {
+            Ok(Some(self.value))
+        } else {
+            Ok(None)
+        }
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn encoded(value: u64) -> Vec<u8> {
+        let mut buffer = [0u8; MAX_LENGTH_64];
+        let length = encode(value, &mut buffer);
+        buffer[..length].to_vec()
+    }
+
+    fn decoded(bits: u32, bytes: &[u8]) -> Result<Option<u64>> {
+        let mut decoder = Decoder::new(bits);
+        let mut value = None;
+        for byte in bytes {
+            value = decoder.push(*byte, "decode")?;
+        }
+        Ok(value)
+    }
+
+    #[test]
+    fn test_encode() {
+
+        assert_eq!(vec![0x00], encoded(0));
+        assert_eq!(vec![0x7F], encoded(127));
+        assert_eq!(vec![0x80, 0x01], encoded(128));
+        assert_eq!(vec![0xDD, 0xC7, 0x01], encoded(25565));
+        assert_eq!(vec![0xFF, 0xFF, 0xFF, 0xFF, 0x0F], encoded(-1i32 as u32 as u64));
+        assert_eq!(vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01], encoded(u64::MAX));
+
+    }
+
+    #[test]
+    fn test_encoded_len() {
+
+        assert_eq!(1, encoded_len_u32(0));
+        assert_eq!(1, encoded_len_u32(127));
+        assert_eq!(2, encoded_len_u32(128));
+        assert_eq!(3, encoded_len_u32(2097151));
+        assert_eq!(5, encoded_len_u32(u32::MAX));
+        assert_eq!(5, encoded_len_i32(-1));
+        assert_eq!(5, encoded_len_i32(i32::MAX));
+        assert_eq!(10, encoded_len_i64(-1));
+        assert_eq!(9, encoded_len_i64(i64::MAX));
+
+        for value in [0, 1, 300, 1 << 35, u64::MAX].iter() {
+            assert_eq!(encoded(*value).len(), encoded_len_u64(*value));
+        }
+
+    }
+
+    #[test]
+    fn test_zigzag() {
+
+        assert_eq!(0, encode_zigzag_i32(0));
+        assert_eq!(1, encode_zigzag_i32(-1));
+        assert_eq!(2, encode_zigzag_i32(1));
+        assert_eq!(u32::MAX - 1, encode_zigzag_i32(i32::MAX));
+        assert_eq!(u32::MAX, encode_zigzag_i32(i32::MIN));
+        assert_eq!(u64::MAX, encode_zigzag_i64(i64::MIN));
+
+        for value in [0, -1, 1, i32::MIN, i32::MAX].iter() {
+            assert_eq!(*value, decode_zigzag_i32(encode_zigzag_i32(*value)));
+        }
+
+        for value in [0, -1, 1, i64::MIN, i64::MAX].iter() {
+            assert_eq!(*value, decode_zigzag_i64(encode_zigzag_i64(*value)));
+        }
+
+        assert_eq!(1, encoded_len_zigzag_i32(-64));
+        assert_eq!(5, encoded_len_zigzag_i32(i32::MIN));
+        assert_eq!(10, encoded_len_zigzag_i64(i64::MIN));
+
+    }
+
+    #[test]
+    fn test_decode() {
+
+        assert_eq!(Some(25565), decoded(32, &[0xDD, 0xC7, 0x01]).unwrap());
+        assert_eq!(Some(u32::MAX as u64), decoded(32, &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]).unwrap());
+        assert_eq!(Some(u64::MAX), decoded(64, &encoded(u64::MAX)).unwrap());
+        assert_eq!(None, decoded(32, &[0x80, 0x80]).unwrap());
+
+    }
+
+    #[test]
+    fn test_decode_rejects_redundant_zero_groups() {
+
+        let error = decoded(32, &[0x81, 0x80, 0x00]).unwrap_err();
+
+        match error {
+            Error::InvalidEncoding { offset, reason, .. } => {
+                assert_eq!(2, offset);
+                assert_eq!("non-minimal VarInt", reason);
+            },
+            _ => panic!("unexpected error {:?}", error)
+        }
+
+        assert!(decoded(64, &[0x80, 0x00]).is_err());
+        assert_eq!(Some(0), decoded(32, &[0x00]).unwrap());
+
+    }
+
+    #[test]
+    fn test_decode_rejects_too_long() {
+
+        let error = decoded(32, &[0x80, 0x80, 0x80, 0x80, 0x80]).unwrap_err();
+
+        match error {
+            Error::InvalidEncoding { offset, .. } => assert_eq!(4, offset),
+            _ => panic!("unexpected error {:?}", error)
+        }
+
+        assert!(decoded(64, &[0x80; 10]).is_err());
+
+    }
+
+    #[test]
+    fn test_decode_rejects_overflow() {
+
+        let error = decoded(32, &[0xFF, 0xFF, 0xFF, 0xFF, 0x1F]).unwrap_err();
+
+        match error {
+            Error::OutOfRange { offset, .. } => assert_eq!(4, offset),
+            _ => panic!("unexpected error {:?}", error)
+        }
+
+        assert!(decoded(64, &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x03]).is_err());
+
+    }
+
+}
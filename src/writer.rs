@@ -1,223 +1,511 @@
 //! Writing I/O operations
 
 use std::io::Write;
-use std::io::Result;
+
+use crate::bulk::Scalar;
+use crate::codec::{self, Encoded};
+use crate::endian::{BigEndian, ByteOrder, Endian, LittleEndian};
+use crate::error::Result;
+use crate::prefix::LengthPrefix;
+use crate::text::TextEncoding;
+use crate::value::Writable;
+use crate::varint;
 
 /// Extends the Write trait to provide common I/O writer operations
+///
+/// Multi-byte numbers can be written in a byte order picked at compile time, as in
+/// `write_u32::<BigEndian>(value)`, in one picked at runtime, as in
+/// `write_u32_in(value, endian)`, or with the `write_be_*` and `write_le_*` shorthands.
+/// The `write_*_slice` methods write a whole slice with a single write
 pub trait Writer : Write {
 
     /// Writes an unsigned byte to this Writer
     fn write_u8(&mut self, value: u8) -> Result<()> {
-
-        let raw_buffer = vec![value];
-
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
-
-        self.write_all(raw_buffer)
-
+        write_buffer(self, &[value], "write_u8")
     }
 
     /// Writes a signed byte to this Writer
     fn write_i8(&mut self, value: i8) -> Result<()> {
+        write_buffer(self, &[value as u8], "write_i8")
+    }
 
-        let raw_buffer = vec![value as u8];
-
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
-
-        self.write_all(raw_buffer)
+    /// Writes an unsigned short in the given byte order to this Writer
+    fn write_u16_in(&mut self, value: u16, endian: Endian) -> Result<()> {
+        write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_u16", "write_le_u16"))
+    }
 
+    /// Writes an unsigned short in the byte order `E` to this Writer
+    fn write_u16<E: ByteOrder>(&mut self, value: u16) -> Result<()> {
+        self.write_u16_in(value, E::ENDIAN)
     }
 
     /// Writes an unsigned little-endian short to this Writer
     fn write_le_u16(&mut self, value: u16) -> Result<()> {
-        let raw_buffer = vec![
-            value as u8,
-            (value >> 8) as u8
-        ];
-
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
-
-        self.write_all(raw_buffer)
+        self.write_u16::<LittleEndian>(value)
     }
 
     /// Writes an unsigned big-endian short to this Writer
     fn write_be_u16(&mut self, value: u16) -> Result<()> {
-        let raw_buffer = vec![
-            (value >> 8) as u8,
-            value as u8
-        ];
+        self.write_u16::<BigEndian>(value)
+    }
 
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
+    /// Writes a signed short in the given byte order to this Writer
+    fn write_i16_in(&mut self, value: i16, endian: Endian) -> Result<()> {
+        write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_i16", "write_le_i16"))
+    }
 
-        self.write_all(raw_buffer)
+    /// Writes a signed short in the byte order `E` to this Writer
+    fn write_i16<E: ByteOrder>(&mut self, value: i16) -> Result<()> {
+        self.write_i16_in(value, E::ENDIAN)
     }
 
     /// Writes a signed little-endian short to this Writer
     fn write_le_i16(&mut self, value: i16) -> Result<()> {
-        let raw_buffer = vec![
-            value as u8,
-            (value >> 8) as u8
-        ];
-
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
-
-        self.write_all(raw_buffer)
+        self.write_i16::<LittleEndian>(value)
     }
 
     /// Writes a signed big-endian short to this Writer
     fn write_be_i16(&mut self, value: i16) -> Result<()> {
-        let raw_buffer = vec![
-            (value >> 8) as u8,
-            value as u8
-        ];
+        self.write_i16::<BigEndian>(value)
+    }
 
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
+    /// Writes an unsigned integer in the given byte order to this Writer
+    fn write_u32_in(&mut self, value: u32, endian: Endian) -> Result<()> {
+        write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_u32", "write_le_u32"))
+    }
 
-        self.write_all(raw_buffer)
+    /// Writes an unsigned integer in the byte order `E` to this Writer
+    fn write_u32<E: ByteOrder>(&mut self, value: u32) -> Result<()> {
+        self.write_u32_in(value, E::ENDIAN)
     }
 
     /// Writes an unsigned little-endian integer to this Writer
     fn write_le_u32(&mut self, value: u32) -> Result<()> {
-        let raw_buffer = vec![
-            value as u8,
-            (value >> 8) as u8,
-            (value >> 16) as u8,
-            (value >> 24) as u8
-        ];
-
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
-
-        self.write_all(raw_buffer)
+        self.write_u32::<LittleEndian>(value)
     }
 
     /// Writes an unsigned big-endian integer to this Writer
     fn write_be_u32(&mut self, value: u32) -> Result<()> {
-        let raw_buffer = vec![
-            (value >> 24) as u8,
-            (value >> 16) as u8,
-            (value >> 8) as u8,
-            value as u8
-        ];
+        self.write_u32::<BigEndian>(value)
+    }
 
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
+    /// Writes a signed integer in the given byte order to this Writer
+    fn write_i32_in(&mut self, value: i32, endian: Endian) -> Result<()> {
+        write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_i32", "write_le_i32"))
+    }
 
-        self.write_all(raw_buffer)
+    /// Writes a signed integer in the byte order `E` to this Writer
+    fn write_i32<E: ByteOrder>(&mut self, value: i32) -> Result<()> {
+        self.write_i32_in(value, E::ENDIAN)
     }
 
     /// Writes a signed little-endian integer to this Writer
     fn write_le_i32(&mut self, value: i32) -> Result<()> {
-        let raw_buffer = vec![
-            value as u8,
-            (value >> 8) as u8,
-            (value >> 16) as u8,
-            (value >> 24) as u8
-        ];
-
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
-
-        self.write_all(raw_buffer)
+        self.write_i32::<LittleEndian>(value)
     }
 
     /// Writes a signed big-endian integer to this Writer
     fn write_be_i32(&mut self, value: i32) -> Result<()> {
-        let raw_buffer = vec![
-            (value >> 24) as u8,
-            (value >> 16) as u8,
-            (value >> 8) as u8,
-            value as u8
-        ];
+        self.write_i32::<BigEndian>(value)
+    }
 
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
+    /// Writes an unsigned long in the given byte order to this Writer
+    fn write_u64_in(&mut self, value: u64, endian: Endian) -> Result<()> {
+        write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_u64", "write_le_u64"))
+    }
 
-        self.write_all(raw_buffer)
+    /// Writes an unsigned long in the byte order `E` to this Writer
+    fn write_u64<E: ByteOrder>(&mut self, value: u64) -> Result<()> {
+        self.write_u64_in(value, E::ENDIAN)
     }
 
     /// Writes an unsigned little-endian long to this Writer
     fn write_le_u64(&mut self, value: u64) -> Result<()> {
-        let raw_buffer = vec![
-            value as u8,
-            (value >> 8) as u8,
-            (value >> 16) as u8,
-            (value >> 24) as u8,
-            (value >> 32) as u8,
-            (value >> 40) as u8,
-            (value >> 48) as u8,
-            (value >> 56) as u8
-        ];
+        self.write_u64::<LittleEndian>(value)
+    }
 
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
+    /// Writes an unsigned big-endian long to this Writer
+    fn write_be_u64(&mut self, value: u64) -> Result<()> {
+        self.write_u64::<BigEndian>(value)
+    }
 
-        self.write_all(raw_buffer)
+    /// Writes a signed long in the given byte order to this Writer
+    fn write_i64_in(&mut self, value: i64, endian: Endian) -> Result<()> {
+        write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_i64", "write_le_i64"))
+    }
+
+    /// Writes a signed long in the byte order `E` to this Writer
+    fn write_i64<E: ByteOrder>(&mut self, value: i64) -> Result<()> {
+        self.write_i64_in(value, E::ENDIAN)
     }
 
     /// Writes a signed little-endian long to this Writer
     fn write_le_i64(&mut self, value: i64) -> Result<()> {
-        let raw_buffer = vec![
-            value as u8,
-            (value >> 8) as u8,
-            (value >> 16) as u8,
-            (value >> 24) as u8,
-            (value >> 32) as u8,
-            (value >> 40) as u8,
-            (value >> 48) as u8,
-            (value >> 56) as u8
-        ];
+        self.write_i64::<LittleEndian>(value)
+    }
 
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
+    /// Writes a signed big-endian long to this Writer
+    fn write_be_i64(&mut self, value: i64) -> Result<()> {
+        self.write_i64::<BigEndian>(value)
+    }
 
-        self.write_all(raw_buffer)
+    /// Writes an unsigned 128-bit integer in the given byte order to this Writer
+    fn write_u128_in(&mut self, value: u128, endian: Endian) -> Result<()> {
+        write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_u128", "write_le_u128"))
     }
 
-    /// Writes an unsigned big-endian long to this Writer
-    fn write_be_u64(&mut self, value: u64) -> Result<()> {
-        let raw_buffer = vec![
-            (value >> 56) as u8,
-            (value >> 48) as u8,
-            (value >> 40) as u8,
-            (value >> 32) as u8,
-            (value >> 24) as u8,
-            (value >> 16) as u8,
-            (value >> 8) as u8,
-            value as u8
-        ];
+    /// Writes an unsigned 128-bit integer in the byte order `E` to this Writer
+    fn write_u128<E: ByteOrder>(&mut self, value: u128) -> Result<()> {
+        self.write_u128_in(value, E::ENDIAN)
+    }
 
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
+    /// Writes an unsigned little-endian 128-bit integer to this Writer
+    fn write_le_u128(&mut self, value: u128) -> Result<()> {
+        self.write_u128::<LittleEndian>(value)
+    }
 
-        self.write_all(raw_buffer)
+    /// Writes an unsigned big-endian 128-bit integer to this Writer
+    fn write_be_u128(&mut self, value: u128) -> Result<()> {
+        self.write_u128::<BigEndian>(value)
     }
 
-    /// Writes a signed big-endian long to this Writer
-    fn write_be_i64(&mut self, value: i64) -> Result<()> {
-        let raw_buffer = vec![
-            (value >> 56) as u8,
-            (value >> 48) as u8,
-            (value >> 40) as u8,
-            (value >> 32) as u8,
-            (value >> 24) as u8,
-            (value >> 16) as u8,
-            (value >> 8) as u8,
-            value as u8
-        ];
+    /// Writes a signed 128-bit integer in the given byte order to this Writer
+    fn write_i128_in(&mut self, value: i128, endian: Endian) -> Result<()> {
+        write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_i128", "write_le_i128"))
+    }
+
+    /// Writes a signed 128-bit integer in the byte order `E` to this Writer
+    fn write_i128<E: ByteOrder>(&mut self, value: i128) -> Result<()> {
+        self.write_i128_in(value, E::ENDIAN)
+    }
+
+    /// Writes a signed little-endian 128-bit integer to this Writer
+    fn write_le_i128(&mut self, value: i128) -> Result<()> {
+        self.write_i128::<LittleEndian>(value)
+    }
+
+    /// Writes a signed big-endian 128-bit integer to this Writer
+    fn write_be_i128(&mut self, value: i128) -> Result<()> {
+        self.write_i128::<BigEndian>(value)
+    }
+
+    /// Writes a single-precision float in the given byte order to this Writer
+    fn write_f32_in(&mut self, value: f32, endian: Endian) -> Result<()> {
+        write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_f32", "write_le_f32"))
+    }
+
+    /// Writes a single-precision float in the byte order `E` to this Writer
+    fn write_f32<E: ByteOrder>(&mut self, value: f32) -> Result<()> {
+        self.write_f32_in(value, E::ENDIAN)
+    }
+
+    /// Writes a little-endian single-precision float to this Writer
+    fn write_le_f32(&mut self, value: f32) -> Result<()> {
+        self.write_f32::<LittleEndian>(value)
+    }
+
+    /// Writes a big-endian single-precision float to this Writer
+    fn write_be_f32(&mut self, value: f32) -> Result<()> {
+        self.write_f32::<BigEndian>(value)
+    }
+
+    /// Writes a double-precision float in the given byte order to this Writer
+    fn write_f64_in(&mut self, value: f64, endian: Endian) -> Result<()> {
+        write_buffer(self, value.to_bytes(endian).as_ref(), endian.select("write_be_f64", "write_le_f64"))
+    }
+
+    /// Writes a double-precision float in the byte order `E` to this Writer
+    fn write_f64<E: ByteOrder>(&mut self, value: f64) -> Result<()> {
+        self.write_f64_in(value, E::ENDIAN)
+    }
+
+    /// Writes a little-endian double-precision float to this Writer
+    fn write_le_f64(&mut self, value: f64) -> Result<()> {
+        self.write_f64::<LittleEndian>(value)
+    }
+
+    /// Writes a big-endian double-precision float to this Writer
+    fn write_be_f64(&mut self, value: f64) -> Result<()> {
+        self.write_f64::<BigEndian>(value)
+    }
+
+    /// Writes a slice of unsigned shorts in the byte order `E` to this Writer
+    fn write_u16_slice<E: ByteOrder>(&mut self, values: &[u16]) -> Result<()> {
+        write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_u16_slice", "write_le_u16_slice")))
+    }
+
+    /// Writes a slice of little-endian unsigned shorts to this Writer
+    fn write_le_u16_slice(&mut self, values: &[u16]) -> Result<()> {
+        self.write_u16_slice::<LittleEndian>(values)
+    }
+
+    /// Writes a slice of big-endian unsigned shorts to this Writer
+    fn write_be_u16_slice(&mut self, values: &[u16]) -> Result<()> {
+        self.write_u16_slice::<BigEndian>(values)
+    }
+
+    /// Writes a slice of signed shorts in the byte order `E` to this Writer
+    fn write_i16_slice<E: ByteOrder>(&mut self, values: &[i16]) -> Result<()> {
+        write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_i16_slice", "write_le_i16_slice")))
+    }
+
+    /// Writes a slice of little-endian signed shorts to this Writer
+    fn write_le_i16_slice(&mut self, values: &[i16]) -> Result<()> {
+        self.write_i16_slice::<LittleEndian>(values)
+    }
+
+    /// Writes a slice of big-endian signed shorts to this Writer
+    fn write_be_i16_slice(&mut self, values: &[i16]) -> Result<()> {
+        self.write_i16_slice::<BigEndian>(values)
+    }
+
+    /// Writes a slice of unsigned integers in the byte order `E` to this Writer
+    fn write_u32_slice<E: ByteOrder>(&mut self, values: &[u32]) -> Result<()> {
+        write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_u32_slice", "write_le_u32_slice")))
+    }
+
+    /// Writes a slice of little-endian unsigned integers to this Writer
+    fn write_le_u32_slice(&mut self, values: &[u32]) -> Result<()> {
+        self.write_u32_slice::<LittleEndian>(values)
+    }
+
+    /// Writes a slice of big-endian unsigned integers to this Writer
+    fn write_be_u32_slice(&mut self, values: &[u32]) -> Result<()> {
+        self.write_u32_slice::<BigEndian>(values)
+    }
+
+    /// Writes a slice of signed integers in the byte order `E` to this Writer
+    fn write_i32_slice<E: ByteOrder>(&mut self, values: &[i32]) -> Result<()> {
+        write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_i32_slice", "write_le_i32_slice")))
+    }
+
+    /// Writes a slice of little-endian signed integers to this Writer
+    fn write_le_i32_slice(&mut self, values: &[i32]) -> Result<()> {
+        self.write_i32_slice::<LittleEndian>(values)
+    }
+
+    /// Writes a slice of big-endian signed integers to this Writer
+    fn write_be_i32_slice(&mut self, values: &[i32]) -> Result<()> {
+        self.write_i32_slice::<BigEndian>(values)
+    }
+
+    /// Writes a slice of unsigned longs in the byte order `E` to this Writer
+    fn write_u64_slice<E: ByteOrder>(&mut self, values: &[u64]) -> Result<()> {
+        write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_u64_slice", "write_le_u64_slice")))
+    }
+
+    /// Writes a slice of little-endian unsigned longs to this Writer
+    fn write_le_u64_slice(&mut self, values: &[u64]) -> Result<()> {
+        self.write_u64_slice::<LittleEndian>(values)
+    }
+
+    /// Writes a slice of big-endian unsigned longs to this Writer
+    fn write_be_u64_slice(&mut self, values: &[u64]) -> Result<()> {
+        self.write_u64_slice::<BigEndian>(values)
+    }
+
+    /// Writes a slice of signed longs in the byte order `E` to this Writer
+    fn write_i64_slice<E: ByteOrder>(&mut self, values: &[i64]) -> Result<()> {
+        write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_i64_slice", "write_le_i64_slice")))
+    }
+
+    /// Writes a slice of little-endian signed longs to this Writer
+    fn write_le_i64_slice(&mut self, values: &[i64]) -> Result<()> {
+        self.write_i64_slice::<LittleEndian>(values)
+    }
+
+    /// Writes a slice of big-endian signed longs to this Writer
+    fn write_be_i64_slice(&mut self, values: &[i64]) -> Result<()> {
+        self.write_i64_slice::<BigEndian>(values)
+    }
+
+    /// Writes a slice of unsigned 128-bit integers in the byte order `E` to this Writer
+    fn write_u128_slice<E: ByteOrder>(&mut self, values: &[u128]) -> Result<()> {
+        write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_u128_slice", "write_le_u128_slice")))
+    }
+
+    /// Writes a slice of little-endian unsigned 128-bit integers to this Writer
+    fn write_le_u128_slice(&mut self, values: &[u128]) -> Result<()> {
+        self.write_u128_slice::<LittleEndian>(values)
+    }
 
-        // Reassign to a buffer of raw u8s
-        let raw_buffer: &[u8] = &raw_buffer[..];
+    /// Writes a slice of big-endian unsigned 128-bit integers to this Writer
+    fn write_be_u128_slice(&mut self, values: &[u128]) -> Result<()> {
+        self.write_u128_slice::<BigEndian>(values)
+    }
+
+    /// Writes a slice of signed 128-bit integers in the byte order `E` to this Writer
+    fn write_i128_slice<E: ByteOrder>(&mut self, values: &[i128]) -> Result<()> {
+        write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_i128_slice", "write_le_i128_slice")))
+    }
+
+    /// Writes a slice of little-endian signed 128-bit integers to this Writer
+    fn write_le_i128_slice(&mut self, values: &[i128]) -> Result<()> {
+        self.write_i128_slice::<LittleEndian>(values)
+    }
+
+    /// Writes a slice of big-endian signed 128-bit integers to this Writer
+    fn write_be_i128_slice(&mut self, values: &[i128]) -> Result<()> {
+        self.write_i128_slice::<BigEndian>(values)
+    }
+
+    /// Writes a slice of single-precision floats in the byte order `E` to this Writer
+    fn write_f32_slice<E: ByteOrder>(&mut self, values: &[f32]) -> Result<()> {
+        write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_f32_slice", "write_le_f32_slice")))
+    }
+
+    /// Writes a slice of little-endian single-precision floats to this Writer
+    fn write_le_f32_slice(&mut self, values: &[f32]) -> Result<()> {
+        self.write_f32_slice::<LittleEndian>(values)
+    }
+
+    /// Writes a slice of big-endian single-precision floats to this Writer
+    fn write_be_f32_slice(&mut self, values: &[f32]) -> Result<()> {
+        self.write_f32_slice::<BigEndian>(values)
+    }
+
+    /// Writes a slice of double-precision floats in the byte order `E` to this Writer
+    fn write_f64_slice<E: ByteOrder>(&mut self, values: &[f64]) -> Result<()> {
+        write_encoded(self, &Encoded::primitives(values, E::ENDIAN, E::ENDIAN.select("write_be_f64_slice", "write_le_f64_slice")))
+    }
+
+    /// Writes a slice of little-endian double-precision floats to this Writer
+    fn write_le_f64_slice(&mut self, values: &[f64]) -> Result<()> {
+        self.write_f64_slice::<LittleEndian>(values)
+    }
+
+    /// Writes a slice of big-endian double-precision floats to this Writer
+    fn write_be_f64_slice(&mut self, values: &[f64]) -> Result<()> {
+        self.write_f64_slice::<BigEndian>(values)
+    }
+
+    /// Writes an unsigned 32-bit VarInt to this Writer
+    fn write_varint_u32(&mut self, value: u32) -> Result<()> {
+        write_encoded(self, &Encoded::varint(value as u64, "write_varint_u32"))
+    }
 
-        self.write_all(raw_buffer)
+    /// Writes a signed 32-bit VarInt, stored in two's complement, to this Writer
+    fn write_varint_i32(&mut self, value: i32) -> Result<()> {
+        write_encoded(self, &Encoded::varint(value as u32 as u64, "write_varint_i32"))
+    }
+
+    /// Writes an unsigned 64-bit VarLong to this Writer
+    fn write_varint_u64(&mut self, value: u64) -> Result<()> {
+        write_encoded(self, &Encoded::varint(value, "write_varint_u64"))
+    }
+
+    /// Writes a signed 64-bit VarLong, stored in two's complement, to this Writer
+    fn write_varint_i64(&mut self, value: i64) -> Result<()> {
+        write_encoded(self, &Encoded::varint(value as u64, "write_varint_i64"))
+    }
+
+    /// Writes a signed 32-bit ZigZag-encoded VarInt to this Writer
+    fn write_zigzag_i32(&mut self, value: i32) -> Result<()> {
+        write_encoded(self, &Encoded::varint(varint::encode_zigzag_i32(value) as u64, "write_zigzag_i32"))
+    }
+
+    /// Writes a signed 64-bit ZigZag-encoded VarLong to this Writer
+    fn write_zigzag_i64(&mut self, value: i64) -> Result<()> {
+        write_encoded(self, &Encoded::varint(varint::encode_zigzag_i64(value), "write_zigzag_i64"))
+    }
+
+    /// Writes a length prefix of the given type to this Writer
+    fn write_length_prefix(&mut self, prefix: LengthPrefix, length: usize) -> Result<()> {
+        write_encoded(self, &Encoded::length(prefix, length, "write_length_prefix")?)
+    }
+
+    /// Writes a length-prefixed UTF-8 string to this Writer
+    ///
+    /// Strings longer than the prefix can describe are rejected before anything is written
+    fn write_string(&mut self, value: &str, prefix: LengthPrefix) -> Result<()> {
+        write_encoded(self, &codec::write_string(value, prefix)?)
+    }
+
+    /// Writes a string the way Java's `DataOutput.writeUTF` does to this Writer
+    ///
+    /// That is a big-endian u16 byte length followed by modified UTF-8. Strings whose
+    /// encoding is longer than 65535 bytes are rejected before anything is written
+    fn write_java_utf(&mut self, value: &str) -> Result<()> {
+        write_encoded(self, &codec::write_java_utf(value)?)
+    }
+
+    /// Writes a UTF-16 string prefixed with its length in code units to this Writer
+    ///
+    /// When `bom` is set, a byte order mark is written first and counted in the length
+    fn write_utf16(&mut self, value: &str, prefix: LengthPrefix, endian: Endian, bom: bool) -> Result<()> {
+        write_encoded(self, &codec::write_utf16(value, prefix, endian, bom)?)
+    }
+
+    /// Writes a UTF-16 string terminated by a NUL code unit to this Writer
+    ///
+    /// When `bom` is set, a byte order mark is written first. Strings that contain NUL
+    /// are rejected, since they could not be read back
+    fn write_utf16_nul(&mut self, value: &str, endian: Endian, bom: bool) -> Result<()> {
+        write_encoded(self, &codec::write_utf16_nul(value, endian, bom)?)
+    }
+
+    /// Writes a NUL-terminated string to this Writer
+    ///
+    /// Strings that contain NUL, or characters the encoding cannot represent, are
+    /// rejected before anything is written
+    fn write_cstring(&mut self, value: &str, encoding: TextEncoding) -> Result<()> {
+        write_encoded(self, &codec::write_cstring(value, encoding)?)
+    }
+
+    /// Writes a string into a fixed-width field of this Writer, padded with `pad_byte`
+    ///
+    /// Values whose encoding is longer than `width` are rejected rather than truncated.
+    /// Trailing `pad_byte`s in the value itself cannot be told apart from padding when
+    /// the field is read back
+    fn write_fixed_str(&mut self, value: &str, width: usize, pad_byte: u8, encoding: TextEncoding) -> Result<()> {
+        write_encoded(self, &codec::write_fixed_str(value, width, pad_byte, encoding)?)
+    }
+
+    /// Writes a value of any Writable type to this Writer
+    ///
+    /// Named `write_value` rather than `write` so that it cannot be confused with `Write::write`
+    fn write_value<T: Writable + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.write_to(self)
     }
 
 }
 
 impl<T> Writer for T where T: Write { }
+
+/// Writes the whole buffer to the given writer
+///
+/// Short writes are retried until everything is written, as are writes that fail with
+/// `ErrorKind::Interrupted`. Errors report how many bytes were written before the failure
+pub(crate) fn write_buffer<W: Write + ?Sized>(writer: &mut W, buffer: &[u8], operation: &'static str) -> Result<()> {
+
+    let mut count = 0;
+
+    while count < buffer.len() {
+        let result = writer.write(&buffer[count..]);
+        codec::record_write(result, &mut count, operation)?;
+    }
+
+    Ok(())
+
+}
+
+/// Writes an encoded header and body to the given writer, skipping whichever is empty
+fn write_encoded<W: Write + ?Sized>(writer: &mut W, encoded: &Encoded) -> Result<()> {
+
+    if !encoded.header().is_empty() {
+        write_buffer(writer, encoded.header(), encoded.operation())?;
+    }
+
+    if encoded.body().is_empty() {
+        return Ok(());
+    }
+
+    write_buffer(writer, encoded.body(), encoded.operation())
+        .map_err(|error| error.with_base_offset(encoded.header().len() as u64))
+
+}
+
+/// Writes a length prefix of the given type, rejecting lengths the prefix cannot hold
+pub(crate) fn write_length<W: Write + ?Sized>(writer: &mut W, prefix: LengthPrefix, length: usize, operation: &'static str) -> Result<()> {
+    write_encoded(writer, &Encoded::length(prefix, length, operation)?)
+}